@@ -1,9 +1,31 @@
+const int MAX_MASKS = 8;
+
 in vec4 v_color;
-in highp float mask_dist;
+in vec2 v_screen;
+in vec2 v_uv;
+flat in int v_mask_count;
+flat in vec4 v_masks[MAX_MASKS];
 out vec4 out_color;
+
+// the width in pixels, 0 for no outlines
+uniform float u_outline_width;
+uniform vec4 u_outline_color;
+
 void main() {
-  if (mask_dist < 0.0) {
-    discard;
+  // how many pixels away from the nearest edge
+  vec2 edges = min(v_uv, 1.0 - v_uv) / fwidth(v_uv);
+  // every mask cuts off the left side of its line
+  for (int i = 0; i < v_mask_count; i++) {
+    vec2 p = v_masks[i].xy;
+    vec2 d = v_masks[i].zw;
+    if ((v_screen.x * d.y - v_screen.y * d.x) - (p.x * d.y - p.y * d.x) < 0.0) {
+      discard;
+    }
   }
   out_color = v_color;
+  if (min(edges.x, edges.y) < u_outline_width) {
+    // every point is covered twice
+    out_color = u_outline_color;
+    out_color.a /= 2.0 - out_color.a;
+  }
 }
\ No newline at end of file
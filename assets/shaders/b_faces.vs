@@ -1,27 +1,67 @@
 const highp float zRatio = 0.01;
 const highp float zoffset = 0.0001;
-const mat3x3 pj = mat3x3(0.866025, -0.5, zRatio,  // x -> x'
-                         0.0, 1.0, zRatio,        // y -> y'
-                         -0.866025, -0.5, zRatio  // z -> z'
-);
+
+const int MAX_MASKS = 8;
+
+in vec3 a_pos;
+in vec4 a_color;
+in float a_index;
+// the position of the face in the list
+in float a_id;
+// where the masks of the face start in u_masks and how many they are
+in vec2 a_masks;
+// the normal of the face, zero if it is not lit
+in vec3 a_normal;
+// (0, 0) to (1, 1) from the first to the last corner, for the outlines
+in vec2 a_uv;
 
 out vec4 v_color;
-out highp float mask_dist;
+out vec2 v_screen;
+out vec2 v_uv;
+flat out int v_mask_count;
+// xy: the projected position, zw: the projected direction
+flat out vec4 v_masks[MAX_MASKS];
 
 uniform mat3 u_proj;
+// the projection of the mode, the last row is the depth
+uniform mat3 u_pj;
+// added to the points before they are turned, moving the camera
+uniform vec3 u_translate;
 uniform float u_aspect_ratio;
-uniform float u_index;
 
-uniform vec4 u_color[4];
-uniform vec3 u_pos[4];
-uniform bool u_use_mask;
-uniform vec3 u_mask_pos;
-uniform vec3 u_mask_dir;
+uniform bool u_mask_enabled;
+// every mask takes two texels: (pos, screen) and (dir, 0)
+uniform highp sampler2D u_masks;
+// the id of the highlighted face, -1 for none
+uniform float u_highlighted;
+// how much the highlighted face is mixed with white
+uniform float u_highlight;
+// where the light comes from after the turning, zero for no light
+uniform vec3 u_light;
+// the brightness of a face turned away from the light
+uniform float u_ambient;
+
+vec4 mask_texel(int i) {
+  int width = textureSize(u_masks, 0).x;
+  return texelFetch(u_masks, ivec2(i % width, i / width), 0);
+}
 
 void main() {
-  mat3 view = pj * u_proj;
-  v_color = u_color[gl_VertexID % 4];
-  gl_Position = vec4(view * (u_pos[gl_VertexID % 4]), 1.0);
+  mat3 view = mat3(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u_pj * u_proj;
+  v_color = a_color;
+  if (a_normal != vec3(0.0) && u_light != vec3(0.0)) {
+    vec3 n = normalize(u_proj * a_normal);
+    // the side facing the viewer, the depth grows towards it
+    if (dot(n, vec3(u_pj[0][2], u_pj[1][2], u_pj[2][2])) < 0.0) {
+      n = -n;
+    }
+    v_color.rgb *= u_ambient + (1.0 - u_ambient) * max(dot(n, normalize(u_light)), 0.0);
+  }
+  v_uv = a_uv;
+  if (a_id == u_highlighted) {
+    v_color.rgb = mix(v_color.rgb, vec3(1.0), u_highlight);
+  }
+  gl_Position = vec4(view * (a_pos + u_translate), 1.0);
   if (gl_InstanceID < 2) {
     // base
     v_color.a /= 2 - v_color.a;
@@ -29,16 +69,19 @@ void main() {
     gl_Position.z += zoffset;
     v_color *= 0.5;
   }
-  if (u_use_mask) {
-    vec2 p = (view * u_mask_pos).xy;
-    vec2 d = (view * u_mask_dir).xy;
-    mask_dist =
-        (gl_Position.x * d.y - gl_Position.y * d.x) - (p.x * d.y - p.y * d.x);
-  } else {
-    mask_dist = 1.0;
+  v_screen = gl_Position.xy;
+  v_mask_count = u_mask_enabled ? min(int(a_masks.y), MAX_MASKS) : 0;
+  for (int i = 0; i < v_mask_count; i++) {
+    vec4 p = mask_texel(int(a_masks.x) + i * 2);
+    vec4 d = mask_texel(int(a_masks.x) + i * 2 + 1);
+    if (p.w > 0.5) {
+      v_masks[i] = vec4(p.xy, d.xy);
+    } else {
+      v_masks[i] = vec4((view * (p.xyz + u_translate)).xy, (view * d.xyz).xy);
+    }
   }
   gl_Position.x /= u_aspect_ratio;
-  gl_Position.z += u_index;
+  gl_Position.z += a_index;
   // using lequal
   gl_Position.z = -gl_Position.z;
 }
\ No newline at end of file
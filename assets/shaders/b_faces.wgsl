@@ -0,0 +1,118 @@
+// b_faces.vs and b_faces.fs for the wgpu backend
+const zRatio: f32 = 0.01;
+
+const MAX_MASKS: u32 = 8u;
+
+struct Uniforms {
+  proj: mat3x3<f32>,
+  aspect_ratio: f32,
+  // 1.0 if the masks are used
+  mask_enabled: f32,
+  // the id of the highlighted face, -1 for none
+  highlighted: f32,
+  // how much the highlighted face is mixed with white
+  highlight: f32,
+  // added to the points before they are turned, moving the camera
+  translate: vec3<f32>,
+  // the projection of the mode, the last row is the depth
+  pj: mat3x3<f32>,
+  // where the light comes from after the turning, zero for no light
+  light: vec3<f32>,
+  // the brightness of a face turned away from the light
+  ambient: f32,
+  outline_color: vec4<f32>,
+  // the width in pixels, 0 for no outlines
+  outline_width: f32,
+}
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+// every mask takes two texels: (pos, screen) and (dir, 0)
+@group(0) @binding(1) var u_masks: texture_2d<f32>;
+
+// u.pj with its depth scaled by zRatio, after the turning
+fn view_mat() -> mat3x3<f32> {
+  return mat3x3<f32>(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u.pj * u.proj;
+}
+
+struct VertexInput {
+  @location(0) pos: vec3<f32>,
+  @location(1) color: vec4<f32>,
+  @location(2) index: f32,
+  // the position of the face in the list
+  @location(3) id: f32,
+  // where the masks of the face start in u_masks and how many they are
+  @location(4) masks: vec2<f32>,
+  // the normal of the face, zero if it is not lit
+  @location(5) normal: vec3<f32>,
+  // (0, 0) to (1, 1) from the first to the last corner, for the outlines
+  @location(6) uv: vec2<f32>,
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+  @location(1) screen: vec2<f32>,
+  // the first texel and the number of masks used
+  @location(2) @interpolate(flat) masks: vec2<u32>,
+  @location(3) uv: vec2<f32>,
+}
+
+fn mask_texel(i: u32) -> vec4<f32> {
+  let width = textureDimensions(u_masks).x;
+  return textureLoad(u_masks, vec2<u32>(i % width, i / width), 0);
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+  let view = view_mat();
+  var out: VertexOutput;
+  out.color = in.color;
+  if (any(in.normal != vec3<f32>(0.0)) && any(u.light != vec3<f32>(0.0))) {
+    var n = normalize(u.proj * in.normal);
+    // the side facing the viewer, the depth grows towards it
+    if (dot(n, vec3<f32>(u.pj[0][2], u.pj[1][2], u.pj[2][2])) < 0.0) {
+      n = -n;
+    }
+    let light = u.ambient + (1.0 - u.ambient) * max(dot(n, normalize(u.light)), 0.0);
+    out.color = vec4<f32>(out.color.rgb * light, out.color.a);
+  }
+  out.uv = in.uv;
+  if (in.id == u.highlighted) {
+    out.color = vec4<f32>(mix(out.color.rgb, vec3<f32>(1.0), u.highlight), out.color.a);
+  }
+  // every point is covered twice
+  out.color.a /= 2.0 - out.color.a;
+  let pos = view * (in.pos + u.translate);
+  out.screen = pos.xy;
+  let count = select(0u, min(u32(in.masks.y), MAX_MASKS), u.mask_enabled > 0.5);
+  out.masks = vec2<u32>(u32(in.masks.x), count);
+  // using lequal, the depth of opengl from -1..1 to 0..1
+  let z = -(pos.z + in.index);
+  out.position = vec4<f32>(pos.x / u.aspect_ratio, pos.y, (z + 1.0) * 0.5, 1.0);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  // how many pixels away from the nearest edge
+  let edges = min(in.uv, 1.0 - in.uv) / fwidth(in.uv);
+  let view = view_mat();
+  // every mask cuts off the left side of its line
+  for (var i = 0u; i < in.masks.y; i++) {
+    var p = mask_texel(in.masks.x + i * 2u);
+    var d = mask_texel(in.masks.x + i * 2u + 1u);
+    if (p.w < 0.5) {
+      p = vec4<f32>(view * (p.xyz + u.translate), 0.0);
+      d = vec4<f32>(view * d.xyz, 0.0);
+    }
+    if ((in.screen.x * d.y - in.screen.y * d.x) - (p.x * d.y - p.y * d.x) < 0.0) {
+      discard;
+    }
+  }
+  if (min(edges.x, edges.y) < u.outline_width) {
+    // every point is covered twice
+    let c = u.outline_color;
+    return vec4<f32>(c.rgb, c.a / (2.0 - c.a));
+  }
+  return in.color;
+}
@@ -1,9 +1,18 @@
+const int MAX_MASKS = 8;
+
 in vec4 v_color;
-in highp float mask_dist;
+in vec2 v_screen;
+flat in int v_mask_count;
+flat in vec4 v_masks[MAX_MASKS];
 out vec4 out_color;
 void main() {
-  if (mask_dist < 0.0) {
-    discard;
+  // every mask cuts off the left side of its line
+  for (int i = 0; i < v_mask_count; i++) {
+    vec2 p = v_masks[i].xy;
+    vec2 d = v_masks[i].zw;
+    if ((v_screen.x * d.y - v_screen.y * d.x) - (p.x * d.y - p.y * d.x) < 0.0) {
+      discard;
+    }
   }
   out_color = v_color;
 }
\ No newline at end of file
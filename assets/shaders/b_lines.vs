@@ -1,39 +1,48 @@
 const highp float zRatio = -0.1;
-const mat3x3 pj = mat3x3(0.866025, -0.5, zRatio,  // x -> x'
-                         0.0, 1.0, zRatio,        // y -> y'
-                         -0.866025, -0.5, zRatio  // z -> z'
-);
+
+const int MAX_MASKS = 8;
+
+in vec3 a_pos;
+in vec3 a_color;
+// where the masks of the line start in u_masks and how many they are
+in vec2 a_masks;
 
 out vec4 v_color;
-out highp float mask_dist;
+out vec2 v_screen;
+flat out int v_mask_count;
+// xy: the projected position, zw: the projected direction
+flat out vec4 v_masks[MAX_MASKS];
 
 uniform mat3 u_proj;
+// the projection of the mode, the last row is the depth
+uniform mat3 u_pj;
+// added to the points before they are turned, moving the camera
+uniform vec3 u_translate;
 uniform float u_aspect_ratio;
 
-uniform vec3 u_color1;
-uniform vec3 u_color2;
-uniform vec3 u_pos1;
-uniform vec3 u_pos2;
-uniform bool u_use_mask;
-uniform vec3 u_mask_pos;
-uniform vec3 u_mask_dir;
+uniform bool u_mask_enabled;
+// every mask takes two texels: (pos, screen) and (dir, 0)
+uniform highp sampler2D u_masks;
+
+vec4 mask_texel(int i) {
+  int width = textureSize(u_masks, 0).x;
+  return texelFetch(u_masks, ivec2(i % width, i / width), 0);
+}
 
 void main() {
-  mat3 view = pj * u_proj;
-  if (gl_VertexID == 0) {
-    v_color = vec4(u_color1, 1.0);
-    gl_Position = vec4(view * u_pos1, 1.0);
-  } else {
-    v_color = vec4(u_color2, 1.0);
-    gl_Position = vec4(view * u_pos2, 1.0);
-  }
-  if (u_use_mask) {
-    vec2 p = (view * u_mask_pos).xy;
-    vec2 d = (view * u_mask_dir).xy;
-    mask_dist =
-        (gl_Position.x * d.y - gl_Position.y * d.x) - (p.x * d.y - p.y * d.x);
-  } else {
-    mask_dist = 1.0;
+  mat3 view = mat3(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u_pj * u_proj;
+  v_color = vec4(a_color, 1.0);
+  gl_Position = vec4(view * (a_pos + u_translate), 1.0);
+  v_screen = gl_Position.xy;
+  v_mask_count = u_mask_enabled ? min(int(a_masks.y), MAX_MASKS) : 0;
+  for (int i = 0; i < v_mask_count; i++) {
+    vec4 p = mask_texel(int(a_masks.x) + i * 2);
+    vec4 d = mask_texel(int(a_masks.x) + i * 2 + 1);
+    if (p.w > 0.5) {
+      v_masks[i] = vec4(p.xy, d.xy);
+    } else {
+      v_masks[i] = vec4((view * (p.xyz + u_translate)).xy, (view * d.xyz).xy);
+    }
   }
   gl_Position.x /= u_aspect_ratio;
 }
\ No newline at end of file
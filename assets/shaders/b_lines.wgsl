@@ -0,0 +1,79 @@
+// b_lines.vs and b_lines.fs for the wgpu backend
+const zRatio: f32 = -0.1;
+
+const MAX_MASKS: u32 = 8u;
+
+struct Uniforms {
+  proj: mat3x3<f32>,
+  aspect_ratio: f32,
+  // 1.0 if the masks are used
+  mask_enabled: f32,
+  // not used by the lines
+  highlighted: f32,
+  highlight: f32,
+  // added to the points before they are turned, moving the camera
+  translate: vec3<f32>,
+  // the projection of the mode, the last row is the depth
+  pj: mat3x3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+// every mask takes two texels: (pos, screen) and (dir, 0)
+@group(0) @binding(1) var u_masks: texture_2d<f32>;
+
+// u.pj with its depth scaled by zRatio, after the turning
+fn view_mat() -> mat3x3<f32> {
+  return mat3x3<f32>(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u.pj * u.proj;
+}
+
+struct VertexInput {
+  @location(0) pos: vec3<f32>,
+  @location(1) color: vec3<f32>,
+  // where the masks of the line start in u_masks and how many they are
+  @location(2) masks: vec2<f32>,
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+  @location(1) screen: vec2<f32>,
+  // the first texel and the number of masks used
+  @location(2) @interpolate(flat) masks: vec2<u32>,
+}
+
+fn mask_texel(i: u32) -> vec4<f32> {
+  let width = textureDimensions(u_masks).x;
+  return textureLoad(u_masks, vec2<u32>(i % width, i / width), 0);
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+  let view = view_mat();
+  var out: VertexOutput;
+  out.color = vec4<f32>(in.color, 1.0);
+  let pos = view * (in.pos + u.translate);
+  out.screen = pos.xy;
+  let count = select(0u, min(u32(in.masks.y), MAX_MASKS), u.mask_enabled > 0.5);
+  out.masks = vec2<u32>(u32(in.masks.x), count);
+  // the depth of opengl from -1..1 to 0..1
+  out.position = vec4<f32>(pos.x / u.aspect_ratio, pos.y, (pos.z + 1.0) * 0.5, 1.0);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let view = view_mat();
+  // every mask cuts off the left side of its line
+  for (var i = 0u; i < in.masks.y; i++) {
+    var p = mask_texel(in.masks.x + i * 2u);
+    var d = mask_texel(in.masks.x + i * 2u + 1u);
+    if (p.w < 0.5) {
+      p = vec4<f32>(view * (p.xyz + u.translate), 0.0);
+      d = vec4<f32>(view * d.xyz, 0.0);
+    }
+    if ((in.screen.x * d.y - in.screen.y * d.x) - (p.x * d.y - p.y * d.x) < 0.0) {
+      discard;
+    }
+  }
+  return in.color;
+}
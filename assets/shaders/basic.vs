@@ -1,10 +1,6 @@
 const int permutation[4] = int[4](1, 0, 3, 2);
 const highp float zRatio = -0.1;
 const highp float zoffset = 0.001;
-const mat3x3 pj = mat3x3(0.866025, -0.5, zRatio,  // x -> x'
-                         0.0, 1.0, zRatio,        // y -> y'
-                         -0.866025, -0.5, zRatio  // z -> z'
-);
 
 out vec4 v_color;
 out highp float mask_dist;
@@ -13,6 +9,8 @@ uniform bool u_base_layer;
 uniform mat4 u_colors;
 uniform mat4x3 u_points;
 uniform mat3 u_proj;
+// the projection of the mode, the last row is the depth
+uniform mat3 u_pj;
 
 uniform bool u_use_mask;
 uniform vec3 u_mask_pos;
@@ -20,7 +18,7 @@ uniform vec3 u_mask_dir;
 uniform float u_aspect_ratio;
 
 void main() {
-  mat3 view = pj * u_proj;
+  mat3 view = mat3(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u_pj * u_proj;
   if (u_base_layer) {
     v_color = u_colors[gl_VertexID];
     gl_Position = vec4(view * u_points[gl_VertexID], 1.0);
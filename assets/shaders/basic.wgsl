@@ -0,0 +1,71 @@
+// basic.vs and basic.fs for the wgpu backend,
+// the points are turned on the cpu and every layer is a list of triangles
+const zRatio: f32 = -0.1;
+const zoffset: f32 = 0.001;
+
+struct Uniforms {
+  proj: mat3x3<f32>,
+  aspect_ratio: f32,
+  // 1.0 if the mask is used
+  mask_enabled: f32,
+  // not used by the basic view
+  highlighted: f32,
+  highlight: f32,
+  // not used by the basic view
+  translate: vec3<f32>,
+  // the projection of the mode, the last row is the depth
+  pj: mat3x3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+// the mask takes two texels: (pos, screen) and (dir, 0)
+@group(0) @binding(1) var u_masks: texture_2d<f32>;
+
+// u.pj with its depth scaled by zRatio, after the turning
+fn view_mat() -> mat3x3<f32> {
+  return mat3x3<f32>(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u.pj * u.proj;
+}
+
+struct VertexInput {
+  @location(0) pos: vec3<f32>,
+  @location(1) color: vec4<f32>,
+  // 0 for the base layer, 1 for the one over it
+  @location(2) layer: f32,
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+  @location(1) mask_dist: f32,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+  let view = view_mat();
+  var out: VertexOutput;
+  var pos = view * in.pos;
+  if (in.layer > 0.5) {
+    out.color = in.color * 0.5;
+    pos.z += zoffset;
+  } else {
+    out.color = in.color;
+  }
+  if (u.mask_enabled > 0.5) {
+    let p = (view * textureLoad(u_masks, vec2<u32>(0u, 0u), 0).xyz).xy;
+    let d = (view * textureLoad(u_masks, vec2<u32>(1u, 0u), 0).xyz).xy;
+    out.mask_dist = (pos.x * d.y - pos.y * d.x) - (p.x * d.y - p.y * d.x);
+  } else {
+    out.mask_dist = 1.0;
+  }
+  // the depth of opengl from -1..1 to 0..1
+  out.position = vec4<f32>(pos.x / u.aspect_ratio, pos.y, (pos.z + 1.0) * 0.5, 1.0);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  if (in.mask_dist < 0.0) {
+    discard;
+  }
+  return in.color;
+}
@@ -1,7 +1,10 @@
 use std::sync::mpsc;
 
 use crate::my::game::game_info::MyGameInfo;
+use crate::my::game::{morph::MorphSettings, rotation::RotationSettings};
 use crate::my::performance_evaluation::PerformanceEvaluation;
+use crate::settings::Settings;
+use my_items::shading::Shading;
 
 #[derive(Debug)]
 pub struct GLobalMessage {
@@ -73,7 +76,8 @@ impl GLobalMessage {
 pub struct MyGameOption {
     pub global_message: GLobalMessage,
 
-    pub fullscreen: bool,
+    /// what is saved between runs
+    pub settings: Settings,
     pub screenshot: MyScreenShot,
     pub messages: MyMessage,
     pub time: std::time::Instant,
@@ -83,6 +87,12 @@ pub struct MyGameOption {
     pub performance_evaluation: PerformanceEvaluation,
 
     pub events: MyEvents,
+    /// how the game view turns
+    pub rotation: RotationSettings,
+    /// how the faces change when the level changes its state
+    pub morph: MorphSettings,
+    /// the light and the outlines of the faces, a level may turn them off
+    pub shading: Shading,
 
     pub game_library: my_levels_finder::CollectedGame,
     pub game_info: MyGameInfo,
@@ -93,7 +103,7 @@ impl Default for MyGameOption {
         Self {
             global_message: GLobalMessage::new(),
 
-            fullscreen: false,
+            settings: Default::default(),
             screenshot: Default::default(),
             messages: MyMessage::default(),
             time: std::time::Instant::now(),
@@ -102,6 +112,12 @@ impl Default for MyGameOption {
             performance_evaluation: PerformanceEvaluation::new(),
 
             events: Default::default(),
+            rotation: Default::default(),
+            morph: Default::default(),
+            shading: Shading {
+                light: Some(Default::default()),
+                outline: Some(Default::default()),
+            },
 
             game_library: Self::load_levels(),
             game_info: MyGameInfo::NONE,
@@ -120,6 +136,8 @@ impl MyGameOption {
 #[derive(Debug, PartialEq)]
 pub struct MyScreenShot {
     pub screen_shot: bool,
+    /// the faces of the level alone, drawn offscreen in this frame
+    pub clean_capture: bool,
     pub screen_recording: bool,
     pub screen_recording_stop: bool,
 
@@ -130,6 +148,7 @@ impl Clone for MyScreenShot {
     fn clone(&self) -> Self {
         Self {
             screen_shot: self.screen_shot,
+            clean_capture: self.clean_capture,
             screen_recording: self.screen_recording,
             screen_recording_stop: self.screen_recording_stop,
             video_encoder: None,
@@ -141,6 +160,7 @@ impl Default for MyScreenShot {
     fn default() -> Self {
         Self {
             screen_shot: false,
+            clean_capture: false,
             screen_recording: false,
             screen_recording_stop: false,
             video_encoder: None,
@@ -158,9 +178,15 @@ pub mod media {
     /// or else return Some(error: String)
     pub fn save_image(path: &str, ext: &str, img: &eframe::egui::ColorImage) -> Option<String> {
         let path = format!("{path}.{ext}");
+        // the pixels are premultiplied, which matters for the translucent ones
+        let bytes: Vec<u8> = img
+            .pixels
+            .iter()
+            .flat_map(|p| p.to_srgba_unmultiplied())
+            .collect();
         if let Err(err) = image::save_buffer(
             path,
-            img.as_raw(),
+            &bytes,
             img.width() as u32,
             img.height() as u32,
             image::ColorType::Rgba8,
@@ -192,16 +218,17 @@ pub mod media {
     }
 
     impl Video {
+        /// The size is in pixels, the frames of another size are scaled to it
         pub fn new(
-            height: usize,
             width: usize,
+            height: usize,
             path: &str,
             msg_sender: Sender<(String, u64)>,
             info_sender: Sender<(String, i64)>,
         ) -> Self {
             let lib =
                 unsafe { libloading::Library::new(super::get_lib_name("videosaver")).unwrap() };
-            
+
             let init: fn(usize, usize, String) =
                 *unsafe { lib.get::<fn(usize, usize, String)>(b"new\0").unwrap() };
             let add_frame: fn(ndarray::Array3<u8>, f64) = *unsafe {
@@ -226,10 +253,13 @@ pub mod media {
 
                     let shape = (height, width, 3);
                     let data = msg.image.as_raw();
+                    let [w, h] = msg.image.size;
                     let default = &0;
+                    // the nearest pixel, the window may be resized while recording
                     let frame: ndarray::Array3<u8> =
                         ndarray::Array3::from_shape_fn(shape, |(y, x, c)| {
-                            *data.get((y * shape.1 + x) * 4 + c).unwrap_or(default)
+                            let (x, y) = (x * w / width, y * h / height);
+                            *data.get((y * w + x) * 4 + c).unwrap_or(default)
                         });
                     // println!("Sending {dt} frame: {:?}", frame);
                     add_frame(frame, dt);
@@ -412,6 +442,30 @@ pub struct MyEvents {
     pub left: bool,
     /// keybord/game-movement
     pub right: bool,
+    /// keybord/game-camera, + and -
+    pub zoom_in: bool,
+    /// keybord/game-camera
+    pub zoom_out: bool,
+    /// keybord/game-camera, F fits the scene into the view
+    pub fit: bool,
+    /// keybord/game-camera, Home goes back to the view of the level
+    pub home: bool,
+    /// keybord/game-camera, W and S look from above and below
+    pub pitch_up: bool,
+    /// keybord/game-camera
+    pub pitch_down: bool,
+    /// keybord/game-camera, P switches to the next projection of the level
+    pub next_projection: bool,
+    /// keybord/game-debug, F3 opens the inspector of the level
+    pub inspector: bool,
+    /// keybord/game-avatar, I J K L walk up, left, down and right on the screen, y goes up
+    pub walk: (f32, f32),
+    /// keybord/game-history, Ctrl+Z takes back the last move
+    pub undo: bool,
+    /// keybord/game-history, Ctrl+Y or Ctrl+Shift+Z does it again
+    pub redo: bool,
+    /// keybord/game-history, Ctrl+R starts the level again
+    pub restart: bool,
     /// mouse
     pub moved: (f32, f32),
     pub scrolled: (f32, f32),
@@ -422,6 +476,10 @@ pub struct MyEvents {
 
     /// mouse left button
     pub pressed_l: bool,
+    /// mouse left button, released near where it was pressed, not a drag
+    pub clicked_l: bool,
+    /// pointer position when the left button was pressed
+    pub pressed_pos: (f32, f32),
     /// mouse right button
     pub pressed_r: bool,
     /// mouse middle button
@@ -429,6 +487,8 @@ pub struct MyEvents {
 }
 
 impl MyEvents {
+    /// how far in points the pointer may move between press and release of a click
+    const CLICK_DISTANCE: f32 = 4.0;
     #[allow(dead_code)]
     pub fn reset_all(&mut self) {
         // Copy
@@ -447,11 +507,24 @@ impl MyEvents {
         // self.pressed_l = false;
         // self.pressed_m = false;
         // self.pressed_r = false;
+        self.clicked_l = false;
         self.moved = (0.0, 0.0);
         self.scrolled = (0.0, 0.0);
 
         self.left = false;
         self.right = false;
+        self.zoom_in = false;
+        self.zoom_out = false;
+        self.fit = false;
+        self.home = false;
+        self.pitch_up = false;
+        self.pitch_down = false;
+        self.next_projection = false;
+        self.inspector = false;
+        self.walk = (0.0, 0.0);
+        self.undo = false;
+        self.redo = false;
+        self.restart = false;
     }
     pub fn get(&mut self, ctx: &eframe::egui::Context) {
         self.reset();
@@ -468,8 +541,17 @@ impl MyEvents {
                     eframe::egui::Event::PointerButton {
                         button: eframe::egui::PointerButton::Primary,
                         pressed,
+                        pos,
                         ..
-                    } => self.pressed_l = *pressed,
+                    } => {
+                        if *pressed {
+                            self.pressed_pos = (pos.x, pos.y);
+                        } else if self.pressed_l {
+                            let (dx, dy) = (pos.x - self.pressed_pos.0, pos.y - self.pressed_pos.1);
+                            self.clicked_l = dx * dx + dy * dy <= Self::CLICK_DISTANCE.powi(2);
+                        }
+                        self.pressed_l = *pressed;
+                    }
                     eframe::egui::Event::PointerButton {
                         button: eframe::egui::PointerButton::Secondary,
                         pressed,
@@ -503,12 +585,49 @@ impl MyEvents {
                             // A D
                             eframe::egui::Key::A => self.left = true,
                             eframe::egui::Key::D => self.right = true,
+                            // + - F Home
+                            eframe::egui::Key::Plus | eframe::egui::Key::Equals => {
+                                self.zoom_in = true
+                            }
+                            eframe::egui::Key::Minus => self.zoom_out = true,
+                            eframe::egui::Key::F => self.fit = true,
+                            eframe::egui::Key::Home => self.home = true,
+                            // W S ArrowUp ArrowDown P
+                            eframe::egui::Key::W | eframe::egui::Key::ArrowUp => {
+                                self.pitch_up = true
+                            }
+                            eframe::egui::Key::S | eframe::egui::Key::ArrowDown => {
+                                self.pitch_down = true
+                            }
+                            eframe::egui::Key::P => self.next_projection = true,
+                            eframe::egui::Key::F3 => self.inspector = true,
+                            // I J K L
+                            eframe::egui::Key::I => self.walk = (0.0, 1.0),
+                            eframe::egui::Key::J => self.walk = (-1.0, 0.0),
+                            eframe::egui::Key::K => self.walk = (0.0, -1.0),
+                            eframe::egui::Key::L => self.walk = (1.0, 0.0),
+                            // Ctrl+Z Ctrl+Y Ctrl+R
+                            eframe::egui::Key::Z if modifiers.command => {
+                                if modifiers.shift {
+                                    self.redo = true
+                                } else {
+                                    self.undo = true
+                                }
+                            }
+                            eframe::egui::Key::Y if modifiers.command => self.redo = true,
+                            eframe::egui::Key::R if modifiers.command => self.restart = true,
                             _ => (),
                         }
-                        // A and D of the keyboard
+                        // A, D, W, S, I, J, K and L of the keyboard
                         match p_key {
                             Some(eframe::egui::Key::A) => self.left = true,
                             Some(eframe::egui::Key::D) => self.right = true,
+                            Some(eframe::egui::Key::W) => self.pitch_up = true,
+                            Some(eframe::egui::Key::S) => self.pitch_down = true,
+                            Some(eframe::egui::Key::I) => self.walk = (0.0, 1.0),
+                            Some(eframe::egui::Key::J) => self.walk = (-1.0, 0.0),
+                            Some(eframe::egui::Key::K) => self.walk = (0.0, -1.0),
+                            Some(eframe::egui::Key::L) => self.walk = (1.0, 0.0),
                             _ => (),
                         }
                         self.alt |= modifiers.alt;
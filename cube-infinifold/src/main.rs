@@ -2,29 +2,31 @@ use eframe::{egui, glow, icon_data};
 
 mod game_options;
 mod my;
+mod settings;
 mod test;
 use game_options::{media, MyGameOption};
 use my::{
-    cube_infinifold_logo::MyInfinifoldLogo, game::MyGameView, gl_views::MyGLView, level_index::MyLevelIndex, load_fonts::load_fonts, menu::MyMenu, MyView, MyViewImpl
+    cube_infinifold_logo::MyInfinifoldLogo, game::MyGameView, level_index::MyLevelIndex, load_fonts::load_fonts, menu::MyMenu, render::{self, Backend, MyGLView}, MyView, MyViewImpl
 };
 
 fn main() -> Result<(), eframe::Error> {
     my::install::install();
+    let backend = Backend::from_env();
+    println!("Renderer: {backend:?}");
+    let settings = settings::Settings::load();
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_fullscreen(true)
-            // .with_inner_size(egui::Vec2::new(800.0/1.25, 600.0/1.25))
-            .with_resizable(false)
+        viewport: settings
+            .window
+            .viewport(egui::ViewportBuilder::default(), settings.window_size)
             .with_fullsize_content_view(false)
             .with_icon(
                 icon_data::from_png_bytes(include_bytes!("../../assets/ferris.png"))
                     .expect("can not load file"),
             ),
-        multisampling: 4,
-        renderer: eframe::Renderer::Wgpu,
-        // renderer: eframe::Renderer::Glow,
-        depth_buffer: 24,
+        multisampling: render::MULTISAMPLING,
+        renderer: backend.renderer(),
+        depth_buffer: render::DEPTH_BUFFER,
         ..Default::default()
     };
 
@@ -33,7 +35,7 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Custom 3D painting in eframe using glow",
         options,
-        Box::new(|cc| Box::new(MyApp::new(cc))),
+        Box::new(|cc| Box::new(MyApp::new(cc, settings))),
     )
 }
 
@@ -44,13 +46,17 @@ struct MyApp {
 }
 
 impl MyApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, settings: settings::Settings) -> Self {
         egui_extras::install_image_loaders(&cc.egui_ctx);
         load_fonts(&cc.egui_ctx);
+        let option = MyGameOption {
+            settings,
+            ..Default::default()
+        };
         let mut sf = Self {
             my_view: MyView::None,
-            game_view: MyGLView::new(cc),
-            option: Default::default(),
+            game_view: MyGLView::new(cc, option.messages.send.clone()),
+            option,
         };
         sf.change_to(String::from("Menu"), &cc.egui_ctx);
         sf
@@ -135,6 +141,16 @@ impl eframe::App for MyApp {
                     } => {
                         return "screen shot";
                     }
+                    // the level alone, drawn offscreen with shift F2
+                    egui::Event::Key {
+                        key: egui::Key::F2,
+                        pressed: true,
+                        modifiers: egui::Modifiers::SHIFT,
+                        repeat: false,
+                        physical_key: _,
+                    } => {
+                        return "clean capture";
+                    }
                     egui::Event::Key {
                         key: egui::Key::T,
                         pressed: true,
@@ -144,6 +160,16 @@ impl eframe::App for MyApp {
                     } => {
                         return "text input";
                     }
+                    // fullscreen, borderless and windowed with F11
+                    egui::Event::Key {
+                        key: egui::Key::F11,
+                        pressed: true,
+                        modifiers: egui::Modifiers::NONE,
+                        repeat: false,
+                        physical_key: _,
+                    } => {
+                        return "window mode";
+                    }
                     // hide and show perf eval with F12
                     egui::Event::Key {
                         key: egui::Key::F12,
@@ -181,8 +207,8 @@ impl eframe::App for MyApp {
                         _ => {
                             let rect = ctx.screen_rect();
                             self.option.screenshot.video_encoder = Some(media::Video::new(
-                                (rect.height() * ctx.pixels_per_point()).round() as usize,
                                 (rect.width() * ctx.pixels_per_point()).round() as usize,
+                                (rect.height() * ctx.pixels_per_point()).round() as usize,
                                 "video.mp4",
                                 self.option.messages.send.clone(),
                                 self.option.global_message.sender.clone(),
@@ -197,6 +223,26 @@ impl eframe::App for MyApp {
             "screen shot" => {
                 self.option.screenshot.screen_shot = true;
             }
+            "clean capture" => {
+                if let MyView::MyGame(_) = self.my_view {
+                    self.option.screenshot.clean_capture = true;
+                } else {
+                    let msg = "Clean capture is only for levels".to_string();
+                    let _ = self.option.messages.send.send((msg, 2000));
+                }
+            }
+            "window mode" => {
+                let settings = &mut self.option.settings;
+                settings.window = settings.window.next();
+                for command in settings.window.commands(settings.window_size) {
+                    ctx.send_viewport_cmd(command);
+                }
+                let msg = match settings.save() {
+                    Ok(()) => format!("Window: {}", settings.window.name()),
+                    Err(err) => err,
+                };
+                let _ = self.option.messages.send.send((msg, 2000));
+            }
             "text input" => {
                 self.option.messages.expanded = !self.option.messages.expanded;
             }
@@ -216,6 +262,18 @@ impl eframe::App for MyApp {
             _ => (),
         }
 
+        // the size of the window is kept for the windowed mode
+        if self.option.settings.window == settings::WindowMode::Windowed {
+            let size = ctx.input(|i| {
+                let v = i.viewport();
+                let windowed = v.fullscreen == Some(false) && v.maximized == Some(false);
+                v.inner_rect.filter(|_| windowed).map(|r| r.size())
+            });
+            if let Some(size) = size {
+                self.option.settings.window_size = size;
+            }
+        }
+
         // paint all
         egui::CentralPanel::default().show(ctx, |ui| {
             // Draw View
@@ -251,6 +309,8 @@ impl eframe::App for MyApp {
                 self.option.performance_evaluation.draw(ui);
             }
         });
+        // the view has asked for it
+        self.option.screenshot.clean_capture = false;
         ctx.request_repaint();
 
         // message
@@ -354,8 +414,9 @@ impl eframe::App for MyApp {
     }
 
     fn on_exit(&mut self, gl: Option<&glow::Context>) {
-        if let Some(gl) = gl {
-            self.game_view.destroy_all(gl);
+        if let Err(err) = self.option.settings.save() {
+            println!("{err}");
         }
+        self.game_view.destroy_all(gl);
     }
 }
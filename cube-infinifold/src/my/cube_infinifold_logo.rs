@@ -1,22 +1,15 @@
 use std::sync::Arc;
 
-use eframe::{
-    egui::{self, mutex::Mutex},
-    egui_glow,
-};
+use eframe::egui;
 use rand::random;
 
 use crate::game_options::MyGameOption;
 
-use super::{
-    gl_views::{GLGameBase, GLLinesView, GlPaintOptions},
-    MyViewImpl, UIWidget,
-};
+use super::{gl_views::GlPaintOptions, render::LinesView, MyViewImpl, UIWidget};
 
 pub struct MyInfinifoldLogo {
-    /// Behind an `Arc<Mutex<…>>` so we can pass it to [`egui::PaintCallback`] and paint later.
-    // game_view: Arc<Mutex<GLLinesView>>,
-    game_view: Arc<Mutex<GLLinesView>>,
+    /// Behind an `Arc` so we can pass it to [`egui::PaintCallback`] and paint later.
+    game_view: Arc<dyn LinesView>,
     angle: f32,
 
     // perf: PerformanceEvaluation,
@@ -28,10 +21,7 @@ pub struct MyInfinifoldLogo {
 }
 
 impl MyInfinifoldLogo {
-    pub fn new(
-        game_view: Arc<Mutex<GLLinesView>>,
-        ctx: &eframe::egui::Context,
-    ) -> MyInfinifoldLogo {
+    pub fn new(game_view: Arc<dyn LinesView>, ctx: &eframe::egui::Context) -> MyInfinifoldLogo {
         let btns = vec![UIWidget::new(vec![
             "file://assets/ui/unselected.png",
             "file://assets/ui/selected.png",
@@ -39,7 +29,7 @@ impl MyInfinifoldLogo {
         .with_font(egui::Color32::GREEN, 28.0, egui::FontFamily::Proportional)
         .with_size(200.0, 50.0)
         .load(ctx)];
-        game_view.lock().set_lines(Self::get_box(3, 0.8, 0.8, 0.6));
+        game_view.set_lines(Self::get_box(3, 0.8, 0.8, 0.6));
         Self {
             game_view,
             angle: 0.0,
@@ -60,23 +50,19 @@ impl MyInfinifoldLogo {
             self.angle -= std::f32::consts::PI * 2.0;
             self.box_num += 1;
             self.game_view
-                .lock()
                 .set_lines(Self::get_box(self.box_num, 0.8, 0.8, 0.6));
         } else if self.angle < 0.0 {
             self.angle += std::f32::consts::PI * 2.0;
             self.box_num -= if self.box_num <= 1 { 0 } else { 1 };
             self.game_view
-                .lock()
                 .set_lines(Self::get_box(self.box_num, 0.8, 0.8, 0.6));
         }
 
         // Clone locals so we can move them into the paint callback:
         let angle = self.angle - (45.0 as f32).to_radians();
         self.game_view
-            .lock()
             .set_musk_enabled(angle < (45.0 as f32).to_radians());
 
-        let game_view = self.game_view.clone();
         let option = GlPaintOptions {
             angle,
             scale: 0.2,
@@ -84,12 +70,7 @@ impl MyInfinifoldLogo {
             ..Default::default()
         };
 
-        let callback = egui::PaintCallback {
-            rect: ui.max_rect(),
-            callback: std::sync::Arc::new(egui_glow::CallbackFn::new(move |_info, painter| {
-                game_view.lock().paint(painter.gl(), &option);
-            })),
-        };
+        let callback = self.game_view.clone().paint_callback(ui.max_rect(), option);
         // std::thread::spawn(f)
         ui.painter().add(callback);
     }
@@ -165,25 +146,23 @@ impl MyInfinifoldLogo {
 
         // transform
         let mut res: Vec<my_items::Line> = vec![];
-        let musk = Some(my_items::Musk {
-            pos: my_items::V3 {
+        let musk = my_items::Musk::new(
+            my_items::V3 {
                 x: init_offest + 0.01,
                 y: 1.0,
                 z: init_offest + 0.01,
             },
-            dir: my_items::V3 {
+            my_items::V3 {
                 x: 0.0,
                 y: -1.0,
                 z: 0.0,
             },
-        });
+        );
         let mut times = 0;
-        let cfun = my_items::Colored::Fun(Arc::new(|x| my_items::Color {
-            r: 0.5,
-            g: 0.2 * x as f32,
-            b: 0.8,
-            a: 1.0,
-        }));
+        let cfun = my_items::Colored::Fun(my_items::ColorFun::Linear {
+            base: my_items::Color::from(0.5, 0.0, 0.8, 1.0),
+            step: my_items::Color::from(0.0, 0.2, 0.0, 0.0),
+        });
         for i in ori.iter() {
             for j in i.1.iter() {
                 res.push(my_items::Line {
@@ -197,7 +176,11 @@ impl MyInfinifoldLogo {
                         y: ori[(times + *j) as usize].0[1],
                         z: ori[(times + *j) as usize].0[2],
                     },
-                    msk: if times < len / 2 { None } else { musk.clone() },
+                    msks: if times < len / 2 {
+                        vec![]
+                    } else {
+                        vec![musk.clone()]
+                    },
                     // color: items::Colored::Default,
                     color: cfun.clone(),
                 })
@@ -0,0 +1,386 @@
+//! The avatar of the player, walking on the walkable faces of the level, see `my_items::walk`.
+//!
+//! Clicking a walkable face walks there, I, J, K and L walk to the next face
+//! up, left, down and right on the screen. It is drawn as a small pillar among the faces.
+//! It starts on the start marker of the level, see `my_items::marker`.
+
+use std::{collections::VecDeque, time::Duration};
+
+use my_items::{
+    marker::{Marker, MarkerKind},
+    walk::{self, Step, WalkGraph},
+    Color, Face, Pillar, Projection, V3,
+};
+
+use crate::game_options::MyEvents;
+
+#[derive(Default)]
+pub struct Avatar {
+    /// where it stands, None if there is nowhere to stand in the level
+    at: Option<Step>,
+    /// the face it stands on, to find it again when the faces of the level change
+    standing: Face,
+    /// the points it walks through, the next one first
+    steps: VecDeque<Step>,
+    /// the width of the avatar
+    size: f32,
+    /// whether it is to be drawn again
+    changed: bool,
+    /// the face it stepped onto and where, not told to the level yet
+    arrived: Option<Step>,
+}
+
+impl Avatar {
+    /// in units of the world per second
+    const SPEED: f32 = 6.0;
+    /// the width of the avatar for the narrowest side of the walkable faces
+    const SIZE: f32 = 0.4;
+    const COLOR: Color = Color {
+        r: 0.95,
+        g: 0.55,
+        b: 0.2,
+        a: 1.0,
+    };
+
+    /// Stand on the start marker, or in the middle of the first walkable face without one
+    pub fn new(faces: &[Face], markers: &[Marker]) -> Self {
+        let mut s = Self::default();
+        let walkable = || faces.iter().filter(|f| f.walkable && !f.skipped);
+        s.size = walkable()
+            .flat_map(|f| [distance(&f.pos11, &f.pos12), distance(&f.pos11, &f.pos21)])
+            .fold(f32::INFINITY, f32::min);
+        s.size = if s.size.is_finite() {
+            s.size * Self::SIZE
+        } else {
+            0.0
+        };
+        let start = markers
+            .iter()
+            .filter(|m| m.kind == MarkerKind::Start)
+            .find_map(|m| Some((m.find_face(faces)?, m.pos.clone())));
+        if let Some((id, pos)) = start {
+            s.stand(id, pos, faces);
+        } else if let Some((id, f)) = faces
+            .iter()
+            .enumerate()
+            .find(|(_, f)| f.walkable && !f.skipped)
+        {
+            s.stand(id, walk::centre(f), faces);
+        }
+        s
+    }
+
+    /// Walk on by `dt`, `proj` is how the view is seen for the keys.
+    ///
+    /// True if it is to be drawn again.
+    pub fn update(
+        &mut self,
+        faces: &[Face],
+        graph: &WalkGraph,
+        proj: &Projection,
+        events: &MyEvents,
+        dt: Duration,
+    ) -> bool {
+        if events.walk != (0.0, 0.0) && self.steps.is_empty() {
+            self.walk_towards(faces, graph, proj, events.walk);
+        }
+        self.walk_on(faces, dt);
+        std::mem::take(&mut self.changed)
+    }
+
+    /// The faces of the level changed, stand at the same place of the same face if it is still there
+    pub fn faces_changed(&mut self, faces: &[Face]) {
+        let Some(at) = self.at.take() else {
+            return;
+        };
+        self.steps.clear();
+        self.changed = true;
+        let param = walk::param(&self.standing, &at.pos);
+        let walkable = |(_, f): &(usize, &Face)| f.walkable && !f.skipped;
+        let same = faces
+            .iter()
+            .enumerate()
+            .filter(walkable)
+            .find(|(_, f)| self.standing.tag.is_some() && f.tag == self.standing.tag)
+            .or_else(|| faces.get(at.face).map(|f| (at.face, f)).filter(walkable));
+        if let Some((id, f)) = same {
+            let pos = walk::point(f, param);
+            self.stand(id, pos, faces);
+            return;
+        }
+        // or the nearest one
+        let nearest = faces.iter().enumerate().filter(walkable).min_by(|a, b| {
+            let da = distance(&walk::centre(a.1), &at.pos);
+            let db = distance(&walk::centre(b.1), &at.pos);
+            da.total_cmp(&db)
+        });
+        if let Some((id, f)) = nearest {
+            self.stand(id, walk::centre(f), faces);
+        }
+    }
+
+    /// Stand at `at` again, as it was before the faces changed.
+    ///
+    /// It did not step there, so the level is not told.
+    pub fn place(&mut self, at: Option<Step>, faces: &[Face]) {
+        self.steps.clear();
+        match at {
+            Some(at) if faces.get(at.face).is_some_and(|f| f.walkable && !f.skipped) => {
+                self.stand(at.face, at.pos, faces)
+            }
+            _ => self.faces_changed(faces),
+        }
+        self.arrived = None;
+    }
+
+    /// Where it stands, None if there is nowhere to stand
+    pub fn at(&self) -> Option<&Step> {
+        self.at.as_ref()
+    }
+
+    pub fn is_walking(&self) -> bool {
+        !self.steps.is_empty()
+    }
+
+    /// Walk to `pos` on the face `id`, false if it cannot be reached
+    pub fn walk_to(&mut self, graph: &WalkGraph, id: usize, pos: V3) -> bool {
+        let Some(at) = &self.at else {
+            return false;
+        };
+        let to = Step { face: id, pos };
+        match graph.steps(at, &to) {
+            Some(steps) => {
+                self.steps = steps.into();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether it stands on the face `id` no farther than `within` from `pos`
+    pub fn reached(&self, id: usize, pos: &V3, within: f32) -> bool {
+        self.at
+            .as_ref()
+            .is_some_and(|at| at.face == id && distance(&at.pos, pos) <= within)
+    }
+
+    /// The avatar as a pillar standing where it is
+    pub fn faces(&self) -> Vec<Face> {
+        let Some(at) = &self.at else {
+            return vec![];
+        };
+        let s = self.size;
+        let p = &at.pos;
+        Pillar::new_lit(
+            V3::from(p.x - s / 2.0, p.y, p.z - s / 2.0),
+            V3::from(s, s * 1.5, s),
+            Self::COLOR,
+        )
+        .with_w(self.standing.index)
+        .with_tag("avatar")
+        .with_skipped_filter(false, false, false, true, false, false)
+        .into_vec()
+    }
+
+    fn stand(&mut self, id: usize, pos: V3, faces: &[Face]) {
+        self.standing = faces[id].clone();
+        self.at = Some(Step { face: id, pos });
+        self.arrived = self.at.clone();
+        self.changed = true;
+    }
+
+    /// The face it stepped onto since the last call, and where
+    pub fn take_arrived(&mut self) -> Option<Step> {
+        self.arrived.take()
+    }
+
+    /// The joins changed, stop before one which is not there any more
+    pub fn keep_joined(&mut self, graph: &WalkGraph) {
+        let Some(at) = &self.at else {
+            return;
+        };
+        let mut face = at.face;
+        let kept = self
+            .steps
+            .iter()
+            .take_while(|s| {
+                let ok = s.face == face || graph.link(face, s.face).is_some();
+                face = s.face;
+                ok
+            })
+            .count();
+        self.steps.truncate(kept);
+    }
+
+    /// Walk to the middle of the face joined in the direction `dir` on the screen, y goes up
+    fn walk_towards(
+        &mut self,
+        faces: &[Face],
+        graph: &WalkGraph,
+        proj: &Projection,
+        dir: (f32, f32),
+    ) {
+        let Some(at) = &self.at else {
+            return;
+        };
+        let here = proj.view(&at.pos);
+        let best = graph
+            .links(at.face)
+            .iter()
+            .filter_map(|l| {
+                let there = proj.view(&l.from_pos);
+                let (dx, dy) = (there.x - here.x, there.y - here.y);
+                let len = dx.hypot(dy) * dir.0.hypot(dir.1);
+                (len > f32::EPSILON).then(|| ((dx * dir.0 + dy * dir.1) / len, l))
+            })
+            // within 60 degrees
+            .filter(|(cos, _)| *cos > 0.5)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, link)) = best {
+            self.steps = VecDeque::from([
+                Step {
+                    face: at.face,
+                    pos: link.from_pos.clone(),
+                },
+                Step {
+                    face: link.to,
+                    pos: link.to_pos.clone(),
+                },
+                Step {
+                    face: link.to,
+                    pos: walk::centre(&faces[link.to]),
+                },
+            ]);
+        }
+    }
+
+    /// Go on along the steps, a step to another face is taken at once
+    fn walk_on(&mut self, faces: &[Face], dt: Duration) {
+        let mut left = Self::SPEED * dt.as_secs_f32();
+        while let (Some(at), Some(next)) = (&mut self.at, self.steps.front()) {
+            if next.face != at.face {
+                let next = self.steps.pop_front().unwrap();
+                self.stand(next.face, next.pos, faces);
+                continue;
+            }
+            let d = distance(&at.pos, &next.pos);
+            self.changed = true;
+            if d > left {
+                let t = left / d;
+                at.pos = V3::from(
+                    at.pos.x + (next.pos.x - at.pos.x) * t,
+                    at.pos.y + (next.pos.y - at.pos.y) * t,
+                    at.pos.z + (next.pos.z - at.pos.z) * t,
+                );
+                return;
+            }
+            left -= d;
+            at.pos = self.steps.pop_front().unwrap().pos;
+        }
+    }
+}
+
+fn distance(a: &V3, b: &V3) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_across_a_join() {
+        // two tops which only meet on the screen, see `my_items::walk`
+        let top = |x: f32, y: f32, z: f32| {
+            Pillar::new_upright(V3::from(x, y - 1.0, z), V3::from(2.0, 1.0, 2.0))
+                .with_walkable_top()
+                .into_vec()
+        };
+        let mut faces = top(0.0, 0.0, 0.0);
+        faces.append(&mut top(4.0, 2.0, 2.0));
+        let proj = Projection::new(0.0, 0.1, 1.0);
+        let graph = WalkGraph::new(&faces, &proj);
+        let events = MyEvents::default();
+        let mut avatar = Avatar::new(&faces, &[]);
+        let step = Duration::from_millis(100);
+        assert!(avatar.update(&faces, &graph, &proj, &events, Duration::ZERO));
+        assert_eq!(avatar.at.as_ref().unwrap().face, 2);
+        assert_eq!(avatar.take_arrived().map(|s| s.face), Some(2));
+        assert_eq!(avatar.take_arrived(), None);
+
+        assert!(avatar.walk_to(&graph, 8, V3::from(5.0, 2.0, 3.0)));
+        for _ in 0..20 {
+            avatar.update(&faces, &graph, &proj, &events, step);
+        }
+        let at = avatar.at.as_ref().unwrap();
+        assert_eq!((at.face, &at.pos), (8, &V3::from(5.0, 2.0, 3.0)));
+        assert!(avatar.reached(8, &V3::from(4.5, 2.0, 2.5), 1.0));
+        assert!(!avatar.reached(8, &V3::from(4.5, 2.0, 2.5), 0.5));
+        assert!(!avatar.reached(2, &V3::from(5.0, 2.0, 3.0), 1.0));
+        assert_eq!(avatar.faces()[0].index, faces[8].index);
+        assert!(!avatar.update(&faces, &graph, &proj, &events, step));
+
+        // back with the key, the join is on the left of the screen
+        let events = MyEvents {
+            walk: (-1.0, 0.0),
+            ..Default::default()
+        };
+        avatar.update(&faces, &graph, &proj, &events, Duration::ZERO);
+        assert_eq!(avatar.steps.len(), 3);
+        // and stopped on the way when the joins are gone
+        avatar.keep_joined(&WalkGraph::default());
+        assert_eq!(avatar.steps.len(), 1);
+
+        // the same top, a little higher
+        faces = top(0.0, 0.0, 0.0);
+        faces.append(&mut top(4.0, 3.0, 2.0));
+        avatar.faces_changed(&faces);
+        assert_eq!(avatar.at.as_ref().unwrap().face, 8);
+        assert!((avatar.at.as_ref().unwrap().pos.y - 3.0).abs() < 1e-5);
+
+        let start = Marker::start(V3::from(5.5, 3.0, 3.5));
+        let mut avatar = Avatar::new(&faces, std::slice::from_ref(&start));
+        assert_eq!(
+            avatar.at,
+            Some(Step {
+                face: 8,
+                pos: start.pos
+            })
+        );
+        // and back where it was
+        let back = Step {
+            face: 2,
+            pos: V3::from(1.0, 0.0, 1.0),
+        };
+        avatar.place(Some(back.clone()), &faces);
+        assert_eq!(avatar.at, Some(back));
+        assert!(!avatar.is_walking());
+        assert_eq!(avatar.take_arrived(), None);
+    }
+
+    #[test]
+    fn reaches_the_goal_on_the_same_face() {
+        let faces = Pillar::new_upright(V3::from(0.0, -1.0, 0.0), V3::from(6.0, 1.0, 2.0))
+            .with_walkable_top()
+            .into_vec();
+        let (start, goal) = (
+            Marker::start(V3::from(0.5, 0.0, 1.0)),
+            Marker::goal(V3::from(5.5, 0.0, 1.0)),
+        );
+        let proj = Projection::new(0.0, 0.1, 1.0);
+        let graph = WalkGraph::new(&faces, &proj);
+        let events = MyEvents::default();
+        let mut avatar = Avatar::new(&faces, &[start, goal.clone()]);
+        let step = Duration::from_millis(100);
+        avatar.update(&faces, &graph, &proj, &events, step);
+        assert!(!avatar.reached(2, &goal.pos, 0.5));
+
+        assert!(avatar.walk_to(&graph, 2, goal.pos.clone()));
+        avatar.update(&faces, &graph, &proj, &events, step);
+        assert!(!avatar.reached(2, &goal.pos, 0.5));
+        for _ in 0..10 {
+            avatar.update(&faces, &graph, &proj, &events, step);
+        }
+        assert!(avatar.reached(2, &goal.pos, 0.5));
+    }
+}
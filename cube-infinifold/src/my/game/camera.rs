@@ -0,0 +1,158 @@
+//! The zoom and pan of the game view.
+//!
+//! The wheel zooms at the pointer, the middle button drags the scene,
+//! + and - zoom at the middle, F fits the faces into the view and Home goes back to the view of the level.
+//!
+//! W and S look from above and below, P switches the projection, if the level allows them.
+
+use eframe::egui;
+use levels_interface::{LevelProjection, LevelView};
+use my_items::{Face, Projection, ProjectionMode, V3};
+
+use crate::game_options::MyEvents;
+
+pub struct Camera {
+    pub scale: f32,
+    /// added to the points, the negative of the point in the middle of the view
+    pub translate: V3,
+    /// looking from above, in radians
+    pub pitch: f32,
+    pub mode: ProjectionMode,
+    /// where Home goes back to, None to fit the faces
+    home: Option<LevelView>,
+    /// what the level allows
+    allowed: LevelProjection,
+    /// fit the faces in the next update, the size of the view is not known before
+    to_fit: bool,
+}
+
+impl Camera {
+    const MIN_SCALE: f32 = 0.001;
+    const MAX_SCALE: f32 = 10.0;
+    /// the zoom of a press of + or -
+    const KEY_ZOOM: f32 = 1.25;
+    /// the zoom of scrolling a point is `exp(WHEEL_ZOOM)`
+    const WHEEL_ZOOM: f32 = 0.002;
+    /// how much of the smaller side of the view the faces take after fitting
+    const FIT_FILL: f32 = 0.9;
+    /// the pitch of a press of W or S, in degrees
+    const PITCH_STEP: f32 = 5.0;
+    const MAX_PITCH: f32 = 60.0;
+
+    pub fn new(home: Option<LevelView>, allowed: LevelProjection) -> Self {
+        let mut s = Self {
+            scale: 1.0,
+            translate: V3::default(),
+            pitch: 0.0,
+            mode: allowed.modes.first().copied().unwrap_or_default(),
+            home,
+            allowed,
+            to_fit: false,
+        };
+        s.go_home();
+        s
+    }
+
+    fn go_home(&mut self) {
+        self.pitch = 0.0;
+        match &self.home {
+            Some(view) => {
+                self.scale = view.scale;
+                self.translate = V3::from(-view.center.x, -view.center.y, -view.center.z);
+            }
+            None => self.to_fit = true,
+        }
+    }
+
+    pub fn projection(&self, angle: f32, aspect_ratio: f32) -> Projection {
+        Projection::new(angle, self.scale, aspect_ratio)
+            .with_translate(self.translate.clone())
+            .with_mode(self.mode)
+            .with_pitch(self.pitch)
+    }
+
+    /// Whether the level lets the player turn around the y axis
+    pub fn can_yaw(&self) -> bool {
+        self.allowed.yaw
+    }
+
+    /// Zoom, pan and fit by the events, `rect` is where the view is painted
+    pub fn update(&mut self, events: &MyEvents, rect: egui::Rect, angle: f32, faces: &[Face]) {
+        let aspect_ratio = rect.aspect_ratio();
+        if events.home {
+            self.go_home();
+        }
+        if events.next_projection {
+            let modes = &self.allowed.modes;
+            if let Some(i) = modes.iter().position(|m| *m == self.mode) {
+                self.mode = modes[(i + 1) % modes.len()];
+            }
+        }
+        if self.allowed.pitch && (events.pitch_up || events.pitch_down) {
+            let step = if events.pitch_up {
+                Self::PITCH_STEP
+            } else {
+                -Self::PITCH_STEP
+            };
+            let max = Self::MAX_PITCH.to_radians();
+            self.pitch = (self.pitch + step.to_radians()).clamp(-max, max);
+        }
+        if events.fit || self.to_fit {
+            self.fit(angle, aspect_ratio, faces);
+        }
+
+        let (x, y) = events.pos;
+        if events.scrolled.1 != 0.0 && events.hovered && rect.contains(egui::pos2(x, y)) {
+            let (x, y) = Projection::pixel_to_clip(
+                x - rect.min.x,
+                y - rect.min.y,
+                rect.width(),
+                rect.height(),
+            );
+            let (x, y) = self.projection(angle, aspect_ratio).clip_to_view(x, y);
+            let zoom = (events.scrolled.1 * Self::WHEEL_ZOOM).exp();
+            self.zoom_at(zoom, x, y, angle, aspect_ratio);
+        }
+        if events.zoom_in {
+            self.zoom_at(Self::KEY_ZOOM, 0.0, 0.0, angle, aspect_ratio);
+        }
+        if events.zoom_out {
+            self.zoom_at(1.0 / Self::KEY_ZOOM, 0.0, 0.0, angle, aspect_ratio);
+        }
+
+        if events.pressed_m && events.moved != (0.0, 0.0) {
+            // the view is 2 high
+            let k = 2.0 / rect.height();
+            self.pan(events.moved.0 * k, -events.moved.1 * k, angle, aspect_ratio);
+        }
+    }
+
+    /// Put the faces that are drawn in the middle of the view
+    fn fit(&mut self, angle: f32, aspect_ratio: f32, faces: &[Face]) {
+        let points = faces
+            .iter()
+            .filter(|f| !f.skipped)
+            .flat_map(|f| [&f.pos11, &f.pos12, &f.pos21, &f.pos22]);
+        let fitted = self
+            .projection(angle, aspect_ratio)
+            .fit(points, Self::FIT_FILL);
+        self.scale = fitted.scale.clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        self.translate = fitted.translate;
+        self.to_fit = false;
+    }
+
+    /// Scale by `zoom`, the point at `(x, y)` in view space stays where it is
+    fn zoom_at(&mut self, zoom: f32, x: f32, y: f32, angle: f32, aspect_ratio: f32) {
+        let scale = (self.scale * zoom).clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        let k = scale / self.scale;
+        self.scale = scale;
+        self.pan(x * (1.0 - k), y * (1.0 - k), angle, aspect_ratio);
+    }
+
+    /// Move the scene by `(dx, dy)` in view space
+    fn pan(&mut self, dx: f32, dy: f32, angle: f32, aspect_ratio: f32) {
+        let d = self.projection(angle, aspect_ratio).view_to_world(dx, dy);
+        let t = &self.translate;
+        self.translate = V3::from(t.x + d.x, t.y + d.y, t.z + d.z);
+    }
+}
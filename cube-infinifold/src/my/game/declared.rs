@@ -0,0 +1,110 @@
+//! The levels declared in a json file instead of built as a library, see `my_items::scene`.
+//!
+//! A level in `levels.json` whose file ends with `.json` is one of them.
+//! Its scene is run behind a `MyInterface` as a library is, so the thread of the level
+//! does not tell the two apart.
+
+use levels_interface::{
+    my_interface::MyInterfaceBuilder, LevelInfo, MyInterface, Pointerable, Pointered,
+};
+use my_items::{marker::Marker, scene::Scene, serialize, Face, V3};
+
+struct Declared(Scene);
+
+impl Pointerable for Declared {}
+
+/// The interface of the level in the file at `path`, and the pointer to its scene
+pub fn load(path: &str) -> Result<(MyInterface, Pointered), String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("Cannot read {path}: {err}"))?;
+    let scene = serialize::from_json(&text)?;
+    let p = Box::leak(Box::new(Declared(scene))).get_pointer();
+    let mut builder =
+        MyInterfaceBuilder::new(|| true, LevelInfo::NONE, || Pointered::VOID, destory);
+    builder
+        .with_get_faces(get_faces)
+        .with_get_markers(get_markers)
+        .with_when_clicked(when_clicked)
+        .with_when_stood(when_stood)
+        .with_get_state(get_state)
+        .with_set_state(set_state);
+    Ok((builder.build(None), p))
+}
+
+fn destory(p: Pointered) {
+    if let Some(declared) = Declared::from_pointer(p) {
+        // leaked in `load`
+        drop(unsafe { Box::from_raw(declared as *mut Declared) });
+    }
+}
+
+fn get_faces(p: Pointered) -> Vec<Face> {
+    Declared::from_pointer(p).map_or(vec![], |d| d.0.get_faces())
+}
+
+fn get_markers(p: Pointered) -> Vec<Marker> {
+    Declared::from_pointer(p).map_or(vec![], |d| d.0.markers.clone())
+}
+
+fn when_clicked(p: Pointered, id: usize, _pos: V3) -> bool {
+    Declared::from_pointer(p).is_some_and(|d| !d.0.clicked(id).is_empty())
+}
+
+fn when_stood(p: Pointered, id: usize, _pos: V3) -> bool {
+    Declared::from_pointer(p).is_some_and(|d| !d.0.stood(id).is_empty())
+}
+
+/// The states of the parts of the puzzle, 4 bytes each
+fn get_state(p: Pointered) -> Vec<u8> {
+    Declared::from_pointer(p).map_or(vec![], |d| {
+        d.0.puzzle
+            .states()
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect()
+    })
+}
+
+fn set_state(p: Pointered, state: Vec<u8>) -> bool {
+    let states: Vec<_> = state
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Declared::from_pointer(p).is_some_and(|d| d.0.puzzle.set_states(&states))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_behind_the_interface() {
+        let path = std::env::temp_dir().join("cube-infinifold-declared.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "blocks": [{ "pos": { "x": 0, "y": 0, "z": 0 }, "size": { "x": 2, "y": 1, "z": 2 }, "walkable": true }],
+                "markers": [{ "kind": "Start", "pos": { "x": 1, "y": 1, "z": 1 } }],
+                "puzzle": {
+                    "parts": [{ "name": "plate", "kind": "Plate", "pos": { "x": 2, "y": 0, "z": 0 }, "size": { "x": 2, "y": 1, "z": 2 } }]
+                }
+            }"#,
+        )
+        .unwrap();
+        let (mif, p) = load(path.to_str().unwrap()).unwrap();
+        assert!((mif.is_ok)());
+        assert_eq!((mif.get_faces)(p).len(), 12);
+        assert_eq!((mif.get_markers)(p).len(), 1);
+        assert!(!(mif.when_stood)(p, 2, V3::from(1.0, 1.0, 1.0)));
+        assert!((mif.when_stood)(p, 8, V3::from(3.0, 1.0, 1.0)));
+        assert_eq!((mif.get_faces)(p)[8].pos11.y, 0.5);
+        // and back as it was
+        let pressed = (mif.get_state)(p);
+        assert_eq!(pressed, [1, 0, 0, 0]);
+        assert!((mif.set_state)(p, vec![0; 4]));
+        assert_eq!((mif.get_faces)(p)[8].pos11.y, 1.0);
+        assert!((mif.set_state)(p, pressed));
+        (mif.destory)(p);
+        assert!(load("no such level.json").is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -21,4 +21,10 @@ impl MyGameInfo {
         }
         None
     }
+    pub fn get_group<'a>(
+        &self,
+        lib: &'a my_levels_finder::CollectedGame,
+    ) -> Option<&'a my_levels_finder::CollectedGroup> {
+        lib.groups.get(&self.current_group_id?)
+    }
 }
@@ -0,0 +1,153 @@
+//! The moves of the player in a level, Ctrl+Z takes one back, Ctrl+Y or Ctrl+Shift+Z does it again
+//! and Ctrl+R starts the level again, as a move which is taken back as the others.
+//!
+//! A move is a turn to another snap target, a walk of the avatar, or a switch of the level.
+//! The level is kept as a snapshot after every move, its own state is by `get_state`.
+
+use my_items::walk::Step;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// the snap target the camera is at
+    pub angle: f32,
+    /// where the avatar stands
+    pub avatar: Option<Step>,
+    /// the state of the level, empty if it does not tell
+    pub level: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    /// the snapshot at the start of the level first, the one after the last move last
+    done: Vec<Snapshot>,
+    /// the moves taken back, the last one taken back last
+    undone: Vec<Snapshot>,
+}
+
+impl History {
+    /// Keep the level after a move, nothing is kept if it did not change
+    pub fn record(&mut self, now: Snapshot) {
+        if self.done.last() != Some(&now) {
+            self.done.push(now);
+            self.undone.clear();
+        }
+    }
+
+    /// The level before the last move, None if there was no move
+    pub fn undo(&mut self) -> Option<&Snapshot> {
+        if self.done.len() < 2 {
+            return None;
+        }
+        self.undone.extend(self.done.pop());
+        self.done.last()
+    }
+
+    /// The level after the last move taken back, None if there is none
+    pub fn redo(&mut self) -> Option<&Snapshot> {
+        self.done.push(self.undone.pop()?);
+        self.done.last()
+    }
+
+    /// The level at the start, kept as the last move
+    pub fn restart(&mut self) -> Option<&Snapshot> {
+        let first = self.done.first()?.clone();
+        self.record(first);
+        self.done.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_taken_back_and_again() {
+        let at = |angle: f32| Snapshot {
+            angle,
+            avatar: None,
+            level: vec![],
+        };
+        let mut history = History::default();
+        assert_eq!(history.undo(), None);
+        history.record(at(0.0));
+        history.record(at(0.0));
+        assert_eq!(history.undo(), None);
+        history.record(at(1.0));
+        history.record(at(2.0));
+        assert_eq!(history.undo(), Some(&at(1.0)));
+        assert_eq!(history.undo(), Some(&at(0.0)));
+        assert_eq!(history.redo(), Some(&at(1.0)));
+        // a new move forgets what was taken back
+        history.record(at(3.0));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(&at(1.0)));
+        assert_eq!(history.restart(), Some(&at(0.0)));
+        assert_eq!(history.redo(), None);
+        // and the restart taken back
+        assert_eq!(history.undo(), Some(&at(1.0)));
+        assert_eq!(history.redo(), Some(&at(0.0)));
+        assert_eq!(history.restart(), Some(&at(0.0)));
+        assert_eq!(history.undo(), Some(&at(1.0)));
+    }
+
+    #[test]
+    fn lever_and_plate_taken_back_and_again() {
+        use super::super::avatar::Avatar;
+        use my_items::{
+            puzzle::{Part, Puzzle},
+            scene::{Block, Scene},
+            V3,
+        };
+
+        let one = V3::from(1.0, 1.0, 1.0);
+        let mut scene = Scene {
+            blocks: vec![Block {
+                pos: V3::from(0.0, -1.0, 0.0),
+                size: V3::from(2.0, 1.0, 2.0),
+                walkable: true,
+                tag: None,
+            }],
+            puzzle: Puzzle::default()
+                .with_part(Part::lever("lever", V3::from(4.0, 0.0, 0.0), one.clone()))
+                .with_part(Part::plate("plate", V3::from(2.0, -1.0, 0.0), one))
+                .with_link("lever", "plate"),
+            ..Default::default()
+        };
+        let mut avatar = Avatar::new(&scene.get_faces(), &[]);
+        // what the game view does in a frame and after a move
+        let frame = |scene: &mut Scene, avatar: &mut Avatar| {
+            if let Some(at) = avatar.take_arrived() {
+                scene.stood(at.face);
+            }
+        };
+        let now = |scene: &Scene, avatar: &Avatar| Snapshot {
+            angle: 0.0,
+            avatar: avatar.at().cloned(),
+            level: scene.puzzle.states().iter().map(|s| *s as u8).collect(),
+        };
+        let restore = |scene: &mut Scene, avatar: &mut Avatar, snap: &Snapshot| {
+            let states: Vec<_> = snap.level.iter().map(|s| *s as u32).collect();
+            scene.puzzle.set_states(&states);
+            avatar.place(snap.avatar.clone(), &scene.get_faces());
+        };
+
+        let mut history = History::default();
+        frame(&mut scene, &mut avatar);
+        history.record(now(&scene, &avatar));
+        // the lever presses the plate, away from the avatar
+        assert_eq!(scene.clicked(8).len(), 2);
+        frame(&mut scene, &mut avatar);
+        let pressed = now(&scene, &avatar);
+        assert_eq!(pressed.level, [1, 1]);
+        history.record(pressed.clone());
+
+        let before = history.undo().unwrap().clone();
+        restore(&mut scene, &mut avatar, &before);
+        frame(&mut scene, &mut avatar);
+        assert_eq!(scene.puzzle.states(), [0, 0]);
+        let again = history.redo().unwrap().clone();
+        restore(&mut scene, &mut avatar, &again);
+        frame(&mut scene, &mut avatar);
+        assert_eq!(now(&scene, &avatar), pressed);
+    }
+}
@@ -0,0 +1,236 @@
+//! The inspector of the game view, F3 opens and closes it.
+//!
+//! It lists the faces of the level with their tag, `index`, musks and whether they are skipped,
+//! and the thresholds the level reported.
+//! The face of the row under the pointer is highlighted in the view, and the lines of its musks are drawn.
+//! The wireframe draws the edges of every face, the skipped ones too,
+//! and the musks can be turned off to see what they cut.
+//! The walk graph draws how the walkable faces are joined for the avatar, see `my_items::walk`.
+
+use eframe::egui;
+use my_items::{
+    walk::{self, WalkGraph},
+    Face, Projection,
+};
+
+#[derive(Default)]
+pub struct Inspector {
+    pub open: bool,
+    /// the edges of every face over the view
+    pub wireframe: bool,
+    /// draw the faces as if they had no musks
+    pub bypass_musks: bool,
+    /// the joins of the walkable faces over the view
+    pub walk_graph: bool,
+    /// the face of the row under the pointer
+    hovered: Option<usize>,
+    /// where the window was in the last frame, the view is not picked under it
+    rect: Option<egui::Rect>,
+}
+
+impl Inspector {
+    const EDGE: egui::Color32 = egui::Color32::from_rgb(230, 230, 230);
+    const SKIPPED_EDGE: egui::Color32 = egui::Color32::from_rgb(110, 110, 110);
+    const HOVERED_EDGE: egui::Color32 = egui::Color32::YELLOW;
+    const MUSK: egui::Color32 = egui::Color32::from_rgb(255, 120, 0);
+    const WALK: egui::Color32 = egui::Color32::from_rgb(0, 220, 255);
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        if !self.open {
+            self.hovered = None;
+            self.rect = None;
+        }
+    }
+
+    /// Whether the faces are drawn with their musks
+    pub fn musk_enabled(&self) -> bool {
+        !(self.open && self.bypass_musks)
+    }
+
+    /// The face of the row under the pointer, if the inspector is open
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered.filter(|_| self.open)
+    }
+
+    /// Whether the window covers the point of the screen
+    pub fn covers(&self, x: f32, y: f32) -> bool {
+        self.open && self.rect.is_some_and(|r| r.contains(egui::pos2(x, y)))
+    }
+
+    /// The window listing the faces, `thresholds` and `walk_angle` in radians
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        faces: &[Face],
+        thresholds: Option<&[f32]>,
+        walk: &WalkGraph,
+        walk_angle: f32,
+    ) {
+        if !self.open {
+            return;
+        }
+        let mut open = self.open;
+        let mut hovered = None;
+        let shown = egui::Window::new("Inspector")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                let thresholds = match thresholds {
+                    Some(t) => t
+                        .iter()
+                        .map(|a| format!("{:.1}", a.to_degrees()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    None => "not reported".to_string(),
+                };
+                ui.label(format!("Thresholds: {thresholds}"));
+                let joins = walk.faces().map(|id| walk.links(id).len()).sum::<usize>() / 2;
+                ui.label(format!(
+                    "Walk graph: {} faces, {joins} joins at {:.1}",
+                    walk.faces().count(),
+                    walk_angle.to_degrees()
+                ));
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.wireframe, "Wireframe");
+                    ui.checkbox(&mut self.bypass_musks, "Bypass musks");
+                    ui.checkbox(&mut self.walk_graph, "Walk graph");
+                });
+                ui.separator();
+                let skipped = faces.iter().filter(|f| f.skipped).count();
+                ui.label(format!("{} faces, {skipped} skipped", faces.len()));
+                ui.monospace(Self::HEADER);
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (id, f) in faces.iter().enumerate() {
+                        let mut text = egui::RichText::new(row(id, f)).monospace();
+                        if f.skipped {
+                            text = text.weak();
+                        }
+                        let label = egui::Label::new(text).sense(egui::Sense::hover());
+                        if ui.add(label).hovered() {
+                            hovered = Some(id);
+                        }
+                    }
+                });
+            });
+        self.open = open;
+        self.hovered = hovered;
+        self.rect = shown.map(|s| s.response.rect);
+    }
+
+    /// The wireframe, the walk graph and the musks of the hovered face over the view in `rect`
+    pub fn paint(
+        &self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        proj: &Projection,
+        faces: &[Face],
+        walk: &WalkGraph,
+    ) {
+        if !self.open {
+            return;
+        }
+        let to_screen = |x: f32, y: f32| {
+            let (x, y) = Projection::clip_to_pixel(x, y, rect.width(), rect.height());
+            rect.min + egui::vec2(x, y)
+        };
+        let corners = |f: &Face| {
+            [&f.pos11, &f.pos12, &f.pos22, &f.pos21].map(|p| {
+                let c = proj.face_clip(p, f.index);
+                to_screen(c.x, c.y)
+            })
+        };
+        if self.wireframe {
+            for f in faces {
+                let color = if f.skipped {
+                    Self::SKIPPED_EDGE
+                } else {
+                    Self::EDGE
+                };
+                let mut points = corners(f).to_vec();
+                points.push(points[0]);
+                painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+            }
+        }
+        if self.walk_graph {
+            let at = |p: &my_items::V3, f: &Face| {
+                let c = proj.face_clip(p, f.index);
+                to_screen(c.x, c.y)
+            };
+            let stroke = egui::Stroke::new(2.0, Self::WALK);
+            for id in walk.faces().filter(|id| *id < faces.len()) {
+                let f = &faces[id];
+                let centre = at(&walk::centre(f), f);
+                painter.circle_filled(centre, 4.0, Self::WALK);
+                // every join is drawn from both sides, up to where they meet
+                for link in walk.links(id) {
+                    painter.line_segment([centre, at(&link.from_pos, f)], stroke);
+                }
+            }
+        }
+        let Some(f) = self.hovered.and_then(|id| faces.get(id)) else {
+            return;
+        };
+        let mut points = corners(f).to_vec();
+        points.push(points[0]);
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(2.0, Self::HOVERED_EDGE),
+        ));
+        // the lines across the whole view, with a tick to the side that is kept
+        let far = rect.width() + rect.height();
+        for m in f.musks.iter().take(my_items::Musk::MAX) {
+            let ((px, py), (dx, dy)) = proj.view_musk(m);
+            let (qx, qy) = proj.view_to_clip(px + dx, py + dy);
+            let (px, py) = proj.view_to_clip(px, py);
+            let (p, q) = (to_screen(px, py), to_screen(qx, qy));
+            if q == p {
+                continue;
+            }
+            let d = (q - p).normalized();
+            let stroke = egui::Stroke::new(1.5, Self::MUSK);
+            painter.line_segment([p - d * far, p + d * far], stroke);
+            // the right of the direction is kept, y goes down on the screen
+            painter.line_segment([p, p + egui::vec2(-d.y, d.x) * 12.0], stroke);
+        }
+    }
+
+    const HEADER: &'static str = "  id tag                  index skip musks lit";
+}
+
+/// A row of the list of faces, the same columns as `Inspector::HEADER`
+fn row(id: usize, f: &Face) -> String {
+    let mut tag = f.tag.clone().unwrap_or_else(|| "-".to_string());
+    if tag.chars().count() > 20 {
+        tag = tag.chars().take(19).chain(['…']).collect();
+    }
+    format!(
+        "{id:>4} {tag:<20} {:>5.2} {:>4} {:>5} {:>3}",
+        f.index,
+        if f.skipped { "yes" } else { "" },
+        f.musks.len(),
+        if f.lit { "yes" } else { "" },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use my_items::{Musk, Pillar, V3};
+
+    #[test]
+    fn rows_line_up_with_the_header() {
+        let mut faces = Pillar::new_upright(V3::from(0.0, 0.0, 0.0), V3::from(1.0, 1.0, 1.0))
+            .with_tag("a very long name of a pillar")
+            .into_vec();
+        faces[0] = faces[0]
+            .clone()
+            .with_musk(Musk::new_on_screen(0.0, 0.0, 1.0, 0.0));
+        faces[1].skipped = true;
+        for (id, f) in faces.iter().enumerate() {
+            assert_eq!(row(id, f).chars().count(), Inspector::HEADER.len());
+        }
+        assert!(row(0, &faces[0]).ends_with("    1    "));
+        assert!(row(1, &faces[1]).contains(" yes "));
+    }
+}
@@ -1,27 +1,49 @@
 use std::{sync::mpsc, thread};
 
-use levels_interface::{self, MyInterface, Pointered};
-use my_items::Face;
+use levels_interface::{self, LevelProjection, LevelStyle, LevelView, MyInterface, Pointered};
+use my_items::{marker::Marker, walk::WalkGraph, Face, V3};
 
 use crate::game_options::MyGameOption;
 
+use super::declared;
+
 pub struct Level {
     handler: thread::JoinHandle<()>,
     sender: mpsc::Sender<Actions>,
     cb_recver: mpsc::Receiver<Callback>,
 
     faces: Vec<my_items::Face>,
+    /// the start and the goal, fetched with the faces
+    markers: Vec<Marker>,
+    /// where the camera starts, None to fit the faces
+    view: Option<LevelView>,
+    /// the angles where the level changes, empty if it does not tell
+    thresholds: Vec<f32>,
+    /// the projections and turning the level allows
+    projection: LevelProjection,
+    /// whether the level allows the light and the outlines, and its own theme
+    style: LevelStyle,
 
     is_ok: bool,
 }
 enum Actions {
     GetFaces,
     Angled(f32),
+    Clicked(usize, V3),
+    Stood(usize, V3),
+    GetState,
+    SetState(Vec<u8>),
+    WalkGraph(WalkGraph),
     Destory,
 }
 enum Callback {
     Angled(bool),
-    Faces(Vec<Face>),
+    Clicked(bool),
+    Stood(bool),
+    State(Vec<u8>),
+    StateSet(bool),
+    Faces(Vec<Face>, Vec<Marker>),
+    View(Option<LevelView>, Vec<f32>, LevelProjection, LevelStyle),
 }
 
 impl Level {
@@ -37,17 +59,40 @@ impl Level {
             if newed.is_none() {
                 return;
             }
-            let (mif, p, faces) = newed.unwrap();
-            cb_sender.send(Callback::Faces(faces)).expect("Error");
+            let (mif, p, faces, view, thresholds, projection, style) = newed.unwrap();
+            let markers = my_get_markers(&mif, p);
+            cb_sender
+                .send(Callback::Faces(faces, markers))
+                .expect("Error");
+            cb_sender
+                .send(Callback::View(view, thresholds, projection, style))
+                .expect("Error");
             while let Ok(action) = recver.recv() {
                 match action {
                     Actions::GetFaces => {
                         let faces = my_get_faces(&mif, p);
-                        cb_sender.send(Callback::Faces(faces)).expect("Send Error");
+                        let markers = my_get_markers(&mif, p);
+                        cb_sender
+                            .send(Callback::Faces(faces, markers))
+                            .expect("Send Error");
                     }
                     Actions::Angled(angle) => cb_sender
                         .send(Callback::Angled(my_when_angled(&mif, p, angle)))
                         .expect("Send Error"),
+                    Actions::Clicked(id, pos) => cb_sender
+                        .send(Callback::Clicked(my_when_clicked(&mif, p, id, pos)))
+                        .expect("Send Error"),
+                    Actions::Stood(id, pos) => cb_sender
+                        .send(Callback::Stood(my_when_stood(&mif, p, id, pos)))
+                        .expect("Send Error"),
+                    Actions::GetState => cb_sender
+                        .send(Callback::State((mif.get_state)(p)))
+                        .expect("Send Error"),
+                    Actions::SetState(state) => cb_sender
+                        .send(Callback::StateSet((mif.set_state)(p, state)))
+                        .expect("Send Error"),
+                    // nothing is sent back
+                    Actions::WalkGraph(graph) => (mif.set_walk_graph)(p, graph),
                     Actions::Destory => break,
                 }
                 if !(mif.is_ok)() {
@@ -57,21 +102,48 @@ impl Level {
             // destory
             my_destory(mif, p);
         });
-        if let Ok(Callback::Faces(faces)) = cb_recver.recv() {
-            Some(Self {
-                handler,
-                sender,
-                cb_recver,
-                faces,
-                is_ok: true,
-            })
-        } else {
-            None
-        }
+        let Ok(Callback::Faces(faces, markers)) = cb_recver.recv() else {
+            return None;
+        };
+        let Ok(Callback::View(view, thresholds, projection, style)) = cb_recver.recv() else {
+            return None;
+        };
+        Some(Self {
+            handler,
+            sender,
+            cb_recver,
+            faces,
+            markers,
+            view,
+            thresholds,
+            projection,
+            style,
+            is_ok: true,
+        })
     }
     pub fn get_faces(&self) -> &Vec<my_items::Face> {
         &self.faces
     }
+    /// The start and the goal on the faces
+    pub fn get_markers(&self) -> &[Marker] {
+        &self.markers
+    }
+    /// Where the level wants the camera to start
+    pub fn get_view(&self) -> Option<&LevelView> {
+        self.view.as_ref()
+    }
+    /// The angles where the level changes, None if it does not tell
+    pub fn get_thresholds(&self) -> Option<&[f32]> {
+        (!self.thresholds.is_empty()).then_some(self.thresholds.as_slice())
+    }
+    /// How the level may be looked at
+    pub fn get_projection(&self) -> &LevelProjection {
+        &self.projection
+    }
+    /// Whether the level allows the light and the outlines, and its own theme
+    pub fn get_style(&self) -> &LevelStyle {
+        &self.style
+    }
     pub fn when_angled(&mut self, angle: f32) -> bool {
         if let Err(err) = self.sender.send(Actions::Angled(angle)) {
             println!("{}", err.to_string());
@@ -80,15 +152,7 @@ impl Level {
         }
         if let Ok(Callback::Angled(angled)) = self.cb_recver.recv() {
             if angled {
-                if let Err(err) = self.sender.send(Actions::GetFaces) {
-                    println!("{}", err.to_string());
-                    self.is_ok = false;
-                    return false;
-                }
-                if let Ok(Callback::Faces(faces)) = self.cb_recver.recv() {
-                    self.faces = faces;
-                    return true;
-                }
+                return self.refresh_faces();
             } else {
                 return false;
             }
@@ -97,6 +161,83 @@ impl Level {
         self.is_ok = false;
         false
     }
+    /// Tell the level that the face `id` is clicked at `pos`, true if the faces are changed
+    pub fn when_clicked(&mut self, id: usize, pos: V3) -> bool {
+        if let Err(err) = self.sender.send(Actions::Clicked(id, pos)) {
+            println!("{err}");
+            self.is_ok = false;
+            return false;
+        }
+        if let Ok(Callback::Clicked(changed)) = self.cb_recver.recv() {
+            return changed && self.refresh_faces();
+        }
+        // error here
+        self.is_ok = false;
+        false
+    }
+    /// Tell the level that the avatar stepped onto the face `id` at `pos`, true if the faces are changed
+    pub fn when_stood(&mut self, id: usize, pos: V3) -> bool {
+        if let Err(err) = self.sender.send(Actions::Stood(id, pos)) {
+            println!("{err}");
+            self.is_ok = false;
+            return false;
+        }
+        if let Ok(Callback::Stood(changed)) = self.cb_recver.recv() {
+            return changed && self.refresh_faces();
+        }
+        // error here
+        self.is_ok = false;
+        false
+    }
+    /// The state of the level to be set again by `set_state`, empty if it does not tell
+    pub fn get_state(&mut self) -> Vec<u8> {
+        if let Err(err) = self.sender.send(Actions::GetState) {
+            println!("{err}");
+            self.is_ok = false;
+            return vec![];
+        }
+        if let Ok(Callback::State(state)) = self.cb_recver.recv() {
+            return state;
+        }
+        // error here
+        self.is_ok = false;
+        vec![]
+    }
+    /// Set the level to a state it was in, true if the faces are changed
+    pub fn set_state(&mut self, state: Vec<u8>) -> bool {
+        if let Err(err) = self.sender.send(Actions::SetState(state)) {
+            println!("{err}");
+            self.is_ok = false;
+            return false;
+        }
+        if let Ok(Callback::StateSet(changed)) = self.cb_recver.recv() {
+            return changed && self.refresh_faces();
+        }
+        // error here
+        self.is_ok = false;
+        false
+    }
+    /// Tell the level how its walkable faces are joined now
+    pub fn set_walk_graph(&mut self, graph: WalkGraph) {
+        if let Err(err) = self.sender.send(Actions::WalkGraph(graph)) {
+            println!("{err}");
+            self.is_ok = false;
+        }
+    }
+    fn refresh_faces(&mut self) -> bool {
+        if let Err(err) = self.sender.send(Actions::GetFaces) {
+            println!("{err}");
+            self.is_ok = false;
+            return false;
+        }
+        if let Ok(Callback::Faces(faces, markers)) = self.cb_recver.recv() {
+            self.faces = faces;
+            self.markers = markers;
+            return true;
+        }
+        self.is_ok = false;
+        false
+    }
     #[allow(dead_code)]
     pub fn destory(self) {
         let _ = self.sender.send(Actions::Destory);
@@ -108,14 +249,37 @@ impl Level {
     }
 }
 
-fn my_new(path: String) -> Option<(MyInterface, Pointered, Vec<Face>)> {
-    // todo!("load the lib and call init()");
-    match MyInterface::from_lib_safe(path) {
-        Ok(mif) => {
+#[allow(clippy::type_complexity)]
+fn my_new(
+    path: String,
+) -> Option<(
+    MyInterface,
+    Pointered,
+    Vec<Face>,
+    Option<LevelView>,
+    Vec<f32>,
+    LevelProjection,
+    LevelStyle,
+)> {
+    // a level declared in a file is run behind the same interface
+    let loaded = if path.ends_with(".json") {
+        declared::load(&path)
+    } else {
+        // todo!("load the lib and call init()");
+        MyInterface::from_lib_safe(path).map(|mif| {
             // todo!("call new() and save self to Level");
             let p = (mif.new)();
+            (mif, p)
+        })
+    };
+    match loaded {
+        Ok((mif, p)) => {
             let faces = (mif.get_faces)(p);
-            Some((mif, p, faces))
+            let view = (mif.get_view)(p);
+            let thresholds = (mif.get_thresholds)(p);
+            let projection = (mif.get_projection)(p);
+            let style = (mif.get_style)(p);
+            Some((mif, p, faces, view, thresholds, projection, style))
         }
         Err(err) => {
             println!("Error: {}", err);
@@ -135,6 +299,18 @@ fn my_when_angled(mif: &MyInterface, p: Pointered, angle: f32) -> bool {
     (mif.when_angled)(p, angle)
 }
 
+fn my_when_clicked(mif: &MyInterface, p: Pointered, id: usize, pos: V3) -> bool {
+    (mif.when_clicked)(p, id, pos)
+}
+
+fn my_when_stood(mif: &MyInterface, p: Pointered, id: usize, pos: V3) -> bool {
+    (mif.when_stood)(p, id, pos)
+}
+
 fn my_get_faces(mif: &MyInterface, p: Pointered) -> Vec<Face> {
     (mif.get_faces)(p)
 }
+
+fn my_get_markers(mif: &MyInterface, p: Pointered) -> Vec<Marker> {
+    (mif.get_markers)(p)
+}
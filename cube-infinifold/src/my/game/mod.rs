@@ -1,99 +1,534 @@
-use eframe::{
-    egui::{self, mutex::Mutex},
-    egui_glow,
-};
-use std::sync::Arc;
+use eframe::egui;
+use std::{sync::Arc, time::Duration};
 
-use crate::game_options::MyGameOption;
+use crate::game_options::{media, MyGameOption};
 
 use super::{
-    gl_views::{GLFacesView, GLGameBase, GlPaintOptions},
-    MyViewImpl, UIWidget,
+    gl_views::GlPaintOptions,
+    render::{self, Capture, FacesView, PendingCapture},
+    theme, MyViewImpl, UIWidget,
 };
 
 // mod penrose_triangle;
+mod avatar;
+mod camera;
+mod declared;
 pub mod game_info;
+mod history;
+mod inspector;
 mod load_level;
+pub mod morph;
+mod records;
+pub mod rotation;
 
 pub struct MyGameView {
-    game_view: Arc<Mutex<GLFacesView>>,
+    game_view: Arc<dyn FacesView>,
     angle: f32,
+    rotation: rotation::Rotation,
+    /// where the camera snaps to, and the thresholds of the level
+    snap: rotation::Snap,
+    camera: camera::Camera,
+    /// the faces drawn while the level changes its state
+    tween: morph::Tween,
     btns: Vec<UIWidget>,
     change_to: Option<String>,
     // faces: Vec<items::Face>,
     // level: penrose_triangle::PenroseTriangle,
     level: load_level::Level,
+    /// the face under the pointer
+    hovered: Option<my_items::pick::Picked>,
+    inspector: inspector::Inspector,
+    /// the theme of the level, or of its group
+    theme: my_items::theme::Theme,
+    /// the player walking on the level
+    avatar: avatar::Avatar,
+    /// the joins of the walkable faces, as seen at `walk_graph_angle`
+    walk_graph: my_items::walk::WalkGraph,
+    /// the angle where the camera last passed a snap target or stopped turning
+    walk_graph_angle: f32,
+    /// the last snap target the camera passed, a turn of the run
+    walk_angle: f32,
+    /// the angle, pitch and projection the graph is built for, None to build it again
+    walk_built_for: Option<(f32, f32, my_items::ProjectionMode)>,
+    /// the markers of the level, with the faces they are on and the squares drawn for them
+    markers: Vec<(my_items::marker::Marker, usize, my_items::Face)>,
+    /// the id of the first marker square in the faces of the view
+    marker_offset: usize,
+    /// the path of the library, to keep the records of the level
+    level_path: String,
+    /// the time played and the snap targets turned to, until the goal is reached
+    run: records::Record,
+    /// the run and the best before it, once the goal is reached
+    finished: Option<(records::Record, Option<records::Record>)>,
+    /// the level after every move, to take the moves back
+    history: history::History,
+    /// whether the player moved since the level was kept in the history
+    moved: bool,
 }
 
 impl MyGameView {
     pub fn new(
-        game_view: Arc<Mutex<GLFacesView>>,
+        game_view: Arc<dyn FacesView>,
         ctx: &eframe::egui::Context,
         option: &MyGameOption,
     ) -> Option<MyGameView> {
+        // let level = penrose_triangle::PenroseTriangle::new();
+        let level = load_level::Level::new(option)?;
+        let theme = level
+            .get_style()
+            .theme
+            .clone()
+            .or_else(|| {
+                let group = option.game_info.get_group(&option.game_library)?;
+                Some(group.theme.clone())
+            })
+            .unwrap_or_default();
         let btns = vec![UIWidget::new(vec![
             "file://assets/ui/unselected.png",
             "file://assets/ui/selected.png",
         ])
         .with_font(egui::Color32::GREEN, 28.0, egui::FontFamily::Proportional)
+        .with_palette(&theme.palette)
         .with_size(200.0, 50.0)
         .load(ctx)];
-        // let level = penrose_triangle::PenroseTriangle::new();
-        let level = load_level::Level::new(option)?;
-        game_view.lock().set_faces(level.get_faces().clone());
+        let avatar = avatar::Avatar::new(level.get_faces(), level.get_markers());
+        let markers = place_markers(level.get_faces(), level.get_markers());
+        let mut faces = level.get_faces().clone();
+        let marker_offset = faces.len();
+        faces.extend(markers.iter().map(|(_, _, f)| f.clone()));
+        faces.append(&mut avatar.faces());
+        game_view.set_faces(faces);
+        let level_path = option
+            .game_info
+            .get_library_path(&option.game_library)
+            .unwrap_or(String::from("testpenrose.dll"));
+        let tween = morph::Tween::new(level.get_faces().clone());
+        let camera = camera::Camera::new(level.get_view().cloned(), level.get_projection().clone());
+        let snap = rotation::Snap::new(level.get_thresholds(), option.rotation.snap_step);
+        let angle = 0_f32.to_radians();
         Some(Self {
             game_view,
-            angle: 0_f32.to_radians(),
+            angle,
+            rotation: rotation::Rotation::new(angle),
+            snap,
+            camera,
+            tween,
             btns: btns,
             change_to: None,
             level,
+            hovered: None,
+            inspector: Default::default(),
+            theme,
+            avatar,
+            walk_graph: Default::default(),
+            walk_graph_angle: angle,
+            walk_angle: angle,
+            walk_built_for: None,
+            markers,
+            marker_offset,
+            level_path,
+            run: records::Record {
+                time: Duration::ZERO,
+                turns: 0,
+            },
+            finished: None,
+            history: Default::default(),
+            // the level at the start is kept first
+            moved: true,
         })
     }
 
     fn paint_opengl(&mut self, ui: &mut egui::Ui, option: &MyGameOption) {
         self.calc_angle(option);
 
-        if self.level.when_angled(self.angle) {
-            self.game_view
-                .lock()
-                .set_faces(self.level.get_faces().clone());
+        let mode = self.camera.mode;
+        self.camera.update(
+            &option.events,
+            ui.max_rect(),
+            self.angle,
+            self.level.get_faces(),
+        );
+        if self.camera.mode != mode {
+            let _ = option
+                .messages
+                .send
+                .send((format!("Projection: {}", self.camera.mode.name()), 1500));
         }
-
-        let game_view = self.game_view.clone();
-        let option = GlPaintOptions {
+        // the settings of the player, where the level allows them
+        let style = self.level.get_style();
+        let shading = my_items::shading::Shading {
+            light: option.shading.light.clone().filter(|_| style.lighting),
+            outline: option.shading.outline.clone().filter(|_| style.outlines),
+        };
+        let paint_option = GlPaintOptions {
             angle: self.angle,
-            scale: 0.05,
-            aspect_ratio: ui.max_rect().aspect_ratio(),
-            ..Default::default()
+            pitch: self.camera.pitch,
+            mode: self.camera.mode,
+            translate: self.camera.translate.clone(),
+            scale: self.camera.scale,
+            aspect_ratio: render::aspect_ratio_of(ui.ctx(), ui.max_rect()),
+            shading,
         };
+        let projection = paint_option.projection();
+        self.pick_face(ui, option, &paint_option);
+        if option.screenshot.clean_capture {
+            self.capture(ui, option, &paint_option);
+        }
+        let morphed = self
+            .tween
+            .update(&option.morph, self.angle, option.dt)
+            .is_some();
+        self.update_walk_graph(&projection);
+        let walked = self.avatar.update(
+            self.level.get_faces(),
+            &self.walk_graph,
+            &projection,
+            &option.events,
+            option.dt,
+        );
+        if let Some(at) = self.avatar.take_arrived() {
+            if self.level.when_stood(at.face, at.pos) {
+                self.faces_changed();
+            }
+        }
+        self.moved |= walked;
+        if morphed || walked {
+            let mut faces = self.tween.current().clone();
+            self.marker_offset = faces.len();
+            faces.extend(self.markers.iter().map(|(_, _, f)| f.clone()));
+            faces.append(&mut self.avatar.faces());
+            self.game_view.set_faces(faces);
+        }
+        self.check_goal(option);
+        self.update_history(option);
+        self.game_view
+            .set_musk_enabled(self.inspector.musk_enabled());
 
-        let callback = egui::PaintCallback {
-            rect: ui.max_rect(),
-            callback: std::sync::Arc::new(egui_glow::CallbackFn::new(move |_info, painter| {
-                game_view.lock().paint(painter.gl(), &option);
-            })),
-        };
+        let rect = ui.max_rect();
+        theme::paint_background(
+            ui.painter(),
+            rect,
+            &self.theme.background,
+            &projection,
+            ui.input(|i| i.time),
+        );
+        let callback = self.game_view.clone().paint_callback(rect, paint_option);
         ui.painter().add(callback);
+        self.inspector.paint(
+            &ui.painter_at(rect),
+            rect,
+            &projection,
+            self.level.get_faces(),
+            &self.walk_graph,
+        );
     }
 
-    fn calc_angle(&mut self, option: &MyGameOption) {
-        // if we use the mouse to control the angle
-        // drag distance * 0.01 is the angle
-        // however, we can use the keyboard to control the angle
-        // when we press the key 'L', the angle will snap to previous 45 * n degree
-        // when we press the key 'R', the angle will snap to next 45 * n degree
-        let angle = self.angle.to_degrees();
-        let angle = if option.events.pressed_l {
-            angle + option.events.moved.0 * 0.05
-        } else if option.events.left {
-            ((angle - 23.0) / 45.0).round() * 45.0
-        } else if option.events.right {
-            ((angle + 23.0) / 45.0).round() * 45.0
+    /// Join the walkable faces again if they changed, or the camera passed a snap target,
+    /// stopped turning or looks from elsewhere, and tell the level
+    fn update_walk_graph(&mut self, projection: &my_items::Projection) {
+        let angle = self.walk_graph_angle;
+        let key = (angle, projection.pitch, projection.mode);
+        if self.walk_built_for == Some(key) {
+            return;
+        }
+        let projection = my_items::Projection {
+            angle,
+            ..projection.clone()
+        };
+        self.walk_graph = my_items::walk::WalkGraph::new(self.level.get_faces(), &projection);
+        self.walk_built_for = Some(key);
+        self.avatar.keep_joined(&self.walk_graph);
+        self.level.set_walk_graph(self.walk_graph.clone());
+    }
+
+    /// Take back or do again a move, or start again, by the keys,
+    /// and keep the level in the history once a move is over
+    fn update_history(&mut self, option: &MyGameOption) {
+        let events = &option.events;
+        if events.restart {
+            self.restart();
+            return;
+        }
+        // the run is over, the moves are kept as they are
+        let snapshot = match self.finished {
+            None if events.undo => self.history.undo().cloned(),
+            None if events.redo => self.history.redo().cloned(),
+            _ => None,
+        };
+        if let Some(snapshot) = snapshot {
+            self.restore(snapshot);
+            return;
+        }
+        if self.moved && self.rotation.is_still() && !self.avatar.is_walking() {
+            self.moved = false;
+            let now = history::Snapshot {
+                angle: self.walk_angle,
+                avatar: self.avatar.at().cloned(),
+                level: self.level.get_state(),
+            };
+            self.history.record(now);
+        }
+    }
+
+    /// Back to the start of the level, with a new run
+    fn restart(&mut self) {
+        if let Some(snapshot) = self.history.restart().cloned() {
+            self.restore(snapshot);
+        }
+        self.run = records::Record {
+            time: Duration::ZERO,
+            turns: 0,
+        };
+        self.finished = None;
+    }
+
+    /// Turn the camera, set the level and put the avatar as they were in `snapshot`
+    fn restore(&mut self, snapshot: history::Snapshot) {
+        let from = self.angle;
+        self.angle = snapshot.angle;
+        self.rotation = rotation::Rotation::new(snapshot.angle);
+        self.walk_graph_angle = snapshot.angle;
+        self.walk_angle = snapshot.angle;
+        let mut changed = self.level.set_state(snapshot.level);
+        // the levels changing by the angle alone are set by it
+        if self.camera.can_yaw() {
+            for angle in self.snap.reported(from, snapshot.angle) {
+                changed |= self.level.when_angled(angle);
+            }
+        }
+        if changed {
+            self.faces_changed();
+        }
+        self.avatar.place(snapshot.avatar, self.level.get_faces());
+    }
+
+    /// Count the time played, and keep the run when the avatar reaches the goal
+    fn check_goal(&mut self, option: &MyGameOption) {
+        if self.finished.is_some() {
+            return;
+        }
+        self.run.time += option.dt;
+        // on the square drawn for the goal
+        let reached = self.markers.iter().any(|(m, id, f)| {
+            m.kind == my_items::marker::MarkerKind::Goal
+                && self
+                    .avatar
+                    .reached(*id, &m.pos, length(&f.pos11, &f.pos12) / 2.0)
+        });
+        if !reached {
+            return;
+        }
+        let mut records = records::Records::load();
+        let before = records.add(&self.level_path, self.run);
+        if let Err(err) = records.save() {
+            let _ = option.messages.send.send((err, 2500));
+        }
+        self.finished = Some((self.run, before));
+    }
+
+    /// The time and the turns of the finished run, and the way back
+    fn show_finished(&mut self, ctx: &egui::Context) {
+        let Some((run, before)) = self.finished else {
+            return;
+        };
+        let best = before.map_or(run, |b| b.best(run));
+        let new_best = before.is_none_or(|b| run.time < b.time || run.turns < b.turns);
+        egui::Window::new("完成")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "用时 {:.1} 秒, 转动 {} 次",
+                    run.time.as_secs_f32(),
+                    run.turns
+                ));
+                ui.label(format!(
+                    "最佳 {:.1} 秒, 转动 {} 次{}",
+                    best.time.as_secs_f32(),
+                    best.turns,
+                    if new_best { " (新纪录)" } else { "" }
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("重来").clicked() {
+                        self.restart();
+                    }
+                    if ui.button("选关").clicked() {
+                        self.change_to = Some(String::from("Select Group"));
+                    }
+                    if ui.button("返回").clicked() {
+                        self.change_to = Some(String::from("Menu"));
+                    }
+                });
+            });
+    }
+
+    /// Draw the faces alone offscreen at the size of the settings, and save them
+    fn capture(&self, ui: &egui::Ui, option: &MyGameOption, paint_option: &GlPaintOptions) {
+        let settings = &option.settings.capture;
+        let background = if settings.transparent {
+            egui::Color32::TRANSPARENT
+        } else {
+            // only the color of the background, its lines and dots are drawn by egui
+            self.theme
+                .background
+                .fill()
+                .map_or(ui.visuals().panel_fill, theme::color32)
+        };
+        let capture = Capture {
+            width: settings.width,
+            height: settings.height,
+            supersample: settings.supersample,
+            background: background.to_array().map(|c| c as f32 / 255.0),
+        };
+        // the outlines are as wide in the picture as they are in the view
+        let mut paint_option = paint_option.clone();
+        let view_height = ui.max_rect().height() * ui.ctx().pixels_per_point();
+        if let Some(outline) = &mut paint_option.shading.outline {
+            outline.width *= settings.height as f32 / view_height.max(1.0);
+        }
+        let sender = option.messages.send.clone();
+        let done = Box::new(move |image: Result<egui::ColorImage, String>| {
+            let image = match image {
+                Ok(image) => image,
+                Err(err) => {
+                    let _ = sender.send((err, 2500));
+                    return;
+                }
+            };
+            let t = chrono::offset::Local::now().to_string().replace(":", "_");
+            let name = format!("output/capture-{t}");
+            std::thread::spawn(move || {
+                if let Some(err) = media::save_image(&name, "png", &image) {
+                    println!("Cannot save image! Error: {err}");
+                } else {
+                    let _ = sender.send((format!("{name}.png saved successfully"), 2000));
+                }
+            });
+        });
+        // the face under the pointer is not highlighted in the picture
+        self.game_view.set_highlighted(None);
+        self.game_view.capture(PendingCapture {
+            capture,
+            option: paint_option,
+            done,
+        });
+        let _ = option.messages.send.send((
+            format!("Capturing {}x{}", settings.width, settings.height),
+            500,
+        ));
+    }
+
+    /// Find the face under the pointer, highlight it, and walk there or tell the level when it is clicked
+    fn pick_face(&mut self, ui: &egui::Ui, option: &MyGameOption, paint_option: &GlPaintOptions) {
+        let rect = ui.max_rect();
+        let (x, y) = option.events.pos;
+        let over_view = rect.contains(egui::pos2(x, y)) && !self.inspector.covers(x, y);
+        self.hovered = if option.events.hovered && over_view {
+            let (x, y) = my_items::Projection::pixel_to_clip(
+                x - rect.min.x,
+                y - rect.min.y,
+                rect.width(),
+                rect.height(),
+            );
+            self.game_view.pick(paint_option, x, y)
         } else {
-            angle
+            None
         };
-        self.angle = angle.to_radians();
+        // a row of the inspector under the pointer is highlighted instead
+        let highlighted = self.inspector.hovered();
+        self.game_view
+            .set_highlighted(highlighted.or(self.hovered.as_ref().map(|p| p.id)));
+
+        if !option.events.clicked_l {
+            return;
+        }
+        // the faces going away while morphing are not in the level any more
+        let faces = self.level.get_faces();
+        let picked = self.hovered.as_ref().and_then(|p| {
+            let Some(i) = p.id.checked_sub(self.marker_offset) else {
+                return (p.id < faces.len()).then(|| (p.id, p.pos.clone()));
+            };
+            // a marker is clicked as the face it is on
+            let (_, id, _) = self.markers.get(i)?;
+            let face = faces.get(*id)?;
+            Some((
+                *id,
+                my_items::walk::point(face, my_items::walk::param(face, &p.pos)),
+            ))
+        });
+        if let Some((id, pos)) = picked {
+            if self.walk_graph.contains(id) {
+                self.avatar.walk_to(&self.walk_graph, id, pos.clone());
+            }
+            if self.level.when_clicked(id, pos) {
+                self.faces_changed();
+            }
+        }
+    }
+
+    /// Turn the view, and tell the level every threshold passed on the way
+    fn calc_angle(&mut self, option: &MyGameOption) {
+        let from = self.angle;
+        if !self.camera.can_yaw() {
+            return;
+        }
+        self.rotation
+            .update(&option.events, &option.rotation, &self.snap, option.dt);
+        self.angle = self.rotation.angle;
+
+        let touched = self.snap.touched(from, self.angle);
+        // the joins are kept while turning, and are the ones on the screen at a snap target
+        // or once the angle stays, even under a held drag
+        if !touched.is_empty() || from == self.angle {
+            self.walk_graph_angle = self.angle;
+        }
+        if let Some(angle) = touched.last() {
+            if *angle != self.walk_angle {
+                self.moved = true;
+                if self.finished.is_none() {
+                    self.run.turns += 1;
+                }
+            }
+            self.walk_angle = *angle;
+        }
+        let mut changed = false;
+        for angle in self.snap.reported(from, self.angle) {
+            changed |= self.level.when_angled(angle);
+        }
+        if changed {
+            self.faces_changed();
+        }
     }
+
+    /// The level changed its faces, tween to them and find where the avatar stands on them
+    fn faces_changed(&mut self) {
+        self.tween.start(self.level.get_faces().clone());
+        self.avatar.faces_changed(self.level.get_faces());
+        self.markers = place_markers(self.level.get_faces(), self.level.get_markers());
+        self.walk_built_for = None;
+        self.moved = true;
+    }
+}
+
+/// Find the faces the markers are on, and the squares drawn for them
+fn place_markers(
+    faces: &[my_items::Face],
+    markers: &[my_items::marker::Marker],
+) -> Vec<(my_items::marker::Marker, usize, my_items::Face)> {
+    markers
+        .iter()
+        .filter_map(|m| {
+            let id = m.find_face(faces)?;
+            let f = &faces[id];
+            let size = length(&f.pos11, &f.pos12).min(length(&f.pos11, &f.pos21)) * 0.6;
+            Some((m.clone(), id, m.face(f, size)))
+        })
+        .collect()
+}
+
+fn length(a: &my_items::V3, b: &my_items::V3) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
 }
 
 impl MyViewImpl for MyGameView {
@@ -104,7 +539,7 @@ impl MyViewImpl for MyGameView {
 
     fn to_change(&self, _option: &mut MyGameOption) -> Option<String> {
         match self.change_to.clone()?.as_str() {
-            "Logo" | "Menu" | "Exit" => self.change_to.clone(),
+            "Logo" | "Menu" | "Exit" | "Select Group" => self.change_to.clone(),
             "Start" | "Game" => self.change_to.clone(),
             "Error" => Some("Menu".to_string()),
             s => {
@@ -123,7 +558,18 @@ impl MyViewImpl for MyGameView {
             self.change_to = Some(String::from("Error"));
             return;
         }
+        if option.events.inspector {
+            self.inspector.toggle();
+        }
         self.paint_opengl(ui, option);
+        self.inspector.show(
+            ui.ctx(),
+            self.level.get_faces(),
+            self.level.get_thresholds(),
+            &self.walk_graph,
+            self.walk_graph_angle,
+        );
+        self.show_finished(ui.ctx());
         if self.btns[0].button(ui, "返回", 0, 1).clicked() {
             println!("返回");
             self.change_to = Some(String::from("Menu"));
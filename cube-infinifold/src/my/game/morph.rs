@@ -0,0 +1,118 @@
+//! Tweening the faces when the level changes its state.
+//!
+//! The faces are matched by `my_items::morph`, this keeps the time of the tween.
+
+use std::time::Duration;
+
+use my_items::{
+    morph::{Appear, Morph},
+    Face,
+};
+
+use super::rotation::Easing;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MorphSettings {
+    /// how long a change of the faces takes, zero to change at once
+    pub duration: Duration,
+    pub easing: Easing,
+    /// how the faces without a match come and go
+    pub appear: Appear,
+}
+
+impl Default for MorphSettings {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_millis(400),
+            easing: Easing::default(),
+            appear: Appear::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Tween {
+    morph: Option<Morph>,
+    elapsed: Duration,
+    /// the faces drawn in the last frame
+    current: Vec<Face>,
+}
+
+impl Tween {
+    pub fn new(faces: Vec<Face>) -> Self {
+        Self {
+            current: faces,
+            ..Default::default()
+        }
+    }
+
+    /// Start going from what is drawn now to `faces`, which is drawn until the next update
+    pub fn start(&mut self, faces: Vec<Face>) {
+        self.morph = Some(Morph::new(self.current.clone(), faces));
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// The faces drawn now
+    pub fn current(&self) -> &Vec<Face> {
+        &self.current
+    }
+
+    /// Go on by `dt`, the faces to draw if they changed
+    pub fn update(
+        &mut self,
+        settings: &MorphSettings,
+        angle: f32,
+        dt: Duration,
+    ) -> Option<Vec<Face>> {
+        let morph = self.morph.as_ref()?;
+        self.elapsed += dt;
+        let t = if settings.duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / settings.duration.as_secs_f32()
+        };
+        self.current = if t >= 1.0 {
+            self.morph.take()?.target().clone()
+        } else {
+            morph.at(settings.easing.apply(t), settings.appear, angle)
+        };
+        Some(self.current.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use my_items::{Pillar, V3};
+
+    #[test]
+    fn ends_on_the_new_faces() {
+        let pillar = |h| {
+            Pillar::new_upright(V3::from(0.0, 0.0, 0.0), V3::from(1.0, h, 1.0))
+                .with_tag("p")
+                .into_vec()
+        };
+        let mut settings = MorphSettings::default();
+        let mut tween = Tween::new(pillar(1.0));
+        let dt = Duration::from_millis(100);
+        assert!(tween.update(&settings, 0.0, dt).is_none());
+
+        // started twice before a frame, from the faces drawn
+        tween.start(pillar(5.0));
+        assert_eq!(tween.current()[2].pos11.y, 1.0);
+        tween.start(pillar(3.0));
+        let faces = tween.update(&settings, 0.0, dt).unwrap();
+        assert_eq!(faces.len(), pillar(1.0).len());
+        assert!(faces[2].pos11.y > 1.0 && faces[2].pos11.y < 3.0);
+        for _ in 0..10 {
+            tween.update(&settings, 0.0, dt);
+        }
+        assert_eq!(tween.current[2].pos11.y, 3.0);
+        assert!(tween.morph.is_none());
+
+        settings.duration = Duration::ZERO;
+        tween.start(pillar(2.0));
+        let faces = tween.update(&settings, 0.0, Duration::ZERO).unwrap();
+        assert_eq!(faces[2].pos11.y, 2.0);
+    }
+}
@@ -0,0 +1,105 @@
+//! The best runs of the levels, kept in `records.json` of the working directory.
+//!
+//! ```json
+//! { "levels/testpenrose.dll": { "time_ms": 12345, "turns": 4 } }
+//! ```
+//! A level is known by the path of its library.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use my_levels_finder::get_json;
+
+/// A run from the start of a level to its goal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Record {
+    pub time: Duration,
+    /// the snap targets the camera turned to
+    pub turns: u32,
+}
+
+impl Record {
+    /// The shortest time and the fewest turns, which may be of two runs
+    pub fn best(self, other: Self) -> Self {
+        Self {
+            time: self.time.min(other.time),
+            turns: self.turns.min(other.turns),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Records(BTreeMap<String, Record>);
+
+impl Records {
+    const FILE: &'static str = "records.json";
+
+    /// The saved records, none if there are none
+    pub fn load() -> Self {
+        Self::from_json(&get_json(Self::FILE))
+    }
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::write(Self::FILE, self.to_json().pretty(2))
+            .map_err(|err| format!("Cannot save {}: {err}", Self::FILE))
+    }
+
+    /// Keep the run of the level, the best before it is returned
+    pub fn add(&mut self, level: &str, run: Record) -> Option<Record> {
+        let before = self.0.get(level).copied();
+        self.0
+            .insert(level.to_string(), before.map_or(run, |b| b.best(run)));
+        before
+    }
+
+    fn from_json(j: &json::JsonValue) -> Self {
+        // a run without its time or its turns would be the best of it forever
+        let records = j
+            .entries()
+            .filter_map(|(level, r)| {
+                let record = Record {
+                    time: Duration::from_millis(r["time_ms"].as_u64()?),
+                    turns: r["turns"].as_u32()?,
+                };
+                Some((level.to_string(), record))
+            })
+            .collect();
+        Self(records)
+    }
+    fn to_json(&self) -> json::JsonValue {
+        let mut j = json::JsonValue::new_object();
+        for (level, r) in &self.0 {
+            j[level.as_str()] = json::object! {
+                time_ms: r.time.as_millis() as u64,
+                turns: r.turns,
+            };
+        }
+        j
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_runs_kept() {
+        let run = |ms, turns| Record {
+            time: Duration::from_millis(ms),
+            turns,
+        };
+        let mut records = Records::default();
+        assert_eq!(records.add("a", run(5000, 3)), None);
+        assert_eq!(records.add("a", run(4000, 6)), Some(run(5000, 3)));
+        assert_eq!(records.add("b", run(1000, 1)), None);
+        assert_eq!(records.0["a"], run(4000, 3));
+        assert_eq!(Records::from_json(&records.to_json()), records);
+        // what is broken is left out
+        let j = json::parse(
+            r#"{"a": 3, "b": {"turns": 2}, "c": {"time_ms": -5, "turns": 1}, "d": {"time_ms": 7, "turns": 2}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Records::from_json(&j).0.into_iter().collect::<Vec<_>>(),
+            vec![("d".to_string(), run(7, 2))]
+        );
+    }
+}
@@ -0,0 +1,381 @@
+//! The animated angle of the game view.
+//!
+//! Dragging with the left button turns the scene and flings it on when released,
+//! A/D and the arrow keys turn it to the previous or next snap target with an eased animation.
+//! A fling settles on the nearest snap target the same way.
+//! The angles are in radians, the settings are in degrees.
+
+use std::time::Duration;
+
+use crate::game_options::MyEvents;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    Linear,
+    /// fast at first and slow at the end
+    EaseOut,
+    /// slow at both ends
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// The progress of the animation at the time `t`, both of them range from 0 to 1
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotationSettings {
+    /// degrees between the snap targets
+    pub snap_step: f32,
+    /// how long turning to a snap target takes
+    pub duration: Duration,
+    pub easing: Easing,
+    /// degrees of dragging for a point the pointer moves
+    pub drag_speed: f32,
+    /// how fast a fling slows down, the speed is multiplied by `exp(-friction * seconds)`
+    pub friction: f32,
+    /// a fling slower than this, in degrees per second, stops
+    pub min_speed: f32,
+}
+
+impl Default for RotationSettings {
+    fn default() -> Self {
+        Self {
+            snap_step: 45.0,
+            duration: Duration::from_millis(300),
+            easing: Easing::default(),
+            drag_speed: 0.05,
+            friction: 4.0,
+            min_speed: 5.0,
+        }
+    }
+}
+
+/// The angles to snap to, every multiple of the step,
+/// and the thresholds told to the level when they are passed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snap {
+    /// radians between the snap targets
+    step: f32,
+    /// the thresholds of the level, sorted, the snap targets if it has none
+    thresholds: Vec<f32>,
+}
+
+impl Snap {
+    /// how far an angle must be from a target to be not on it
+    const EPSILON: f32 = 1e-4;
+
+    /// Snap every `step` degrees, and report the thresholds of the level
+    pub fn new(thresholds: Option<&[f32]>, step: f32) -> Self {
+        let mut thresholds = thresholds.unwrap_or_default().to_vec();
+        thresholds.sort_by(f32::total_cmp);
+        Self {
+            step: step.to_radians(),
+            thresholds,
+        }
+    }
+
+    /// The first target after `angle`
+    pub fn next(&self, angle: f32) -> f32 {
+        ((angle + Self::EPSILON) / self.step).floor() * self.step + self.step
+    }
+    /// The last target before `angle`
+    pub fn previous(&self, angle: f32) -> f32 {
+        ((angle - Self::EPSILON) / self.step).ceil() * self.step - self.step
+    }
+    /// The target nearest to `angle`
+    pub fn nearest(&self, angle: f32) -> f32 {
+        (angle / self.step).round() * self.step
+    }
+
+    /// The first threshold after `angle`, None if there is none
+    fn next_threshold(&self, angle: f32) -> Option<f32> {
+        if self.thresholds.is_empty() {
+            return Some(self.next(angle));
+        }
+        self.thresholds
+            .iter()
+            .copied()
+            .find(|t| *t > angle + Self::EPSILON)
+    }
+    /// The last threshold before `angle`, None if there is none
+    fn previous_threshold(&self, angle: f32) -> Option<f32> {
+        if self.thresholds.is_empty() {
+            return Some(self.previous(angle));
+        }
+        self.thresholds
+            .iter()
+            .rev()
+            .copied()
+            .find(|t| *t < angle - Self::EPSILON)
+    }
+
+    /// The thresholds passed when turning from `from` to `to`, in the order they are passed.
+    /// Neither `from` nor `to` is included
+    pub fn crossed(&self, from: f32, to: f32) -> Vec<f32> {
+        let mut res = vec![];
+        let mut angle = from;
+        loop {
+            let t = if to > from {
+                self.next_threshold(angle)
+            } else {
+                self.previous_threshold(angle)
+            };
+            let Some(t) = t.filter(|t| if to > from { *t < to } else { *t > to }) else {
+                return res;
+            };
+            res.push(t);
+            angle = t;
+        }
+    }
+
+    /// The snap targets passed or reached when turning from `from` to `to`, in the order.
+    /// `from` is not included
+    pub fn touched(&self, from: f32, to: f32) -> Vec<f32> {
+        let mut res = vec![];
+        if from == to {
+            return res;
+        }
+        let mut angle = from;
+        loop {
+            let t = if to > from {
+                self.next(angle)
+            } else {
+                self.previous(angle)
+            };
+            let reached = if to > from {
+                t <= to + Self::EPSILON
+            } else {
+                t >= to - Self::EPSILON
+            };
+            if !reached {
+                return res;
+            }
+            res.push(t);
+            angle = t;
+        }
+    }
+
+    /// The angles told to the level when turning from `from` to `to`:
+    /// just past every target passed in the order, and at last `to`.
+    /// So the level sees every range between its thresholds, however they are closed
+    pub fn reported(&self, from: f32, to: f32) -> Vec<f32> {
+        let past = if to > from {
+            Self::EPSILON
+        } else {
+            -Self::EPSILON
+        };
+        let mut res: Vec<f32> = self.crossed(from, to).iter().map(|t| t + past).collect();
+        res.push(to);
+        res
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Motion {
+    Still,
+    /// with the speed of the pointer in radians per second
+    Dragged(f32),
+    Flung(f32),
+    Animated {
+        from: f32,
+        to: f32,
+        elapsed: Duration,
+    },
+}
+
+pub struct Rotation {
+    pub angle: f32,
+    motion: Motion,
+}
+
+impl Rotation {
+    pub fn new(angle: f32) -> Self {
+        Self {
+            angle,
+            motion: Motion::Still,
+        }
+    }
+
+    /// Whether it is neither dragged nor turning by itself
+    pub fn is_still(&self) -> bool {
+        self.motion == Motion::Still
+    }
+
+    /// Turn by the events in the time `dt` since the last frame
+    pub fn update(
+        &mut self,
+        events: &MyEvents,
+        settings: &RotationSettings,
+        snap: &Snap,
+        dt: Duration,
+    ) {
+        let seconds = dt.as_secs_f32().max(1e-4);
+        if events.pressed_l {
+            let turned = (events.moved.0 * settings.drag_speed).to_radians();
+            self.angle += turned;
+            // smoothed, a single frame is too jumpy
+            let speed = match self.motion {
+                Motion::Dragged(speed) => speed * 0.5 + turned / seconds * 0.5,
+                _ => turned / seconds,
+            };
+            self.motion = Motion::Dragged(speed);
+            return;
+        }
+        if events.left || events.right {
+            // go on from where the running animation ends
+            let base = match self.motion {
+                Motion::Animated { to, .. } => to,
+                _ => self.angle,
+            };
+            let to = if events.left {
+                snap.previous(base)
+            } else {
+                snap.next(base)
+            };
+            self.motion = Motion::Animated {
+                from: self.angle,
+                to,
+                elapsed: Duration::ZERO,
+            };
+        }
+
+        self.motion = match self.motion.clone() {
+            Motion::Dragged(speed) | Motion::Flung(speed) => {
+                let speed = speed * (-settings.friction * seconds).exp();
+                if speed.abs() < settings.min_speed.to_radians() {
+                    Motion::Animated {
+                        from: self.angle,
+                        to: snap.nearest(self.angle),
+                        elapsed: Duration::ZERO,
+                    }
+                } else {
+                    self.angle += speed * seconds;
+                    Motion::Flung(speed)
+                }
+            }
+            Motion::Animated { from, to, elapsed } => {
+                let elapsed = elapsed + dt;
+                let t = elapsed.as_secs_f32() / settings.duration.as_secs_f32().max(1e-4);
+                self.angle = from + (to - from) * settings.easing.apply(t);
+                if t >= 1.0 {
+                    self.angle = to;
+                    Motion::Still
+                } else {
+                    Motion::Animated { from, to, elapsed }
+                }
+            }
+            Motion::Still => Motion::Still,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_end_where_they_should() {
+        for e in [Easing::Linear, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(e.apply(0.0), 0.0);
+            assert_eq!(e.apply(1.0), 1.0);
+            assert!(e.apply(0.3) < e.apply(0.6));
+        }
+    }
+
+    #[test]
+    fn crosses_in_order() {
+        let snap = Snap::new(None, 45.0);
+        let deg = |v: Vec<f32>| v.iter().map(|a| a.to_degrees().round()).collect::<Vec<_>>();
+        assert_eq!(
+            deg(snap.crossed(10_f32.to_radians(), 100_f32.to_radians())),
+            [45.0, 90.0]
+        );
+        assert_eq!(
+            deg(snap.crossed(90_f32.to_radians(), -10_f32.to_radians())),
+            [45.0, 0.0]
+        );
+        let snap = Snap::new(Some(&[0.5, -1.0, 2.0]), 45.0);
+        assert_eq!(snap.crossed(-2.0, 1.0), [-1.0, 0.5]);
+        assert_eq!(snap.crossed(3.0, 0.5), [2.0]);
+        assert_eq!(
+            snap.reported(-2.0, 1.0),
+            [-1.0 + Snap::EPSILON, 0.5 + Snap::EPSILON, 1.0]
+        );
+        assert!(snap.crossed(0.6, 1.9).is_empty());
+        assert!(snap.crossed(2.5, 3.0).is_empty());
+        // the snap targets go on every step, past the thresholds too
+        assert_eq!(deg(snap.touched(0.6, 2.0)), [45.0, 90.0]);
+        assert_eq!(
+            deg(snap.touched(100_f32.to_radians(), 45_f32.to_radians())),
+            [90.0, 45.0]
+        );
+        assert!(snap.touched(0.5, 0.5).is_empty());
+        assert_eq!(
+            deg(vec![snap.next(2.0), snap.previous(-1.5)]),
+            [135.0, -90.0]
+        );
+    }
+
+    #[test]
+    fn animates_to_the_next_target() {
+        let settings = RotationSettings::default();
+        let snap = Snap::new(None, 45.0);
+        let mut r = Rotation::new(10_f32.to_radians());
+        let mut events = MyEvents {
+            right: true,
+            ..Default::default()
+        };
+        r.update(&events, &settings, &snap, Duration::from_millis(100));
+        // pressed again before the animation ends
+        r.update(&events, &settings, &snap, Duration::from_millis(100));
+        events.right = false;
+        let mut last = r.angle;
+        for _ in 0..10 {
+            r.update(&events, &settings, &snap, Duration::from_millis(50));
+            assert!(r.angle >= last);
+            last = r.angle;
+        }
+        assert_eq!(r.angle, 90_f32.to_radians());
+    }
+
+    #[test]
+    fn flings_after_drag() {
+        let settings = RotationSettings::default();
+        let snap = Snap::new(None, 45.0);
+        let mut r = Rotation::new(0.0);
+        let mut events = MyEvents {
+            pressed_l: true,
+            moved: (20.0, 0.0),
+            ..Default::default()
+        };
+        let dt = Duration::from_millis(16);
+        r.update(&events, &settings, &snap, dt);
+        events.pressed_l = false;
+        events.moved = (0.0, 0.0);
+        let released = r.angle;
+        r.update(&events, &settings, &snap, dt);
+        assert!(r.angle > released);
+        for _ in 0..200 {
+            r.update(&events, &settings, &snap, dt);
+        }
+        // and settled on a snap target
+        assert_eq!(r.motion, Motion::Still);
+        assert_eq!(r.angle, snap.nearest(r.angle));
+    }
+}
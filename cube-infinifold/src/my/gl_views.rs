@@ -1,129 +1,148 @@
-use std::sync::Arc;
+use eframe::glow;
 
-use eframe::{egui::mutex::Mutex, glow};
+use super::{
+    render::PendingCapture,
+    shaders::{self, MessageSender, Shaders},
+};
 // use rand::distributions::uniform;
 
-#[allow(dead_code)]
-
-pub struct MyGLView {
-    pub basic: Arc<Mutex<GLGameView>>,
-    pub lines: Arc<Mutex<GLLinesView>>,
-    pub faces: Arc<Mutex<GLFacesView>>,
-}
-
-impl MyGLView {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let gl = cc
-            .gl
-            .as_ref()
-            .expect("You need to run eframe with the glow backend");
-        Self {
-            basic: Arc::new(Mutex::new(GLGameView::new(gl))),
-            lines: Arc::new(Mutex::new(GLLinesView::new(gl))),
-            faces: Arc::new(Mutex::new(GLFacesView::new(gl))),
-        }
-    }
-    pub fn destroy_all(&self, gl: &glow::Context) {
-        self.lines.lock().destroy(gl);
-        self.basic.lock().destroy(gl);
-    }
-}
-
 #[derive(Clone, PartialEq)]
 pub struct GlPaintOptions {
     pub angle: f32,
+    /// turning around the right of the screen after the angle, see `my_items::Projection`
+    pub pitch: f32,
+    pub mode: my_items::ProjectionMode,
     pub translate: my_items::V3,
     pub scale: f32,
     pub aspect_ratio: f32,
+    /// the light and the outlines of the faces
+    pub shading: my_items::shading::Shading,
 }
 impl Default for GlPaintOptions {
     fn default() -> Self {
         Self {
             angle: Default::default(),
+            pitch: 0.0,
+            mode: Default::default(),
             translate: Default::default(),
             scale: 1.0,
             aspect_ratio: 1.0,
+            shading: Default::default(),
         }
     }
 }
 impl GlPaintOptions {
-    fn get_projection_mat(&self) -> [f32; 9] {
-        [
-            self.scale * self.angle.cos(),
-            0.0,
-            -self.scale * self.angle.sin(),
-            0.0,
-            self.scale,
-            0.0,
-            self.scale * self.angle.sin(),
-            0.0,
-            self.scale * self.angle.cos(),
-        ]
+    /// the same projection as the shaders, done on the cpu
+    pub fn projection(&self) -> my_items::Projection {
+        my_items::Projection::new(self.angle, self.scale, self.aspect_ratio)
+            .with_translate(self.translate.clone())
+            .with_mode(self.mode)
+            .with_pitch(self.pitch)
+    }
+    pub(super) fn get_projection_mat(&self) -> [f32; 9] {
+        self.projection().proj_mat()
+    }
+    pub(super) fn get_pj_mat(&self) -> [f32; 9] {
+        self.projection().pj_mat()
     }
 }
 
 pub trait GLGameBase {
-    fn new(gl: &glow::Context) -> Self;
+    fn new(gl: &glow::Context, messages: MessageSender) -> Self;
     fn destroy(&self, gl: &glow::Context);
-    fn paint(&self, gl: &glow::Context, option: &GlPaintOptions);
+    fn paint(&mut self, gl: &glow::Context, option: &GlPaintOptions);
+    /// The capture to draw before the view is painted, see `render::FacesView::capture`
+    fn take_capture(&mut self) -> Option<PendingCapture> {
+        None
+    }
+}
+
+/// Draw `view` into a framebuffer of the size of the capture, then bind `target` again
+pub(super) unsafe fn capture<V: GLGameBase>(
+    view: &mut V,
+    gl: &glow::Context,
+    pending: &PendingCapture,
+    target: Option<glow::Framebuffer>,
+) -> Result<eframe::egui::ColorImage, String> {
+    use glow::HasContext as _;
+
+    let capture = &pending.capture;
+    let max_side = gl.get_parameter_i32(glow::MAX_RENDERBUFFER_SIZE).max(0) as u32;
+    let samples = capture.samples(max_side)?;
+    let (width, height) = (
+        (capture.width * samples) as i32,
+        (capture.height * samples) as i32,
+    );
+    let framebuffer = gl.create_framebuffer()?;
+    let color = gl.create_renderbuffer()?;
+    let depth = gl.create_renderbuffer()?;
+    gl.bind_renderbuffer(glow::RENDERBUFFER, Some(color));
+    gl.renderbuffer_storage(glow::RENDERBUFFER, glow::RGBA8, width, height);
+    gl.bind_renderbuffer(glow::RENDERBUFFER, Some(depth));
+    gl.renderbuffer_storage(glow::RENDERBUFFER, glow::DEPTH_COMPONENT24, width, height);
+    gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+    gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+    gl.framebuffer_renderbuffer(
+        glow::FRAMEBUFFER,
+        glow::COLOR_ATTACHMENT0,
+        glow::RENDERBUFFER,
+        Some(color),
+    );
+    gl.framebuffer_renderbuffer(
+        glow::FRAMEBUFFER,
+        glow::DEPTH_ATTACHMENT,
+        glow::RENDERBUFFER,
+        Some(depth),
+    );
+
+    let result = if gl.check_framebuffer_status(glow::FRAMEBUFFER) != glow::FRAMEBUFFER_COMPLETE {
+        Err(format!("Cannot make a framebuffer of {width}x{height}"))
+    } else {
+        // the clip rect of egui is not for the picture
+        let scissor = gl.is_enabled(glow::SCISSOR_TEST);
+        gl.disable(glow::SCISSOR_TEST);
+        gl.viewport(0, 0, width, height);
+        let [r, g, b, a] = capture.background;
+        gl.clear_color(r, g, b, a);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+        view.paint(gl, &capture.option(&pending.option, samples));
+        let mut bytes = vec![0; (width * height * 4) as usize];
+        gl.read_pixels(
+            0,
+            0,
+            width,
+            height,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(&mut bytes),
+        );
+        if scissor {
+            gl.enable(glow::SCISSOR_TEST);
+        }
+        Ok(capture.resolve(&bytes, width as usize * 4, samples, true, false))
+    };
+
+    gl.bind_framebuffer(glow::FRAMEBUFFER, target);
+    gl.delete_framebuffer(framebuffer);
+    gl.delete_renderbuffer(color);
+    gl.delete_renderbuffer(depth);
+    result
 }
 
 pub struct GLGameView {
     program: glow::Program,
     vertex_array: glow::VertexArray,
+    shaders: Shaders,
 }
 
 impl GLGameBase for GLGameView {
-    fn new(gl: &glow::Context) -> Self {
+    fn new(gl: &glow::Context, messages: MessageSender) -> Self {
         use glow::HasContext as _;
 
-        let shader_version = if cfg!(target_arch = "wasm32") {
-            "#version 300 es"
-        } else {
-            "#version 330"
-        };
-
         unsafe {
-            let program = gl.create_program().expect("Cannot create program");
-
-            let (vertex_shader_source, fragment_shader_source) = (
-                include_str!("../../../assets/shaders/basic.vs"),
-                include_str!("../../../assets/shaders/basic.fs"),
-            );
-            let shader_sources = [
-                (glow::VERTEX_SHADER, vertex_shader_source),
-                (glow::FRAGMENT_SHADER, fragment_shader_source),
-            ];
-
-            let shaders: Vec<_> = shader_sources
-                .iter()
-                .map(|(shader_type, shader_source)| {
-                    let shader = gl
-                        .create_shader(*shader_type)
-                        .expect("Cannot create shader");
-                    gl.shader_source(shader, &format!("{shader_version}\n{shader_source}"));
-                    gl.compile_shader(shader);
-                    assert!(
-                        gl.get_shader_compile_status(shader),
-                        "Failed to compile {shader_type}: {}",
-                        gl.get_shader_info_log(shader)
-                    );
-                    gl.attach_shader(program, shader);
-                    shader
-                })
-                .collect();
-
-            gl.link_program(program);
-            assert!(
-                gl.get_program_link_status(program),
-                "{}",
-                gl.get_program_info_log(program)
-            );
-
-            for shader in shaders {
-                gl.detach_shader(program, shader);
-                gl.delete_shader(shader);
-            }
+            let mut shaders =
+                Shaders::new(&[shaders::BASIC_VS, shaders::BASIC_FS]).with_messages(messages);
+            let program = load_program(gl, &mut shaders, &[]);
 
             let vertex_array = gl
                 .create_vertex_array()
@@ -132,6 +151,7 @@ impl GLGameBase for GLGameView {
             Self {
                 program,
                 vertex_array,
+                shaders,
             }
         }
     }
@@ -143,7 +163,7 @@ impl GLGameBase for GLGameView {
         }
     }
 
-    fn paint(&self, gl: &glow::Context, option: &GlPaintOptions) {
+    fn paint(&mut self, gl: &glow::Context, option: &GlPaintOptions) {
         let identity = glm::mat4(
             1.0, 0.0, 0.0, 0.0, //
             0.0, 1.0, 0.0, 0.0, //
@@ -168,6 +188,10 @@ impl GLGameBase for GLGameView {
         use glow::HasContext as _;
 
         unsafe {
+            if let Some(program) = reload_program(gl, &mut self.shaders, &[]) {
+                gl.delete_program(self.program);
+                self.program = program;
+            }
             gl.use_program(Some(self.program));
             // gl.depth_mask(true);
             gl.enable(glow::DEPTH_TEST);
@@ -179,6 +203,11 @@ impl GLGameBase for GLGameView {
                 false,
                 &proj,
             );
+            gl.uniform_matrix_3_f32_slice(
+                gl.get_uniform_location(self.program, "u_pj").as_ref(),
+                false,
+                &option.get_pj_mat(),
+            );
             gl.uniform_1_f32(
                 gl.get_uniform_location(self.program, "u_aspect_ratio")
                     .as_ref(),
@@ -282,91 +311,396 @@ impl GLGameBase for GLGameView {
     }
 }
 
+/// Every musk takes two texels of `u_masks`: `(pos, screen)` and `(dir, 0)`
+pub(super) const MASK_TEXTURE_WIDTH: usize = 1024;
+
+/// Add the musks of an item to the texels of `u_masks`,
+/// it returns where they start and how many of them are used, at most `Musk::MAX`
+pub(super) fn push_musks(texels: &mut Vec<[f32; 4]>, musks: &[my_items::Musk]) -> [f32; 2] {
+    let start = texels.len();
+    let musks = &musks[..musks.len().min(my_items::Musk::MAX)];
+    for m in musks {
+        let screen = (m.space == my_items::MuskSpace::Screen) as i32 as f32;
+        texels.push([m.pos.x, m.pos.y, m.pos.z, screen]);
+        texels.push([m.dir.x, m.dir.y, m.dir.z, 0.0]);
+    }
+    [start as f32, musks.len() as f32]
+}
+
+/// The bytes of a slice, to upload it to the gpu
+pub(super) unsafe fn as_bytes<T>(v: &[T]) -> &[u8] {
+    std::slice::from_raw_parts(v.as_ptr() as *const u8, std::mem::size_of_val(v))
+}
+
+/// What the lines and faces views upload, the same for glow and wgpu
+pub struct Mesh {
+    pub vertices: Vec<f32>,
+    /// the triangles to draw, empty if the vertices are drawn in order
+    pub elements: Vec<u32>,
+    /// the musks, see `push_musks`
+    pub texels: Vec<[f32; 4]>,
+}
+
+impl Mesh {
+    /// 2 vertices for a line, see `GLLinesView::ATTRIBUTES`
+    pub fn lines(lines: &[my_items::Line], angle: f32) -> Self {
+        let mut vertices: Vec<f32> = vec![];
+        let mut texels = vec![];
+        for l in lines {
+            let masks = push_musks(&mut texels, &l.msks);
+            for (pos, id) in [(&l.pos1, 0), (&l.pos2, 3)] {
+                vertices.extend_from_slice(&[pos.x, pos.y, pos.z]);
+                vertices.extend_from_slice(&l.color.get_at(id, angle).as_slice3());
+                vertices.extend_from_slice(&masks);
+            }
+        }
+        Self {
+            vertices,
+            elements: vec![],
+            texels,
+        }
+    }
+
+    /// 4 vertices and the triangles of `FACE_TRIANGLES` for a face, see `GLFacesView::ATTRIBUTES`.
+    /// The faces are put in the `order` of `my_items::order::draw_order`
+    pub fn faces(faces: &[my_items::Face], angle: f32, order: &[usize]) -> Self {
+        let stride = GLFacesView::SIZES.iter().sum::<i32>() as usize;
+        let mut vertices: Vec<f32> = vec![];
+        let mut elements: Vec<u32> = vec![];
+        let mut texels = vec![];
+        for (id, f) in order.iter().map(|id| (*id, &faces[*id])) {
+            let masks = push_musks(&mut texels, &f.musks);
+            let first = (vertices.len() / stride) as u32;
+            let normal = match f.lit {
+                true => f.normal(),
+                false => my_items::V3::from(0.0, 0.0, 0.0),
+            };
+            let corners = [&f.pos11, &f.pos12, &f.pos21, &f.pos22];
+            let uvs = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+            for (i, (pos, uv)) in corners.iter().zip(uvs).enumerate() {
+                vertices.extend_from_slice(&[pos.x, pos.y, pos.z]);
+                vertices.extend_from_slice(&f.color.get_at(i, angle).as_slice4());
+                vertices.extend_from_slice(&[f.index, id as f32]);
+                vertices.extend_from_slice(&masks);
+                vertices.extend_from_slice(&[normal.x, normal.y, normal.z]);
+                vertices.extend_from_slice(&uv);
+            }
+            for t in my_items::pick::FACE_TRIANGLES {
+                elements.extend(t.map(|i| first + i as u32));
+            }
+        }
+        Self {
+            vertices,
+            elements,
+            texels,
+        }
+    }
+}
+
+/// The texels of the musks filling whole rows of `MASK_TEXTURE_WIDTH`, and the number of rows
+pub(super) fn mask_texture_data(texels: &[[f32; 4]]) -> (Vec<[f32; 4]>, usize) {
+    let height = texels.len().div_ceil(MASK_TEXTURE_WIDTH).max(1);
+    let mut data = texels.to_vec();
+    data.resize(MASK_TEXTURE_WIDTH * height, [0.0; 4]);
+    (data, height)
+}
+
+/// Upload the texels of the musks to a `MASK_TEXTURE_WIDTH` wide float texture
+unsafe fn upload_musks(gl: &glow::Context, texture: glow::Texture, texels: &[[f32; 4]]) {
+    use glow::HasContext as _;
+
+    let (data, height) = mask_texture_data(texels);
+    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_MIN_FILTER,
+        glow::NEAREST as i32,
+    );
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_MAG_FILTER,
+        glow::NEAREST as i32,
+    );
+    gl.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        glow::RGBA32F as i32,
+        MASK_TEXTURE_WIDTH as i32,
+        height as i32,
+        0,
+        glow::RGBA,
+        glow::FLOAT,
+        Some(as_bytes(&data)),
+    );
+}
+
+/// Compile and link the shaders, the attributes are bound to their position in `attributes`.
+/// `sources` are the vertex and fragment shaders of `shaders.files`, the info log is returned if it fails
+unsafe fn create_program(
+    gl: &glow::Context,
+    shaders: &Shaders,
+    sources: &[String],
+    attributes: &[&str],
+) -> Result<glow::Program, String> {
+    use glow::HasContext as _;
+
+    let shader_version = if cfg!(target_arch = "wasm32") {
+        "#version 300 es"
+    } else {
+        "#version 330"
+    };
+    let program = gl.create_program()?;
+    let shader_types = [glow::VERTEX_SHADER, glow::FRAGMENT_SHADER];
+
+    let mut compiled = vec![];
+    let mut result = Ok(());
+    for ((shader_type, source), file) in shader_types.iter().zip(sources).zip(&shaders.files) {
+        let shader = gl.create_shader(*shader_type)?;
+        gl.shader_source(shader, &format!("{shader_version}\n{source}"));
+        gl.compile_shader(shader);
+        if !gl.get_shader_compile_status(shader) {
+            result = Err(format!(
+                "Failed to compile {}: {}",
+                file.name,
+                gl.get_shader_info_log(shader)
+            ));
+            gl.delete_shader(shader);
+            break;
+        }
+        gl.attach_shader(program, shader);
+        compiled.push(shader);
+    }
+
+    if result.is_ok() {
+        for (i, name) in attributes.iter().enumerate() {
+            gl.bind_attrib_location(program, i as u32, name);
+        }
+        gl.link_program(program);
+        if !gl.get_program_link_status(program) {
+            let names: Vec<_> = shaders.files.iter().map(|f| f.name).collect();
+            result = Err(format!(
+                "Failed to link {}: {}",
+                names.join(", "),
+                gl.get_program_info_log(program)
+            ));
+        }
+    }
+
+    for shader in compiled {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+    match result {
+        Ok(()) => Ok(program),
+        Err(log) => {
+            gl.delete_program(program);
+            Err(log)
+        }
+    }
+}
+
+/// The program of the shader files, or of the built in shaders if the files are broken
+unsafe fn load_program(
+    gl: &glow::Context,
+    shaders: &mut Shaders,
+    attributes: &[&str],
+) -> glow::Program {
+    let sources = shaders.sources();
+    create_program(gl, shaders, &sources, attributes).unwrap_or_else(|log| {
+        shaders.report_error(&log);
+        create_program(gl, shaders, &shaders.embedded(), attributes)
+            .expect("The built in shaders are broken")
+    })
+}
+
+/// A new program if the shader files are changed, the last good one is kept if they fail
+unsafe fn reload_program(
+    gl: &glow::Context,
+    shaders: &mut Shaders,
+    attributes: &[&str],
+) -> Option<glow::Program> {
+    let sources = shaders.reload()?;
+    match create_program(gl, shaders, &sources, attributes) {
+        Ok(program) => {
+            shaders.report_reloaded();
+            Some(program)
+        }
+        Err(log) => {
+            shaders.report_error(&log);
+            None
+        }
+    }
+}
+
+/// Point the attributes to the vertex buffer, they are `sizes` floats one after another
+unsafe fn set_attributes(gl: &glow::Context, sizes: &[i32]) {
+    use glow::HasContext as _;
+
+    let stride = sizes.iter().sum::<i32>() * std::mem::size_of::<f32>() as i32;
+    let mut offset = 0;
+    for (i, size) in sizes.iter().enumerate() {
+        gl.vertex_attrib_pointer_f32(i as u32, *size, glow::FLOAT, false, stride, offset);
+        gl.enable_vertex_attrib_array(i as u32);
+        offset += size * std::mem::size_of::<f32>() as i32;
+    }
+}
+
+/// The uniforms of the lines and faces shaders, looked up once
+struct Uniforms {
+    proj: Option<glow::UniformLocation>,
+    pj: Option<glow::UniformLocation>,
+    translate: Option<glow::UniformLocation>,
+    aspect_ratio: Option<glow::UniformLocation>,
+    mask_enabled: Option<glow::UniformLocation>,
+    masks: Option<glow::UniformLocation>,
+    highlighted: Option<glow::UniformLocation>,
+    highlight: Option<glow::UniformLocation>,
+    light: Option<glow::UniformLocation>,
+    ambient: Option<glow::UniformLocation>,
+    outline_color: Option<glow::UniformLocation>,
+    outline_width: Option<glow::UniformLocation>,
+}
+impl Uniforms {
+    unsafe fn new(gl: &glow::Context, program: glow::Program) -> Self {
+        use glow::HasContext as _;
+
+        Self {
+            proj: gl.get_uniform_location(program, "u_proj"),
+            pj: gl.get_uniform_location(program, "u_pj"),
+            translate: gl.get_uniform_location(program, "u_translate"),
+            aspect_ratio: gl.get_uniform_location(program, "u_aspect_ratio"),
+            mask_enabled: gl.get_uniform_location(program, "u_mask_enabled"),
+            masks: gl.get_uniform_location(program, "u_masks"),
+            highlighted: gl.get_uniform_location(program, "u_highlighted"),
+            highlight: gl.get_uniform_location(program, "u_highlight"),
+            light: gl.get_uniform_location(program, "u_light"),
+            ambient: gl.get_uniform_location(program, "u_ambient"),
+            outline_color: gl.get_uniform_location(program, "u_outline_color"),
+            outline_width: gl.get_uniform_location(program, "u_outline_width"),
+        }
+    }
+    /// Set the uniforms shared by the lines and faces, and bind the musks to the texture unit 0
+    unsafe fn set(
+        &self,
+        gl: &glow::Context,
+        option: &GlPaintOptions,
+        musk_enabled: bool,
+        musks: glow::Texture,
+    ) {
+        use glow::HasContext as _;
+
+        gl.uniform_matrix_3_f32_slice(self.proj.as_ref(), false, &option.get_projection_mat());
+        gl.uniform_matrix_3_f32_slice(self.pj.as_ref(), false, &option.get_pj_mat());
+        let t = &option.translate;
+        gl.uniform_3_f32(self.translate.as_ref(), t.x, t.y, t.z);
+        gl.uniform_1_f32(self.aspect_ratio.as_ref(), option.aspect_ratio);
+        gl.uniform_1_i32(self.mask_enabled.as_ref(), musk_enabled as i32);
+        let (light, ambient) = option.shading.light_uniforms();
+        gl.uniform_3_f32_slice(self.light.as_ref(), &light);
+        gl.uniform_1_f32(self.ambient.as_ref(), ambient);
+        let (outline_color, outline_width) = option.shading.outline_uniforms();
+        gl.uniform_4_f32_slice(self.outline_color.as_ref(), &outline_color);
+        gl.uniform_1_f32(self.outline_width.as_ref(), outline_width);
+        gl.active_texture(glow::TEXTURE0);
+        gl.bind_texture(glow::TEXTURE_2D, Some(musks));
+        gl.uniform_1_i32(self.masks.as_ref(), 0);
+    }
+}
+
 pub struct GLLinesView {
     program: glow::Program,
+    shaders: Shaders,
     vertex_array: glow::VertexArray,
+    /// 2 vertices for a line, see `GLLinesView::ATTRIBUTES`
+    vertex_buffer: glow::Buffer,
+    mask_texture: glow::Texture,
+    uniforms: Uniforms,
     lines: Vec<my_items::Line>,
     musk_enabled: bool,
+    /// the lines are changed after they were uploaded
+    dirty: bool,
+    /// the angle the colors were uploaded at, if any color depends on it
+    colors_angle: Option<f32>,
+    vertex_count: i32,
 }
 
 #[allow(dead_code)]
 
 impl GLLinesView {
+    const ATTRIBUTES: [&'static str; 3] = ["a_pos", "a_color", "a_masks"];
+    pub(super) const SIZES: [i32; 3] = [3, 3, 2];
+
     pub fn set_lines(&mut self, line_vec: Vec<my_items::Line>) {
         self.lines = line_vec;
+        self.dirty = true;
     }
     pub fn add_line(&mut self, line: my_items::Line) {
         self.lines.push(line);
+        self.dirty = true;
     }
     pub fn add_lines(&mut self, mut lines: Vec<my_items::Line>) {
         self.lines.append(&mut lines);
+        self.dirty = true;
     }
     pub fn set_musk_enabled(&mut self, musk: bool) {
         self.musk_enabled = musk;
     }
+
+    unsafe fn upload(&mut self, gl: &glow::Context, angle: f32) {
+        use glow::HasContext as _;
+
+        let mesh = Mesh::lines(&self.lines, angle);
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertex_buffer));
+        gl.buffer_data_u8_slice(
+            glow::ARRAY_BUFFER,
+            as_bytes(&mesh.vertices),
+            glow::STATIC_DRAW,
+        );
+        upload_musks(gl, self.mask_texture, &mesh.texels);
+
+        self.vertex_count = self.lines.len() as i32 * 2;
+        self.colors_angle = self
+            .lines
+            .iter()
+            .any(|l| l.color.is_angled())
+            .then_some(angle);
+        self.dirty = false;
+    }
 }
 
 impl GLGameBase for GLLinesView {
-    fn new(gl: &glow::Context) -> Self {
+    fn new(gl: &glow::Context, messages: MessageSender) -> Self {
         use glow::HasContext as _;
 
-        let shader_version = if cfg!(target_arch = "wasm32") {
-            "#version 300 es"
-        } else {
-            "#version 330"
-        };
-
         unsafe {
-            let program = gl.create_program().expect("Cannot create program");
-
-            let (vertex_shader_source, fragment_shader_source) = (
-                include_str!("../../../assets/shaders/b_lines.vs"),
-                include_str!("../../../assets/shaders/b_lines.fs"),
-            );
-            let shader_sources = [
-                (glow::VERTEX_SHADER, vertex_shader_source),
-                (glow::FRAGMENT_SHADER, fragment_shader_source),
-            ];
-
-            let shaders: Vec<_> = shader_sources
-                .iter()
-                .map(|(shader_type, shader_source)| {
-                    let shader = gl
-                        .create_shader(*shader_type)
-                        .expect("Cannot create shader");
-                    gl.shader_source(shader, &format!("{shader_version}\n{shader_source}"));
-                    gl.compile_shader(shader);
-                    assert!(
-                        gl.get_shader_compile_status(shader),
-                        "Failed to compile {shader_type}: {}",
-                        gl.get_shader_info_log(shader)
-                    );
-                    gl.attach_shader(program, shader);
-                    shader
-                })
-                .collect();
-
-            gl.link_program(program);
-            assert!(
-                gl.get_program_link_status(program),
-                "{}",
-                gl.get_program_info_log(program)
-            );
-
-            for shader in shaders {
-                gl.detach_shader(program, shader);
-                gl.delete_shader(shader);
-            }
+            let mut shaders =
+                Shaders::new(&[shaders::LINES_VS, shaders::LINES_FS]).with_messages(messages);
+            let program = load_program(gl, &mut shaders, &Self::ATTRIBUTES);
+            let uniforms = Uniforms::new(gl, program);
 
             let vertex_array = gl
                 .create_vertex_array()
                 .expect("Cannot create vertex array");
+            let vertex_buffer = gl.create_buffer().expect("Cannot create buffer");
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            set_attributes(gl, &Self::SIZES);
+            gl.bind_vertex_array(None);
+
+            let mask_texture = gl.create_texture().expect("Cannot create texture");
 
             Self {
                 program,
+                shaders,
                 vertex_array,
+                vertex_buffer,
+                mask_texture,
+                uniforms,
                 lines: vec![],
                 musk_enabled: true,
+                dirty: true,
+                colors_angle: None,
+                vertex_count: 0,
             }
         }
     }
@@ -375,239 +709,173 @@ impl GLGameBase for GLLinesView {
         unsafe {
             gl.delete_program(self.program);
             gl.delete_vertex_array(self.vertex_array);
+            gl.delete_buffer(self.vertex_buffer);
+            gl.delete_texture(self.mask_texture);
         }
     }
 
-    fn paint(&self, gl: &glow::Context, option: &GlPaintOptions) {
-        let proj = option.get_projection_mat();
-
+    fn paint(&mut self, gl: &glow::Context, option: &GlPaintOptions) {
         use glow::HasContext as _;
-        let col: &my_items::Colored = &my_items::Colored::default();
 
         unsafe {
+            if let Some(program) = reload_program(gl, &mut self.shaders, &Self::ATTRIBUTES) {
+                gl.delete_program(self.program);
+                self.program = program;
+                self.uniforms = Uniforms::new(gl, program);
+            }
+            if self.dirty || self.colors_angle.is_some_and(|a| a != option.angle) {
+                self.upload(gl, option.angle);
+            }
+
             gl.use_program(Some(self.program));
             gl.enable(glow::DEPTH_TEST);
             gl.clear(glow::DEPTH_BUFFER_BIT);
             gl.depth_func(glow::LEQUAL);
 
-            gl.uniform_matrix_3_f32_slice(
-                gl.get_uniform_location(self.program, "u_proj").as_ref(),
-                false,
-                &proj,
-            );
-            gl.uniform_1_f32(
-                gl.get_uniform_location(self.program, "u_aspect_ratio")
-                    .as_ref(),
-                option.aspect_ratio,
-            );
-            let color = col.get(0);
-            gl.uniform_3_f32(
-                gl.get_uniform_location(self.program, "u_color1").as_ref(),
-                color.r,
-                color.g,
-                color.b,
-            );
-            let color = col.get(1);
-            gl.uniform_3_f32(
-                gl.get_uniform_location(self.program, "u_color2").as_ref(),
-                color.r,
-                color.g,
-                color.b,
-            );
-            let mut use_mask = false;
-            gl.uniform_1_i32(
-                gl.get_uniform_location(self.program, "u_use_mask").as_ref(),
-                use_mask as i32,
-            );
+            self.uniforms
+                .set(gl, option, self.musk_enabled, self.mask_texture);
             gl.bind_vertex_array(Some(self.vertex_array));
-
-            for l in self.lines.iter() {
-                gl.uniform_3_f32(
-                    gl.get_uniform_location(self.program, "u_pos1").as_ref(),
-                    l.pos1.x,
-                    l.pos1.y,
-                    l.pos1.z,
-                );
-                gl.uniform_3_f32(
-                    gl.get_uniform_location(self.program, "u_pos2").as_ref(),
-                    l.pos2.x,
-                    l.pos2.y,
-                    l.pos2.z,
-                );
-                let col2 = &l.color;
-                if !(col == col2) {
-                    let col = col2;
-                    let color = col.get(0);
-                    // println!("{:#?}",col);
-                    // println!("{:#?}",col2);
-                    // todo!("test");
-                    gl.uniform_3_f32(
-                        gl.get_uniform_location(self.program, "u_color1").as_ref(),
-                        color.r,
-                        color.g,
-                        color.b,
-                    );
-                    let color = col.get(3);
-                    gl.uniform_3_f32(
-                        gl.get_uniform_location(self.program, "u_color2").as_ref(),
-                        color.r,
-                        color.g,
-                        color.b,
-                    );
-                }
-                if self.musk_enabled {
-                    if let Some(msk) = &l.msk {
-                        use_mask = true;
-                        gl.uniform_1_i32(
-                            gl.get_uniform_location(self.program, "u_use_mask").as_ref(),
-                            use_mask as i32,
-                        );
-                        gl.uniform_3_f32(
-                            gl.get_uniform_location(self.program, "u_mask_pos").as_ref(),
-                            msk.pos.x,
-                            msk.pos.y,
-                            msk.pos.z,
-                        );
-                        gl.uniform_3_f32(
-                            gl.get_uniform_location(self.program, "u_mask_dir").as_ref(),
-                            msk.dir.x,
-                            msk.dir.y,
-                            msk.dir.z,
-                        );
-                    } else if use_mask {
-                        use_mask = false;
-                        gl.uniform_1_i32(
-                            gl.get_uniform_location(self.program, "u_use_mask").as_ref(),
-                            use_mask as i32,
-                        );
-                    }
-                }
-                gl.draw_arrays(glow::LINES, 0, 2);
-            }
-
-            // // draw musks
-            // gl.uniform_1_i32(
-            //     gl.get_uniform_location(self.program, "u_use_mask").as_ref(),
-            //     false as i32,
-            // );
-            // gl.uniform_3_f32(
-            //     gl.get_uniform_location(self.program, "u_color1").as_ref(),
-            //     0.9,
-            //     0.1,
-            //     0.1,
-            // );
-            // gl.uniform_3_f32(
-            //     gl.get_uniform_location(self.program, "u_color2").as_ref(),
-            //     0.9,
-            //     0.2,
-            //     0.2,
-            // );
-            // for l in self.lines.iter() {
-            //     if let Some(msk) = &l.msk {
-            //         gl.uniform_3_f32(
-            //             gl.get_uniform_location(self.program, "u_pos1").as_ref(),
-            //             msk.pos.x,
-            //             msk.pos.y,
-            //             msk.pos.z,
-            //         );
-            //         gl.uniform_3_f32(
-            //             gl.get_uniform_location(self.program, "u_pos2").as_ref(),
-            //             msk.pos.x + msk.dir.x,
-            //             msk.pos.y + msk.dir.y,
-            //             msk.pos.z + msk.dir.z,
-            //         );
-            //         gl.draw_arrays(glow::LINES, 0, 2);
-            //     }
-            // }
+            gl.draw_arrays(glow::LINES, 0, self.vertex_count);
+            gl.bind_vertex_array(None);
         }
     }
 }
 
 pub struct GLFacesView {
     program: glow::Program,
+    shaders: Shaders,
     vertex_array: glow::VertexArray,
+    /// 4 vertices for a face, see `GLFacesView::ATTRIBUTES`
+    vertex_buffer: glow::Buffer,
+    /// the 4 triangles of every face, `my_items::pick::FACE_TRIANGLES`
+    element_buffer: glow::Buffer,
+    mask_texture: glow::Texture,
+    uniforms: Uniforms,
     faces: Vec<my_items::Face>,
     musk_enabled: bool,
+    highlighted: Option<usize>,
+    /// the faces are changed after they were uploaded
+    dirty: bool,
+    /// the angle the colors were uploaded at, if any color depends on it
+    colors_angle: Option<f32>,
+    /// the ids of the faces in the order they were uploaded, the translucent ones sorted
+    order: Vec<usize>,
+    element_count: i32,
+    /// drawn in the next paint
+    capture: Option<PendingCapture>,
 }
 
 #[allow(dead_code)]
 
 impl GLFacesView {
+    /// how much a highlighted face is mixed with white
+    pub(super) const HIGHLIGHT: f32 = 0.3;
+    const ATTRIBUTES: [&'static str; 7] = [
+        "a_pos", "a_color", "a_index", "a_id", "a_masks", "a_normal", "a_uv",
+    ];
+    pub(super) const SIZES: [i32; 7] = [3, 4, 1, 1, 2, 3, 2];
+
     pub fn set_faces(&mut self, faces: Vec<my_items::Face>) {
         self.faces = faces;
+        self.dirty = true;
     }
     pub fn add_face(&mut self, face: my_items::Face) {
         self.faces.push(face);
+        self.dirty = true;
     }
     pub fn add_faces(&mut self, mut faces: Vec<my_items::Face>) {
         self.faces.append(&mut faces);
+        self.dirty = true;
     }
     pub fn set_musk_enabled(&mut self, musk: bool) {
         self.musk_enabled = musk;
     }
+    pub fn get_faces(&self) -> &Vec<my_items::Face> {
+        &self.faces
+    }
+    /// Draw the face of this id brighter, None to stop
+    pub fn set_highlighted(&mut self, id: Option<usize>) {
+        self.highlighted = id;
+    }
+    /// The face drawn at `(x, y)` in clip space with these options
+    pub fn pick(&self, option: &GlPaintOptions, x: f32, y: f32) -> Option<my_items::pick::Picked> {
+        my_items::pick::pick_face(&self.faces, &option.projection(), x, y, self.musk_enabled)
+    }
+    /// Draw the faces offscreen in the next paint
+    pub fn set_capture(&mut self, pending: PendingCapture) {
+        self.capture = Some(pending);
+    }
+
+    unsafe fn upload(&mut self, gl: &glow::Context, angle: f32, order: Vec<usize>) {
+        use glow::HasContext as _;
+
+        let mesh = Mesh::faces(&self.faces, angle, &order);
+        self.order = order;
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertex_buffer));
+        gl.buffer_data_u8_slice(
+            glow::ARRAY_BUFFER,
+            as_bytes(&mesh.vertices),
+            glow::STATIC_DRAW,
+        );
+        gl.bind_vertex_array(Some(self.vertex_array));
+        gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.element_buffer));
+        gl.buffer_data_u8_slice(
+            glow::ELEMENT_ARRAY_BUFFER,
+            as_bytes(&mesh.elements),
+            glow::STATIC_DRAW,
+        );
+        gl.bind_vertex_array(None);
+        upload_musks(gl, self.mask_texture, &mesh.texels);
+
+        self.element_count = mesh.elements.len() as i32;
+        self.colors_angle = self
+            .faces
+            .iter()
+            .any(|f| f.color.is_angled())
+            .then_some(angle);
+        self.dirty = false;
+    }
 }
 
 impl GLGameBase for GLFacesView {
-    fn new(gl: &glow::Context) -> Self {
+    fn new(gl: &glow::Context, messages: MessageSender) -> Self {
         use glow::HasContext as _;
 
-        let shader_version = if cfg!(target_arch = "wasm32") {
-            "#version 300 es"
-        } else {
-            "#version 330"
-        };
-
         unsafe {
-            let program = gl.create_program().expect("Cannot create program");
-
-            let (vertex_shader_source, fragment_shader_source) = (
-                include_str!("../../../assets/shaders/b_faces.vs"),
-                include_str!("../../../assets/shaders/b_faces.fs"),
-            );
-            let shader_sources = [
-                (glow::VERTEX_SHADER, vertex_shader_source),
-                (glow::FRAGMENT_SHADER, fragment_shader_source),
-            ];
-
-            let shaders: Vec<_> = shader_sources
-                .iter()
-                .map(|(shader_type, shader_source)| {
-                    let shader = gl
-                        .create_shader(*shader_type)
-                        .expect("Cannot create shader");
-                    gl.shader_source(shader, &format!("{shader_version}\n{shader_source}"));
-                    gl.compile_shader(shader);
-                    assert!(
-                        gl.get_shader_compile_status(shader),
-                        "Failed to compile {shader_type}: {}",
-                        gl.get_shader_info_log(shader)
-                    );
-                    gl.attach_shader(program, shader);
-                    shader
-                })
-                .collect();
-
-            gl.link_program(program);
-            assert!(
-                gl.get_program_link_status(program),
-                "{}",
-                gl.get_program_info_log(program)
-            );
-
-            for shader in shaders {
-                gl.detach_shader(program, shader);
-                gl.delete_shader(shader);
-            }
+            let mut shaders =
+                Shaders::new(&[shaders::FACES_VS, shaders::FACES_FS]).with_messages(messages);
+            let program = load_program(gl, &mut shaders, &Self::ATTRIBUTES);
+            let uniforms = Uniforms::new(gl, program);
 
             let vertex_array = gl
                 .create_vertex_array()
                 .expect("Cannot create vertex array");
+            let vertex_buffer = gl.create_buffer().expect("Cannot create buffer");
+            let element_buffer = gl.create_buffer().expect("Cannot create buffer");
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(element_buffer));
+            set_attributes(gl, &Self::SIZES);
+            gl.bind_vertex_array(None);
+
+            let mask_texture = gl.create_texture().expect("Cannot create texture");
 
             Self {
                 program,
+                shaders,
                 vertex_array,
+                vertex_buffer,
+                element_buffer,
+                mask_texture,
+                uniforms,
                 faces: vec![],
                 musk_enabled: true,
+                highlighted: None,
+                dirty: true,
+                colors_angle: None,
+                order: vec![],
+                element_count: 0,
+                capture: None,
             }
         }
     }
@@ -616,98 +884,49 @@ impl GLGameBase for GLFacesView {
         unsafe {
             gl.delete_program(self.program);
             gl.delete_vertex_array(self.vertex_array);
+            gl.delete_buffer(self.vertex_buffer);
+            gl.delete_buffer(self.element_buffer);
+            gl.delete_texture(self.mask_texture);
         }
     }
 
-    fn paint(&self, gl: &glow::Context, option: &GlPaintOptions) {
-        let proj = option.get_projection_mat();
-
+    fn paint(&mut self, gl: &glow::Context, option: &GlPaintOptions) {
         use glow::HasContext as _;
-        let col: &my_items::Colored = &my_items::Colored::default();
 
         unsafe {
+            if let Some(program) = reload_program(gl, &mut self.shaders, &Self::ATTRIBUTES) {
+                gl.delete_program(self.program);
+                self.program = program;
+                self.uniforms = Uniforms::new(gl, program);
+            }
+            // the translucent faces are uploaded again when they are sorted otherwise
+            let order = my_items::order::draw_order(&self.faces, &option.projection());
+            if self.dirty
+                || self.colors_angle.is_some_and(|a| a != option.angle)
+                || order != self.order
+            {
+                self.upload(gl, option.angle, order);
+            }
+
             gl.use_program(Some(self.program));
             gl.enable(glow::DEPTH_TEST);
             gl.depth_func(glow::LEQUAL);
             // gl.depth_func(glow::GREATER);
             gl.clear(glow::DEPTH_BUFFER_BIT);
 
-            gl.uniform_matrix_3_f32_slice(
-                gl.get_uniform_location(self.program, "u_proj").as_ref(),
-                false,
-                &proj,
-            );
+            self.uniforms
+                .set(gl, option, self.musk_enabled, self.mask_texture);
             gl.uniform_1_f32(
-                gl.get_uniform_location(self.program, "u_aspect_ratio")
-                    .as_ref(),
-                option.aspect_ratio,
-            );
-            gl.uniform_1_f32(
-                gl.get_uniform_location(self.program, "u_index").as_ref(),
-                0.0,
-            );
-            gl.uniform_4_f32_slice(
-                gl.get_uniform_location(self.program, "u_color").as_ref(),
-                &my_items::Color::as_slice4_4(&col.get(0), &col.get(1), &col.get(2), &col.get(3)),
-            );
-            let mut use_mask = false;
-            gl.uniform_1_i32(
-                gl.get_uniform_location(self.program, "u_use_mask").as_ref(),
-                use_mask as i32,
+                self.uniforms.highlighted.as_ref(),
+                self.highlighted.map_or(-1.0, |id| id as f32),
             );
+            gl.uniform_1_f32(self.uniforms.highlight.as_ref(), Self::HIGHLIGHT);
             gl.bind_vertex_array(Some(self.vertex_array));
-
-            for f in self.faces.iter().filter(|f| !f.skipped) {
-                gl.uniform_3_f32_slice(
-                    gl.get_uniform_location(self.program, "u_pos").as_ref(),
-                    &f.get_pos_slice(),
-                );
-                gl.uniform_1_f32(
-                    gl.get_uniform_location(self.program, "u_index").as_ref(),
-                    f.index,
-                );
-                let col2 = &f.color;
-                if !(col == col2) {
-                    let col = col2;
-                    gl.uniform_4_f32_slice(
-                        gl.get_uniform_location(self.program, "u_color").as_ref(),
-                        &my_items::Color::as_slice4_4(
-                            &col.get(0),
-                            &col.get(1),
-                            &col.get(2),
-                            &col.get(3),
-                        ),
-                    );
-                }
-                if self.musk_enabled {
-                    if let Some(msk) = &f.musk {
-                        use_mask = true;
-                        gl.uniform_1_i32(
-                            gl.get_uniform_location(self.program, "u_use_mask").as_ref(),
-                            use_mask as i32,
-                        );
-                        gl.uniform_3_f32(
-                            gl.get_uniform_location(self.program, "u_mask_pos").as_ref(),
-                            msk.pos.x,
-                            msk.pos.y,
-                            msk.pos.z,
-                        );
-                        gl.uniform_3_f32(
-                            gl.get_uniform_location(self.program, "u_mask_dir").as_ref(),
-                            msk.dir.x,
-                            msk.dir.y,
-                            msk.dir.z,
-                        );
-                    } else if use_mask {
-                        use_mask = false;
-                        gl.uniform_1_i32(
-                            gl.get_uniform_location(self.program, "u_use_mask").as_ref(),
-                            use_mask as i32,
-                        );
-                    }
-                }
-                gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 6);
-            }
+            gl.draw_elements(glow::TRIANGLES, self.element_count, glow::UNSIGNED_INT, 0);
+            gl.bind_vertex_array(None);
         }
     }
+    fn take_capture(&mut self) -> Option<PendingCapture> {
+        self.capture.take()
+    }
 }
@@ -4,6 +4,7 @@ use crate::game_options::MyGameOption;
 
 use super::{
     // performance_evaluation::PerformanceEvaluation,
+    theme,
     MyViewImpl,
     UIWidget,
 };
@@ -93,12 +94,22 @@ impl MyViewImpl for MyLevelIndex {
             stroke: egui::Stroke::new(1.0, egui::Color32::GRAY),
         };
         for (i, g) in &option.game_library.groups {
+            // every chapter in the colors of its theme
+            let palette = &g.theme.palette;
+            let frame = egui::Frame {
+                fill: palette.button.as_ref().map_or(frame.fill, theme::color32),
+                ..frame
+            };
+            let title = palette
+                .text
+                .as_ref()
+                .map_or(egui::Color32::GRAY, theme::color32);
             frame.show(ui, |ui| {
                 ui.vertical(|ui| {
                     ui.label(
                         egui::RichText::new(format!("第 {i} 组"))
                             .size(30.0)
-                            .color(egui::Color32::GRAY),
+                            .color(title),
                     )
                     .on_hover_text_at_pointer(&g.name);
                     ui.horizontal(|ui| {
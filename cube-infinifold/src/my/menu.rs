@@ -1,21 +1,19 @@
 use std::sync::Arc;
 
-use eframe::{
-    egui::{self, mutex::Mutex},
-    egui_glow,
-};
+use eframe::egui;
 
 use crate::game_options::MyGameOption;
 
 use super::{
-    gl_views::{GLGameBase, GLGameView, GlPaintOptions},
+    gl_views::GlPaintOptions,
     // performance_evaluation::PerformanceEvaluation,
+    render::RenderView,
     MyViewImpl,
     UIWidget,
 };
 
 pub struct MyMenu {
-    game_view: Arc<Mutex<GLGameView>>,
+    game_view: Arc<dyn RenderView>,
     angle: f32,
 
     btns: Vec<UIWidget>,
@@ -24,7 +22,7 @@ pub struct MyMenu {
 }
 
 impl MyMenu {
-    pub fn new(game_view: Arc<Mutex<GLGameView>>, _ctx: &eframe::egui::Context) -> MyMenu {
+    pub fn new(game_view: Arc<dyn RenderView>, _ctx: &eframe::egui::Context) -> MyMenu {
         let btns = vec![
             UIWidget::new(vec![
                 "file://assets/ui/unselected.png",
@@ -56,7 +54,6 @@ impl MyMenu {
             0.0
         };
         let angle = self.angle;
-        let game_view = self.game_view.clone();
         let option = GlPaintOptions {
             angle,
             scale: 1.0,
@@ -64,13 +61,7 @@ impl MyMenu {
             ..Default::default()
         };
 
-        // Clone locals so we can move them into the paint callback:
-        let callback = egui::PaintCallback {
-            rect: ui.max_rect(),
-            callback: std::sync::Arc::new(egui_glow::CallbackFn::new(move |_info, painter| {
-                game_view.lock().paint(painter.gl(), &option);
-            })),
-        };
+        let callback = self.game_view.clone().paint_callback(ui.max_rect(), option);
         ui.painter().add(callback);
     }
 }
@@ -9,6 +9,10 @@ pub mod load_fonts;
 pub mod menu;
 pub mod performance_evaluation;
 pub mod install;
+pub mod render;
+pub mod shaders;
+pub mod theme;
+pub mod wgpu_views;
 
 pub trait MyViewImpl {
     fn destory(&mut self);
@@ -16,6 +20,7 @@ pub trait MyViewImpl {
     fn to_change(&self, option: &mut crate::MyGameOption) -> Option<String>;
 }
 
+#[allow(clippy::large_enum_variant)]
 pub enum MyView {
     MyMenu(menu::MyMenu),
     MyLevels(level_index::MyLevelIndex),
@@ -42,6 +47,9 @@ pub struct UIWidget {
     pub y: f32,
     pub font_color: egui::Color32,
     pub font_id: egui::FontId,
+    /// the tints of the images, white keeps their colors
+    pub tint: egui::Color32,
+    pub hovered_tint: egui::Color32,
 }
 
 impl UIWidget {
@@ -58,6 +66,8 @@ impl UIWidget {
                 family: egui::FontFamily::Proportional,
             },
             imgs_ids: vec![],
+            tint: egui::Color32::WHITE,
+            hovered_tint: egui::Color32::WHITE,
         }
     }
     // eframe:
@@ -85,6 +95,19 @@ impl UIWidget {
         self.font_id = egui::FontId { size, family };
         self
     }
+    /// The colors of the theme, where it sets them
+    fn with_palette(mut self, palette: &my_items::theme::Palette) -> Self {
+        if let Some(c) = &palette.button {
+            self.tint = theme::color32(c);
+        }
+        if let Some(c) = &palette.hovered {
+            self.hovered_tint = theme::color32(c);
+        }
+        if let Some(c) = &palette.text {
+            self.font_color = theme::color32(c);
+        }
+        self
+    }
 
     fn button(
         &self,
@@ -101,13 +124,18 @@ impl UIWidget {
                 focusable: false,
             },
         );
+        let tint = if response.hovered() {
+            self.hovered_tint
+        } else {
+            self.tint
+        };
         if self.imgs_ids.is_empty() {
             let img_str = if response.hovered() {
                 self.imgs.get(hov_id).unwrap_or(&self.default_img)
             } else {
                 self.imgs.get(img_id).unwrap_or(&self.default_img)
             };
-            egui::Image::new(img_str).paint_at(ui, rect);
+            egui::Image::new(img_str).tint(tint).paint_at(ui, rect);
         } else {
             let default_img = egui::TextureId::default();
             let texture = if response.hovered() {
@@ -119,7 +147,7 @@ impl UIWidget {
                 *texture,
                 rect,
                 egui::Rect::from_min_max(egui::Pos2::new(0.0, 0.0), egui::Pos2::new(1.0, 1.0)),
-                egui::Color32::WHITE,
+                tint,
             );
         }
         ui.painter().text(
@@ -0,0 +1,345 @@
+//! The game views for either backend of eframe.
+//!
+//! The views are used through `RenderView`, `LinesView` and `FacesView`,
+//! the glow ones are in `gl_views` and the wgpu ones in `wgpu_views`.
+//!
+//! The faces can be captured alone by `FacesView::capture`, drawn offscreen at any size.
+//! It is done in the next paint callback of the view, before the view is painted.
+
+use std::sync::Arc;
+
+use eframe::{
+    egui::{self, mutex::Mutex},
+    egui_glow, glow,
+};
+
+use super::{
+    gl_views::{capture, GLFacesView, GLGameBase, GLGameView, GLLinesView, GlPaintOptions},
+    shaders::MessageSender,
+    wgpu_views::{self, WgpuFacesView, WgpuGameView, WgpuLinesView},
+};
+
+/// samples of every pixel, the pipelines of wgpu are made for it
+pub const MULTISAMPLING: u16 = 4;
+/// bits of the depth buffer, the pipelines of wgpu are made for it
+pub const DEPTH_BUFFER: u8 = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    Glow,
+    Wgpu,
+}
+
+impl Backend {
+    /// `glow` or `wgpu`, the same as the argument `--renderer`
+    pub const ENV: &'static str = "CUBE_INFINIFOLD_RENDERER";
+
+    /// The backend chosen by `--renderer <name>` or `CUBE_INFINIFOLD_RENDERER`, wgpu by default
+    pub fn from_env() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let name = args
+            .iter()
+            .position(|a| a == "--renderer")
+            .and_then(|i| args.get(i + 1).cloned())
+            .or_else(|| std::env::var(Self::ENV).ok());
+        match name.as_deref().map(str::to_lowercase).as_deref() {
+            Some("glow") => Self::Glow,
+            None | Some("wgpu") => Self::Wgpu,
+            Some(name) => {
+                println!("Unknown renderer {name}, using wgpu");
+                Self::Wgpu
+            }
+        }
+    }
+
+    pub fn renderer(self) -> eframe::Renderer {
+        match self {
+            Self::Glow => eframe::Renderer::Glow,
+            Self::Wgpu => eframe::Renderer::Wgpu,
+        }
+    }
+}
+
+/// The aspect ratio of `rect` in physical pixels, rounded the same as the viewport of a callback
+pub fn aspect_ratio_in_pixels(
+    rect: egui::Rect,
+    pixels_per_point: f32,
+    screen_size_px: [u32; 2],
+) -> f32 {
+    let info = egui::PaintCallbackInfo {
+        viewport: rect,
+        clip_rect: rect,
+        pixels_per_point,
+        screen_size_px,
+    };
+    let px = info.viewport_in_pixels();
+    if px.width_px <= 0 || px.height_px <= 0 {
+        return 1.0;
+    }
+    px.width_px as f32 / px.height_px as f32
+}
+
+/// The aspect ratio of `rect` when it is painted into the window of `ctx`
+pub fn aspect_ratio_of(ctx: &egui::Context, rect: egui::Rect) -> f32 {
+    let ppp = ctx.pixels_per_point();
+    let screen = ctx.screen_rect().size() * ppp;
+    aspect_ratio_in_pixels(
+        rect,
+        ppp,
+        [screen.x.round() as u32, screen.y.round() as u32],
+    )
+}
+
+/// A view painted into the egui frame, by the backend eframe runs with
+pub trait RenderView: Send + Sync {
+    /// The callback painting the view into `rect`
+    fn paint_callback(
+        self: Arc<Self>,
+        rect: egui::Rect,
+        option: GlPaintOptions,
+    ) -> egui::PaintCallback;
+    /// Free the resources of glow, the ones of wgpu are dropped with the renderer
+    fn destroy(&self, _gl: Option<&glow::Context>) {}
+}
+
+/// A picture of the faces alone, drawn offscreen without egui, see `FacesView::capture`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capture {
+    /// the size of the picture in pixels
+    pub width: u32,
+    pub height: u32,
+    /// every pixel is the mean of `supersample` x `supersample` samples,
+    /// fewer if the picture would be too large for the gpu
+    pub supersample: u32,
+    /// premultiplied, as it is written into the picture. Transparent leaves the background out
+    pub background: [f32; 4],
+}
+
+/// Called with the picture, or why it could not be drawn
+pub type CaptureDone = Box<dyn FnOnce(Result<egui::ColorImage, String>) + Send>;
+
+pub struct PendingCapture {
+    pub capture: Capture,
+    /// the options of the view, the aspect ratio is the one of the picture
+    pub option: GlPaintOptions,
+    pub done: CaptureDone,
+}
+
+impl Capture {
+    /// The samples of a side of a pixel, as many as the picture can have within `max_side`
+    pub fn samples(&self, max_side: u32) -> Result<u32, String> {
+        let side = self.width.max(self.height);
+        if self.width == 0 || self.height == 0 || side > max_side {
+            return Err(format!(
+                "Cannot capture {}x{}, the largest side is {max_side}",
+                self.width, self.height
+            ));
+        }
+        Ok(self.supersample.clamp(1, max_side / side))
+    }
+
+    /// The options to draw the picture with `samples`, the outlines are as wide as in the picture
+    pub fn option(&self, option: &GlPaintOptions, samples: u32) -> GlPaintOptions {
+        let mut option = GlPaintOptions {
+            aspect_ratio: self.width as f32 / self.height as f32,
+            ..option.clone()
+        };
+        if let Some(outline) = &mut option.shading.outline {
+            outline.width *= samples as f32;
+        }
+        option
+    }
+
+    /// The picture from the rgba bytes drawn with `samples`, `stride` bytes a row.
+    /// `bottom_up` if the first row is the bottom one, `bgra` if red and blue are swapped
+    pub fn resolve(
+        &self,
+        bytes: &[u8],
+        stride: usize,
+        samples: u32,
+        bottom_up: bool,
+        bgra: bool,
+    ) -> egui::ColorImage {
+        let (width, height, s) = (self.width as usize, self.height as usize, samples as usize);
+        let count = (s * s) as u32;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0_u32; 4];
+                for sy in 0..s {
+                    let row = if bottom_up {
+                        (height - 1 - y) * s + (s - 1 - sy)
+                    } else {
+                        y * s + sy
+                    };
+                    for sx in 0..s {
+                        let i = row * stride + (x * s + sx) * 4;
+                        for (c, v) in sum.iter_mut().zip(&bytes[i..i + 4]) {
+                            *c += *v as u32;
+                        }
+                    }
+                }
+                let [r, g, b, a] = sum.map(|c| ((c + count / 2) / count) as u8);
+                let (r, b) = if bgra { (b, r) } else { (r, b) };
+                pixels.push(egui::Color32::from_rgba_premultiplied(r, g, b, a));
+            }
+        }
+        egui::ColorImage {
+            size: [width, height],
+            pixels,
+        }
+    }
+}
+
+pub trait LinesView: RenderView {
+    fn set_lines(&self, lines: Vec<my_items::Line>);
+    fn set_musk_enabled(&self, musk: bool);
+}
+
+#[allow(dead_code)]
+pub trait FacesView: RenderView {
+    fn set_faces(&self, faces: Vec<my_items::Face>);
+    fn set_musk_enabled(&self, musk: bool);
+    /// Draw the face of this id brighter, None to stop
+    fn set_highlighted(&self, id: Option<usize>);
+    /// The face drawn at `(x, y)` in clip space with these options
+    fn pick(&self, option: &GlPaintOptions, x: f32, y: f32) -> Option<my_items::pick::Picked>;
+    /// Draw the faces offscreen in the next paint callback, the highlighted face is highlighted too
+    fn capture(&self, pending: PendingCapture);
+}
+
+pub struct MyGLView {
+    pub basic: Arc<dyn RenderView>,
+    pub lines: Arc<dyn LinesView>,
+    pub faces: Arc<dyn FacesView>,
+}
+
+impl MyGLView {
+    /// The shader errors are sent to `messages`, the message panel
+    pub fn new(cc: &eframe::CreationContext<'_>, messages: MessageSender) -> Self {
+        if let Some(gl) = cc.gl.as_ref() {
+            Self {
+                basic: Arc::new(Mutex::new(GLGameView::new(gl, messages.clone()))),
+                lines: Arc::new(Mutex::new(GLLinesView::new(gl, messages.clone()))),
+                faces: Arc::new(Mutex::new(GLFacesView::new(gl, messages))),
+            }
+        } else if let Some(render_state) = cc.wgpu_render_state.as_ref() {
+            wgpu_views::install(render_state, messages);
+            Self {
+                basic: Arc::new(Mutex::new(WgpuGameView::default())),
+                lines: Arc::new(Mutex::new(WgpuLinesView::default())),
+                faces: Arc::new(Mutex::new(WgpuFacesView::default())),
+            }
+        } else {
+            panic!("You need to run eframe with the glow or the wgpu backend")
+        }
+    }
+    pub fn destroy_all(&self, gl: Option<&glow::Context>) {
+        self.basic.destroy(gl);
+        self.lines.destroy(gl);
+        self.faces.destroy(gl);
+    }
+}
+
+impl<T: GLGameBase + Send + 'static> RenderView for Mutex<T> {
+    fn paint_callback(
+        self: Arc<Self>,
+        rect: egui::Rect,
+        option: GlPaintOptions,
+    ) -> egui::PaintCallback {
+        egui::PaintCallback {
+            rect,
+            callback: Arc::new(egui_glow::CallbackFn::new(move |info, painter| {
+                let mut view = self.lock();
+                if let Some(pending) = view.take_capture() {
+                    use glow::HasContext as _;
+                    let gl = painter.gl();
+                    let image = unsafe {
+                        let image = capture(&mut *view, gl, &pending, painter.intermediate_fbo());
+                        // what egui set for the callback
+                        let px = info.viewport_in_pixels();
+                        gl.viewport(px.left_px, px.from_bottom_px, px.width_px, px.height_px);
+                        image
+                    };
+                    (pending.done)(image);
+                }
+                // the viewport set by egui, which is resized with the window
+                let option = GlPaintOptions {
+                    aspect_ratio: aspect_ratio_in_pixels(
+                        info.viewport,
+                        info.pixels_per_point,
+                        info.screen_size_px,
+                    ),
+                    ..option.clone()
+                };
+                view.paint(painter.gl(), &option);
+            })),
+        }
+    }
+    fn destroy(&self, gl: Option<&glow::Context>) {
+        if let Some(gl) = gl {
+            self.lock().destroy(gl);
+        }
+    }
+}
+
+impl LinesView for Mutex<GLLinesView> {
+    fn set_lines(&self, lines: Vec<my_items::Line>) {
+        self.lock().set_lines(lines);
+    }
+    fn set_musk_enabled(&self, musk: bool) {
+        self.lock().set_musk_enabled(musk);
+    }
+}
+
+impl FacesView for Mutex<GLFacesView> {
+    fn set_faces(&self, faces: Vec<my_items::Face>) {
+        self.lock().set_faces(faces);
+    }
+    fn set_musk_enabled(&self, musk: bool) {
+        self.lock().set_musk_enabled(musk);
+    }
+    fn set_highlighted(&self, id: Option<usize>) {
+        self.lock().set_highlighted(id);
+    }
+    fn pick(&self, option: &GlPaintOptions, x: f32, y: f32) -> Option<my_items::pick::Picked> {
+        self.lock().pick(option, x, y)
+    }
+    fn capture(&self, pending: PendingCapture) {
+        self.lock().set_capture(pending);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_are_supersampled() {
+        let capture = Capture {
+            width: 2,
+            height: 1,
+            supersample: 4,
+            background: [0.0; 4],
+        };
+        assert_eq!(capture.samples(4096), Ok(4));
+        assert_eq!(capture.samples(6), Ok(3));
+        assert!(capture.samples(1).is_err());
+        // 4 x 2 samples from the bottom, the left pixel red and the right one half blue
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let clear = [0, 0, 0, 0];
+        let rows = [[red, red, blue, clear], [red, red, clear, blue]];
+        let bytes: Vec<u8> = rows.iter().flatten().flatten().copied().collect();
+        let image = capture.resolve(&bytes, 16, 2, true, false);
+        assert_eq!(image.size, [2, 1]);
+        assert_eq!(image.pixels[0], egui::Color32::RED);
+        assert_eq!(
+            image.pixels[1],
+            egui::Color32::from_rgba_premultiplied(0, 0, 128, 128)
+        );
+        // red and blue swapped
+        let image = capture.resolve(&bytes, 16, 2, false, true);
+        assert_eq!(image.pixels[0], egui::Color32::BLUE);
+    }
+}
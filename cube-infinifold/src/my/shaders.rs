@@ -0,0 +1,168 @@
+//! The shaders of the views, in `assets/shaders`.
+//!
+//! Debug builds read them from the repository and load them again when a file is changed,
+//! release builds only use the ones built in.
+
+use std::{
+    path::PathBuf,
+    sync::mpsc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How the message panel is sent to, `MyMessage::send`
+pub type MessageSender = mpsc::Sender<(String, u64)>;
+
+/// how long a shader error stays in the message panel, in ms
+const ERROR_TIME: u64 = 10000;
+/// how long a reloaded shader is told in the message panel, in ms
+const RELOADED_TIME: u64 = 2000;
+/// how often the files are looked at
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShaderFile {
+    /// the file name in `assets/shaders`
+    pub name: &'static str,
+    /// the source built in, used in release builds and when the file can not be read
+    pub embedded: &'static str,
+}
+
+pub const BASIC_VS: ShaderFile = ShaderFile {
+    name: "basic.vs",
+    embedded: include_str!("../../../assets/shaders/basic.vs"),
+};
+pub const BASIC_FS: ShaderFile = ShaderFile {
+    name: "basic.fs",
+    embedded: include_str!("../../../assets/shaders/basic.fs"),
+};
+pub const LINES_VS: ShaderFile = ShaderFile {
+    name: "b_lines.vs",
+    embedded: include_str!("../../../assets/shaders/b_lines.vs"),
+};
+pub const LINES_FS: ShaderFile = ShaderFile {
+    name: "b_lines.fs",
+    embedded: include_str!("../../../assets/shaders/b_lines.fs"),
+};
+pub const FACES_VS: ShaderFile = ShaderFile {
+    name: "b_faces.vs",
+    embedded: include_str!("../../../assets/shaders/b_faces.vs"),
+};
+pub const FACES_FS: ShaderFile = ShaderFile {
+    name: "b_faces.fs",
+    embedded: include_str!("../../../assets/shaders/b_faces.fs"),
+};
+pub const BASIC_WGSL: ShaderFile = ShaderFile {
+    name: "basic.wgsl",
+    embedded: include_str!("../../../assets/shaders/basic.wgsl"),
+};
+pub const LINES_WGSL: ShaderFile = ShaderFile {
+    name: "b_lines.wgsl",
+    embedded: include_str!("../../../assets/shaders/b_lines.wgsl"),
+};
+pub const FACES_WGSL: ShaderFile = ShaderFile {
+    name: "b_faces.wgsl",
+    embedded: include_str!("../../../assets/shaders/b_faces.wgsl"),
+};
+
+impl ShaderFile {
+    /// where the file is in the repository the game was built from
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../assets/shaders")
+            .join(self.name)
+    }
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(self.path())
+            .and_then(|m| m.modified())
+            .ok()
+    }
+    /// The file in debug builds if it can be read, the embedded source otherwise
+    pub fn source(&self) -> String {
+        if cfg!(debug_assertions) {
+            if let Ok(source) = std::fs::read_to_string(self.path()) {
+                return source;
+            }
+        }
+        self.embedded.to_string()
+    }
+}
+
+/// The files of a program, it tells when they are changed on the disk
+pub struct Shaders {
+    pub files: Vec<ShaderFile>,
+    modified: Vec<Option<SystemTime>>,
+    checked: Instant,
+    messages: Option<MessageSender>,
+}
+
+impl Shaders {
+    pub fn new(files: &[ShaderFile]) -> Self {
+        Self {
+            files: files.to_vec(),
+            modified: files.iter().map(ShaderFile::modified).collect(),
+            checked: Instant::now(),
+            messages: None,
+        }
+    }
+    /// Errors and reloads are shown in the message panel too
+    pub fn with_messages(mut self, messages: MessageSender) -> Self {
+        self.messages = Some(messages);
+        self
+    }
+
+    /// The sources of the files, in the order of `files`
+    pub fn sources(&mut self) -> Vec<String> {
+        self.modified = self.files.iter().map(ShaderFile::modified).collect();
+        self.files.iter().map(ShaderFile::source).collect()
+    }
+    pub fn embedded(&self) -> Vec<String> {
+        self.files.iter().map(|f| f.embedded.to_string()).collect()
+    }
+    /// The sources again if any file is changed since the last `sources`,
+    /// never in release builds
+    pub fn reload(&mut self) -> Option<Vec<String>> {
+        if !cfg!(debug_assertions) || self.checked.elapsed() < CHECK_INTERVAL {
+            return None;
+        }
+        self.checked = Instant::now();
+        let modified: Vec<_> = self.files.iter().map(ShaderFile::modified).collect();
+        if modified == self.modified {
+            return None;
+        }
+        Some(self.sources())
+    }
+
+    /// Print the info log of a failed build and show it in the message panel
+    pub fn report_error(&self, log: &str) {
+        println!("{log}");
+        self.send(log.to_string(), ERROR_TIME);
+    }
+    pub fn report_reloaded(&self) {
+        let names: Vec<_> = self.files.iter().map(|f| f.name).collect();
+        self.send(format!("Reloaded {}", names.join(", ")), RELOADED_TIME);
+    }
+    fn send(&self, msg: String, time: u64) {
+        if let Some(messages) = &self.messages {
+            // the panel is gone when the app is closing
+            let _ = messages.send((msg, time));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_files() {
+        let mut shaders = Shaders::new(&[LINES_VS, LINES_FS]);
+        assert_eq!(shaders.sources().len(), 2);
+        for file in [BASIC_VS, BASIC_FS, FACES_VS, FACES_FS, BASIC_WGSL] {
+            assert!(file.path().exists(), "{}", file.name);
+            assert_eq!(file.source(), file.embedded);
+        }
+        // nothing is changed, so nothing is loaded again
+        shaders.checked -= CHECK_INTERVAL;
+        assert!(shaders.reload().is_none());
+    }
+}
@@ -0,0 +1,102 @@
+//! Drawing the theme of a chapter, see `my_items::theme`.
+//!
+//! The background is painted by egui under the faces, so it is the same on glow and wgpu.
+
+use eframe::egui;
+use my_items::{theme::Background, Color, Projection, V3};
+
+pub fn color32(c: &Color) -> egui::Color32 {
+    let u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    egui::Color32::from_rgba_unmultiplied(u8(c.r), u8(c.g), u8(c.b), u8(c.a))
+}
+
+/// Paint the background over `rect`, `time` in seconds moves the dots
+pub fn paint_background(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    background: &Background,
+    proj: &Projection,
+    time: f64,
+) {
+    let painter = painter.with_clip_rect(rect);
+    match background {
+        Background::None => (),
+        Background::Plain(c) => {
+            painter.rect_filled(rect, 0.0, color32(c));
+        }
+        Background::Gradient { top, bottom } => {
+            let (top, bottom) = (color32(top), color32(bottom));
+            let mut mesh = egui::Mesh::default();
+            mesh.colored_vertex(rect.left_top(), top);
+            mesh.colored_vertex(rect.right_top(), top);
+            mesh.colored_vertex(rect.left_bottom(), bottom);
+            mesh.colored_vertex(rect.right_bottom(), bottom);
+            mesh.add_triangle(0, 1, 2);
+            mesh.add_triangle(1, 3, 2);
+            painter.add(mesh);
+        }
+        Background::Grid {
+            back,
+            line,
+            y,
+            spacing,
+        } => {
+            painter.rect_filled(rect, 0.0, color32(back));
+            paint_grid(&painter, rect, proj, *y, *spacing, color32(line));
+        }
+        Background::Dots {
+            back,
+            dot,
+            spacing,
+            speed,
+        } => {
+            painter.rect_filled(rect, 0.0, color32(back));
+            let offset = (time as f32 * speed).rem_euclid(*spacing);
+            let color = color32(dot);
+            let mut y = rect.min.y - spacing + offset;
+            while y < rect.max.y + spacing {
+                let mut x = rect.min.x - spacing + offset;
+                while x < rect.max.x + spacing {
+                    painter.circle_filled(egui::pos2(x, y), 1.5, color);
+                    x += spacing;
+                }
+                y += spacing;
+            }
+        }
+    }
+}
+
+/// The lines of the plane at the height `y` around the middle of the view
+fn paint_grid(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    proj: &Projection,
+    y: f32,
+    spacing: f32,
+    color: egui::Color32,
+) {
+    // the view is 2 high and the plane is seen aslant, so it reaches further than the screen
+    let reach = 4.0 * proj.aspect_ratio.max(1.0) / proj.scale.max(f32::EPSILON);
+    let count = ((reach / spacing).ceil() as i32).min(200);
+    // the lines stay where they are in the world when the camera moves
+    let snap = |v: f32| (v / spacing).round() * spacing;
+    let (cx, cz) = (snap(-proj.translate.x), snap(-proj.translate.z));
+    let to_screen = |x: f32, z: f32| {
+        let c = proj.face_clip(&V3::from(x, y, z), 0.0);
+        let (x, y) = Projection::clip_to_pixel(c.x, c.y, rect.width(), rect.height());
+        rect.min + egui::vec2(x, y)
+    };
+    let stroke = egui::Stroke::new(1.0, color);
+    let far = count as f32 * spacing;
+    for i in -count..=count {
+        let d = i as f32 * spacing;
+        painter.line_segment(
+            [to_screen(cx + d, cz - far), to_screen(cx + d, cz + far)],
+            stroke,
+        );
+        painter.line_segment(
+            [to_screen(cx - far, cz + d), to_screen(cx + far, cz + d)],
+            stroke,
+        );
+    }
+}
@@ -0,0 +1,1012 @@
+//! The views of `gl_views` for the wgpu backend, drawn with the wgsl versions of the shaders.
+//!
+//! The pipelines and buffers are kept in the `CallbackResources` of egui_wgpu, one set for every
+//! kind of view. Unlike glow, the depth buffer can not be cleared in the middle of a frame,
+//! so at most one view is painted in a frame.
+//! A capture is drawn into textures of its own and submitted before the frame, see `Resources::capture`.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use eframe::{
+    egui::{self, mutex::Mutex},
+    egui_wgpu::{self, wgpu},
+};
+use wgpu::util::DeviceExt as _;
+
+use super::{
+    gl_views::{
+        as_bytes, mask_texture_data, push_musks, GLFacesView, GLLinesView, GlPaintOptions, Mesh,
+        MASK_TEXTURE_WIDTH,
+    },
+    render::{
+        aspect_ratio_in_pixels, FacesView, LinesView, PendingCapture, RenderView, DEPTH_BUFFER,
+        MULTISAMPLING,
+    },
+    shaders::{self, MessageSender, ShaderFile, Shaders},
+};
+
+/// A view drawn by wgpu, it keeps what to draw and `Resources` upload it when it is changed
+pub trait WgpuGameBase: Send + 'static {
+    /// the wgsl with `vs_main` and `fs_main`
+    const SHADER: ShaderFile;
+    /// how many floats every attribute of a vertex takes
+    const SIZES: &'static [i32];
+    const TOPOLOGY: wgpu::PrimitiveTopology;
+    /// The mesh to upload, None if it is not changed since the last time
+    fn mesh(&mut self, option: &GlPaintOptions) -> Option<Mesh>;
+    /// `mask_enabled`, `highlighted` and `highlight` of the uniforms
+    fn uniforms(&self) -> [f32; 3];
+    /// The capture to draw before the view is painted, see `render::FacesView::capture`
+    fn take_capture(&mut self) -> Option<PendingCapture> {
+        None
+    }
+}
+
+/// The blending of egui, the colors are taken as premultiplied
+const BLEND: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation: wgpu::BlendOperation::Add,
+    },
+    alpha: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::OneMinusDstAlpha,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    },
+};
+
+/// Make the pipelines of the views, before any of them is painted
+pub fn install(render_state: &egui_wgpu::RenderState, messages: MessageSender) {
+    let (device, queue) = (&render_state.device, &render_state.queue);
+    let format = render_state.target_format;
+    let resources = &mut render_state.renderer.write().callback_resources;
+    resources.insert(
+        Resources::<WgpuGameView>::new(device, queue, format).with_messages(messages.clone()),
+    );
+    resources.insert(
+        Resources::<WgpuLinesView>::new(device, queue, format).with_messages(messages.clone()),
+    );
+    resources
+        .insert(Resources::<WgpuFacesView>::new(device, queue, format).with_messages(messages));
+}
+
+/// The futures of wgpu are ready at once on native
+fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    let mut f = std::pin::pin!(f);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+            return v;
+        }
+    }
+}
+
+/// The pipeline and buffers of a kind of view
+struct Resources<V> {
+    pipeline: wgpu::RenderPipeline,
+    /// what the pipeline is made again from when the shader is changed
+    layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    shaders: Shaders,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// `Uniforms` of the shaders
+    uniform_buffer: wgpu::Buffer,
+    /// the uniforms and the texture of the musks
+    bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    /// the triangles of the faces, None if the vertices are drawn in order
+    index_buffer: Option<wgpu::Buffer>,
+    count: u32,
+    view: PhantomData<fn() -> V>,
+}
+
+impl<V: WgpuGameBase> Resources<V> {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let visibility = wgpu::ShaderStages::VERTEX_FRAGMENT;
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let mut shaders = Shaders::new(&[V::SHADER]);
+        let pipeline = Self::create_pipeline(device, &layout, format, &shaders.sources()[0])
+            .unwrap_or_else(|log| {
+                shaders.report_error(&log);
+                Self::create_pipeline(device, &layout, format, V::SHADER.embedded)
+                    .expect("The built in shader is broken")
+            });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: std::mem::size_of::<[f32; 44]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = Self::bind_group(device, queue, &bind_group_layout, &uniform_buffer, &[]);
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            layout,
+            format,
+            shaders,
+            bind_group_layout,
+            uniform_buffer,
+            bind_group,
+            vertex_buffer,
+            index_buffer: None,
+            count: 0,
+            view: PhantomData,
+        }
+    }
+
+    /// Errors and reloads of the shader are shown in the message panel too
+    fn with_messages(mut self, messages: MessageSender) -> Self {
+        self.shaders = self.shaders.with_messages(messages);
+        self
+    }
+
+    /// The pipeline of the wgsl `source`, the validation error if it is broken
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        source: &str,
+    ) -> Result<wgpu::RenderPipeline, String> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(V::SHADER.name),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let mut stride = 0;
+        let attributes: Vec<_> = V::SIZES
+            .iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let format = match size {
+                    1 => wgpu::VertexFormat::Float32,
+                    2 => wgpu::VertexFormat::Float32x2,
+                    3 => wgpu::VertexFormat::Float32x3,
+                    _ => wgpu::VertexFormat::Float32x4,
+                };
+                let attribute = wgpu::VertexAttribute {
+                    format,
+                    offset: stride,
+                    shader_location: i as u32,
+                };
+                stride += format.size();
+                attribute
+            })
+            .collect();
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: stride,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &attributes,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(BLEND),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: V::TOPOLOGY,
+                ..Default::default()
+            },
+            // the same as the depth buffer of egui_wgpu, and `LEQUAL` of the glow views
+            depth_stencil: egui_wgpu::depth_format_from_bits(DEPTH_BUFFER, 0).map(|format| {
+                wgpu::DepthStencilState {
+                    format,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil: Default::default(),
+                    bias: Default::default(),
+                }
+            }),
+            multisample: wgpu::MultisampleState {
+                count: MULTISAMPLING as u32,
+                ..Default::default()
+            },
+            multiview: None,
+        });
+        match block_on(device.pop_error_scope()) {
+            Some(err) => Err(format!("Failed to build {}: {err}", V::SHADER.name)),
+            None => Ok(pipeline),
+        }
+    }
+
+    /// The uniforms with the musks in a `MASK_TEXTURE_WIDTH` wide float texture
+    fn bind_group(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        texels: &[[f32; 4]],
+    ) -> wgpu::BindGroup {
+        let (data, height) = mask_texture_data(texels);
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: MASK_TEXTURE_WIDTH as u32,
+                    height: height as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            unsafe { as_bytes(&data) },
+        );
+        let texture_view = texture.create_view(&Default::default());
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+            ],
+        })
+    }
+
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, mesh: &Mesh) {
+        self.vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: unsafe { as_bytes(&mesh.vertices) },
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        if mesh.elements.is_empty() {
+            self.index_buffer = None;
+            let stride = V::SIZES.iter().sum::<i32>() as usize;
+            self.count = (mesh.vertices.len() / stride) as u32;
+        } else {
+            self.index_buffer = Some(device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: unsafe { as_bytes(&mesh.elements) },
+                    usage: wgpu::BufferUsages::INDEX,
+                },
+            ));
+            self.count = mesh.elements.len() as u32;
+        }
+        self.bind_group = Self::bind_group(
+            device,
+            queue,
+            &self.bind_group_layout,
+            &self.uniform_buffer,
+            &mesh.texels,
+        );
+    }
+
+    /// Upload the view if it is changed, and the uniforms of the options
+    fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view: &mut V,
+        option: &GlPaintOptions,
+    ) {
+        if let Some(sources) = self.shaders.reload() {
+            match Self::create_pipeline(device, &self.layout, self.format, &sources[0]) {
+                Ok(pipeline) => {
+                    self.pipeline = pipeline;
+                    self.shaders.report_reloaded();
+                }
+                Err(log) => self.shaders.report_error(&log),
+            }
+        }
+        if let Some(mesh) = view.mesh(option) {
+            self.upload(device, queue, &mesh);
+        }
+        // the columns of a mat3 and the vec3 take 4 floats
+        let p = option.get_projection_mat();
+        let pj = option.get_pj_mat();
+        let [mask_enabled, highlighted, highlight] = view.uniforms();
+        let (light, ambient) = option.shading.light_uniforms();
+        let (outline_color, outline_width) = option.shading.outline_uniforms();
+        let uniforms = [
+            p[0],
+            p[1],
+            p[2],
+            0.0,
+            p[3],
+            p[4],
+            p[5],
+            0.0,
+            p[6],
+            p[7],
+            p[8],
+            0.0,
+            option.aspect_ratio,
+            mask_enabled,
+            highlighted,
+            highlight,
+            option.translate.x,
+            option.translate.y,
+            option.translate.z,
+            0.0,
+            pj[0],
+            pj[1],
+            pj[2],
+            0.0,
+            pj[3],
+            pj[4],
+            pj[5],
+            0.0,
+            pj[6],
+            pj[7],
+            pj[8],
+            0.0,
+            light[0],
+            light[1],
+            light[2],
+            ambient,
+            outline_color[0],
+            outline_color[1],
+            outline_color[2],
+            outline_color[3],
+            outline_width,
+            0.0,
+            0.0,
+            0.0,
+        ];
+        queue.write_buffer(&self.uniform_buffer, 0, unsafe { as_bytes(&uniforms) });
+    }
+
+    /// Draw the view into textures of the size of the capture and read it back,
+    /// the frame waits for it
+    fn capture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view: &mut V,
+        pending: &PendingCapture,
+    ) -> Result<egui::ColorImage, String> {
+        let capture = &pending.capture;
+        let samples = capture.samples(device.limits().max_texture_dimension_2d)?;
+        self.prepare(
+            device,
+            queue,
+            view,
+            &capture.option(&pending.option, samples),
+        );
+
+        let (width, height) = (capture.width * samples, capture.height * samples);
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = |format, sample_count, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size,
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let multisampling = MULTISAMPLING as u32;
+        let msaa = texture(
+            self.format,
+            multisampling,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+        let target = texture(
+            self.format,
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        );
+        let depth = texture(
+            egui_wgpu::depth_format_from_bits(DEPTH_BUFFER, 0).ok_or("There is no depth format")?,
+            multisampling,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+        let (msaa_view, target_view, depth_view) = (
+            msaa.create_view(&Default::default()),
+            target.create_view(&Default::default()),
+            depth.create_view(&Default::default()),
+        );
+        // the rows of a copy are aligned
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let stride = (width * 4).div_ceil(align) * align;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (stride * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let [r, g, b, a] = capture.background.map(|c| c as f64);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &msaa_view,
+                    resolve_target: Some(&target_view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.paint(&mut render_pass);
+        }
+        encoder.copy_texture_to_buffer(
+            target.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(stride),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        queue.submit([encoder.finish()]);
+        buffer.slice(..).map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::Maintain::Wait);
+        let bytes = buffer.slice(..).get_mapped_range();
+        let bgra = matches!(
+            self.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        Ok(capture.resolve(&bytes, stride as usize, samples, false, bgra))
+    }
+
+    fn paint<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        if let Some(index_buffer) = &self.index_buffer {
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..self.count, 0, 0..1);
+        } else {
+            render_pass.draw(0..self.count, 0..1);
+        }
+    }
+}
+
+struct Callback<V> {
+    view: Arc<Mutex<V>>,
+    option: GlPaintOptions,
+    /// where the view is painted, in points
+    rect: egui::Rect,
+}
+
+impl<V: WgpuGameBase> egui_wgpu::CallbackTrait for Callback<V> {
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        screen_descriptor: &egui_wgpu::ScreenDescriptor,
+        _egui_encoder: &mut wgpu::CommandEncoder,
+        callback_resources: &mut egui_wgpu::CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        if let Some(resources) = callback_resources.get_mut::<Resources<V>>() {
+            let mut view = self.view.lock();
+            if let Some(pending) = view.take_capture() {
+                let image = resources.capture(device, queue, &mut view, &pending);
+                (pending.done)(image);
+            }
+            // the viewport set by egui, which is resized with the window
+            let option = GlPaintOptions {
+                aspect_ratio: aspect_ratio_in_pixels(
+                    self.rect,
+                    screen_descriptor.pixels_per_point,
+                    screen_descriptor.size_in_pixels,
+                ),
+                ..self.option.clone()
+            };
+            resources.prepare(device, queue, &mut view, &option);
+        }
+        vec![]
+    }
+
+    fn paint<'a>(
+        &'a self,
+        _info: egui::PaintCallbackInfo,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        callback_resources: &'a egui_wgpu::CallbackResources,
+    ) {
+        if let Some(resources) = callback_resources.get::<Resources<V>>() {
+            resources.paint(render_pass);
+        }
+    }
+}
+
+fn paint_callback<V: WgpuGameBase>(
+    view: Arc<Mutex<V>>,
+    rect: egui::Rect,
+    option: GlPaintOptions,
+) -> egui::PaintCallback {
+    egui_wgpu::Callback::new_paint_callback(rect, Callback { view, option, rect })
+}
+
+/// `GLGameView` for wgpu, the points are turned on the cpu
+#[derive(Default)]
+pub struct WgpuGameView {
+    /// the angle the mesh was made at
+    angle: Option<f32>,
+}
+
+impl WgpuGameView {
+    /// The quads of `GLGameView::paint`, as triangles of two layers
+    fn mesh_at(angle: f32) -> Mesh {
+        // Left, Top, Bottom, Right
+        let points = [
+            [0.7, 0.0, 0.2],
+            [0.0, 0.7, 0.2],
+            [0.0, -0.7, 0.2],
+            [-0.7, 0.0, 0.2],
+        ];
+        let colors = [
+            [0.0, 1.0, 0.5, 0.6],
+            [1.0, 0.5, 0.4, 1.0],
+            [1.0, 0.5, 0.0, 1.0],
+            [0.5, 0.0, 1.0, 1.0],
+        ];
+        let mut vertices: Vec<f32> = vec![];
+        let mut push = |p: &[f32; 3], c: &[f32; 4], layer: f32| {
+            vertices.extend_from_slice(p);
+            vertices.extend_from_slice(c);
+            vertices.push(layer);
+        };
+        for i in 0..10 {
+            let (sin, cos) = (angle + (36.0 * i as f32).to_radians()).sin_cos();
+            let turned = points.map(|[x, y, z]| [cos * x + sin * z, y, cos * z - sin * x]);
+            // the strips of the base layer and the permuted layer over it
+            for i in [0, 1, 2, 1, 2, 3] {
+                push(&turned[i], &colors[i], 0.0);
+            }
+            for i in [1, 0, 3, 0, 3, 2] {
+                push(&turned[i], &colors[i], 1.0);
+            }
+        }
+        let points = [
+            [0.0, 0.0, 0.0],
+            [1.0, 1.0, -0.05],
+            [1.0, 1.05, 0.0],
+            [0.0, 0.05, 0.0],
+        ];
+        for i in [0, 1, 2, 1, 2, 3] {
+            push(&points[i], &[1.0, 0.0, 0.0, 1.0], 0.0);
+        }
+
+        let mut texels = vec![];
+        let musk = my_items::Musk::new(
+            my_items::V3::from(0.0, 0.0, 0.0),
+            my_items::V3::from(1.0, 1.0, 0.0),
+        );
+        push_musks(&mut texels, &[musk]);
+        Mesh {
+            vertices,
+            elements: vec![],
+            texels,
+        }
+    }
+}
+
+impl WgpuGameBase for WgpuGameView {
+    const SHADER: ShaderFile = shaders::BASIC_WGSL;
+    const SIZES: &'static [i32] = &[3, 4, 1];
+    const TOPOLOGY: wgpu::PrimitiveTopology = wgpu::PrimitiveTopology::TriangleList;
+
+    fn mesh(&mut self, option: &GlPaintOptions) -> Option<Mesh> {
+        let angle = option.angle;
+        if self.angle == Some(angle) {
+            return None;
+        }
+        self.angle = Some(angle);
+        Some(Self::mesh_at(angle))
+    }
+    fn uniforms(&self) -> [f32; 3] {
+        [1.0, -1.0, 0.0]
+    }
+}
+
+impl RenderView for Mutex<WgpuGameView> {
+    fn paint_callback(
+        self: Arc<Self>,
+        rect: egui::Rect,
+        option: GlPaintOptions,
+    ) -> egui::PaintCallback {
+        paint_callback(self, rect, option)
+    }
+}
+
+/// `GLLinesView` for wgpu
+pub struct WgpuLinesView {
+    lines: Vec<my_items::Line>,
+    musk_enabled: bool,
+    /// the lines are changed after they were uploaded
+    dirty: bool,
+    /// the angle the colors were uploaded at, if any color depends on it
+    colors_angle: Option<f32>,
+}
+
+impl Default for WgpuLinesView {
+    fn default() -> Self {
+        Self {
+            lines: vec![],
+            musk_enabled: true,
+            dirty: true,
+            colors_angle: None,
+        }
+    }
+}
+
+impl WgpuGameBase for WgpuLinesView {
+    const SHADER: ShaderFile = shaders::LINES_WGSL;
+    const SIZES: &'static [i32] = &GLLinesView::SIZES;
+    const TOPOLOGY: wgpu::PrimitiveTopology = wgpu::PrimitiveTopology::LineList;
+
+    fn mesh(&mut self, option: &GlPaintOptions) -> Option<Mesh> {
+        let angle = option.angle;
+        if !self.dirty && self.colors_angle.is_none_or(|a| a == angle) {
+            return None;
+        }
+        self.colors_angle = self
+            .lines
+            .iter()
+            .any(|l| l.color.is_angled())
+            .then_some(angle);
+        self.dirty = false;
+        Some(Mesh::lines(&self.lines, angle))
+    }
+    fn uniforms(&self) -> [f32; 3] {
+        [self.musk_enabled as i32 as f32, -1.0, 0.0]
+    }
+}
+
+impl RenderView for Mutex<WgpuLinesView> {
+    fn paint_callback(
+        self: Arc<Self>,
+        rect: egui::Rect,
+        option: GlPaintOptions,
+    ) -> egui::PaintCallback {
+        paint_callback(self, rect, option)
+    }
+}
+
+impl LinesView for Mutex<WgpuLinesView> {
+    fn set_lines(&self, lines: Vec<my_items::Line>) {
+        let mut view = self.lock();
+        view.lines = lines;
+        view.dirty = true;
+    }
+    fn set_musk_enabled(&self, musk: bool) {
+        self.lock().musk_enabled = musk;
+    }
+}
+
+/// `GLFacesView` for wgpu
+pub struct WgpuFacesView {
+    faces: Vec<my_items::Face>,
+    musk_enabled: bool,
+    highlighted: Option<usize>,
+    /// the faces are changed after they were uploaded
+    dirty: bool,
+    /// the angle the colors were uploaded at, if any color depends on it
+    colors_angle: Option<f32>,
+    /// the ids of the faces in the order they were uploaded, the translucent ones sorted
+    order: Vec<usize>,
+    /// drawn in the next paint
+    capture: Option<PendingCapture>,
+}
+
+impl Default for WgpuFacesView {
+    fn default() -> Self {
+        Self {
+            faces: vec![],
+            musk_enabled: true,
+            highlighted: None,
+            dirty: true,
+            colors_angle: None,
+            order: vec![],
+            capture: None,
+        }
+    }
+}
+
+impl WgpuGameBase for WgpuFacesView {
+    const SHADER: ShaderFile = shaders::FACES_WGSL;
+    const SIZES: &'static [i32] = &GLFacesView::SIZES;
+    const TOPOLOGY: wgpu::PrimitiveTopology = wgpu::PrimitiveTopology::TriangleList;
+
+    fn mesh(&mut self, option: &GlPaintOptions) -> Option<Mesh> {
+        let angle = option.angle;
+        // the translucent faces are uploaded again when they are sorted otherwise
+        let order = my_items::order::draw_order(&self.faces, &option.projection());
+        if !self.dirty && self.colors_angle.is_none_or(|a| a == angle) && order == self.order {
+            return None;
+        }
+        self.colors_angle = self
+            .faces
+            .iter()
+            .any(|f| f.color.is_angled())
+            .then_some(angle);
+        self.dirty = false;
+        let mesh = Mesh::faces(&self.faces, angle, &order);
+        self.order = order;
+        Some(mesh)
+    }
+    fn uniforms(&self) -> [f32; 3] {
+        [
+            self.musk_enabled as i32 as f32,
+            self.highlighted.map_or(-1.0, |id| id as f32),
+            GLFacesView::HIGHLIGHT,
+        ]
+    }
+    fn take_capture(&mut self) -> Option<PendingCapture> {
+        self.capture.take()
+    }
+}
+
+impl RenderView for Mutex<WgpuFacesView> {
+    fn paint_callback(
+        self: Arc<Self>,
+        rect: egui::Rect,
+        option: GlPaintOptions,
+    ) -> egui::PaintCallback {
+        paint_callback(self, rect, option)
+    }
+}
+
+impl FacesView for Mutex<WgpuFacesView> {
+    fn set_faces(&self, faces: Vec<my_items::Face>) {
+        let mut view = self.lock();
+        view.faces = faces;
+        view.dirty = true;
+    }
+    fn set_musk_enabled(&self, musk: bool) {
+        self.lock().musk_enabled = musk;
+    }
+    fn set_highlighted(&self, id: Option<usize>) {
+        self.lock().highlighted = id;
+    }
+    fn pick(&self, option: &GlPaintOptions, x: f32, y: f32) -> Option<my_items::pick::Picked> {
+        let view = self.lock();
+        my_items::pick::pick_face(&view.faces, &option.projection(), x, y, view.musk_enabled)
+    }
+    fn capture(&self, pending: PendingCapture) {
+        self.lock().capture = Some(pending);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::my::{render::Capture, shaders::LINES_WGSL};
+    use my_items::{Color, Colored, Face, Musk, Pillar, V3};
+
+    const SIZE: u32 = 64;
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    /// Any adapter there is, the software one on machines without a gpu
+    fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::default();
+        let adapter = block_on(instance.request_adapter(&Default::default()))?;
+        let desc = wgpu::DeviceDescriptor {
+            required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                .using_resolution(adapter.limits()),
+            ..Default::default()
+        };
+        block_on(adapter.request_device(&desc, None)).ok()
+    }
+
+    /// Draw the faces into a black `SIZE` square like the game view, in rgba rows
+    fn render(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: Vec<Face>,
+        option: &GlPaintOptions,
+    ) -> Vec<u8> {
+        let mut resources = Resources::<WgpuFacesView>::new(device, queue, FORMAT);
+        let mut view = WgpuFacesView {
+            faces,
+            ..Default::default()
+        };
+        let pending = PendingCapture {
+            capture: Capture {
+                width: SIZE,
+                height: SIZE,
+                supersample: 1,
+                background: [0.0, 0.0, 0.0, 1.0],
+            },
+            option: option.clone(),
+            done: Box::new(|_| ()),
+        };
+        let image = resources
+            .capture(device, queue, &mut view, &pending)
+            .unwrap();
+        image.pixels.iter().flat_map(|p| p.to_array()).collect()
+    }
+
+    #[test]
+    fn views_upload() {
+        let Some((device, queue)) = device() else {
+            println!("No wgpu adapter, skipped");
+            return;
+        };
+        let option = GlPaintOptions::default();
+        let mut basic = Resources::<WgpuGameView>::new(&device, &queue, FORMAT);
+        basic.prepare(&device, &queue, &mut WgpuGameView::default(), &option);
+        assert_eq!(basic.count, 10 * 12 + 6);
+        let mut lines = Resources::<WgpuLinesView>::new(&device, &queue, FORMAT);
+        let mut view = WgpuLinesView {
+            lines: vec![my_items::Line::default_with(0.0, 0.0, 0.0, 1.0, 1.0, 1.0)],
+            ..Default::default()
+        };
+        lines.prepare(&device, &queue, &mut view, &option);
+        assert_eq!(lines.count, 2);
+        // nothing is changed
+        assert!(view.mesh(&option).is_none());
+    }
+
+    #[test]
+    fn broken_shader_is_reported() {
+        let Some((device, queue)) = device() else {
+            println!("No wgpu adapter, skipped");
+            return;
+        };
+        let resources = Resources::<WgpuLinesView>::new(&device, &queue, FORMAT);
+        let broken = LINES_WGSL.embedded.replace("fn fs_main", "fn fs_broken");
+        let err = Resources::<WgpuLinesView>::create_pipeline(
+            &device,
+            &resources.layout,
+            FORMAT,
+            &broken,
+        )
+        .unwrap_err();
+        assert!(err.contains("b_lines.wgsl"), "{err}");
+    }
+
+    #[test]
+    fn capture_with_padded_rows() {
+        let Some((device, queue)) = device() else {
+            println!("No wgpu adapter, skipped");
+            return;
+        };
+        let mut resources = Resources::<WgpuFacesView>::new(&device, &queue, FORMAT);
+        let mut view = WgpuFacesView {
+            faces: Pillar::new_upright(V3::from(-1.0, -1.0, -1.0), V3::from(2.0, 2.0, 2.0))
+                .into_vec(),
+            ..Default::default()
+        };
+        // 30 pixels of 2 samples are not a row of 256 bytes
+        let pending = PendingCapture {
+            capture: Capture {
+                width: 30,
+                height: 20,
+                supersample: 2,
+                background: [0.0; 4],
+            },
+            option: GlPaintOptions {
+                scale: 0.3,
+                ..Default::default()
+            },
+            done: Box::new(|_| ()),
+        };
+        let image = resources
+            .capture(&device, &queue, &mut view, &pending)
+            .unwrap();
+        assert_eq!(image.size, [30, 20]);
+        assert_eq!(image.pixels[0], egui::Color32::TRANSPARENT);
+        assert_ne!(image.pixels[10 * 30 + 15], egui::Color32::TRANSPARENT);
+    }
+
+    #[test]
+    fn faces_match_rasterizer() {
+        let Some((device, queue)) = device() else {
+            println!("No wgpu adapter, skipped");
+            return;
+        };
+        let option = GlPaintOptions {
+            angle: 0.3,
+            scale: 0.3,
+            ..Default::default()
+        };
+        let mut faces =
+            Pillar::new_upright(V3::from(-1.0, -1.0, -1.0), V3::from(2.0, 2.0, 2.0)).into_vec();
+        // a face in front, with its upper half cut off
+        faces.push(
+            Face::new(
+                V3::from(-2.0, -2.0, 2.0),
+                V3::from(2.0, -2.0, 2.0),
+                V3::from(-2.0, 2.0, 2.0),
+                V3::from(2.0, 2.0, 2.0),
+            )
+            .with_color(Colored::Pure(Color::from(0.2, 0.4, 0.8, 1.0)))
+            .with_musk(Musk::new_on_screen(0.0, 0.0, 1.0, 0.0)),
+        );
+
+        let actual = render(&device, &queue, faces.clone(), &option);
+        let black = Color::from(0.0, 0.0, 0.0, 1.0);
+        let expected = my_rasterizer::render(&faces, &[], &option.projection(), SIZE, SIZE, black);
+        assert!(actual.chunks(4).any(|p| p != [0, 0, 0, 255]));
+
+        // the edges are multisampled, the rest is the same
+        let different = expected
+            .pixels()
+            .zip(actual.chunks(4))
+            .filter(|(e, a)| e.0.iter().zip(a.iter()).any(|(e, a)| e.abs_diff(*a) > 2))
+            .count();
+        assert!(
+            different < (SIZE * SIZE / 10) as usize,
+            "{different} pixels are different"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! The settings kept between runs, in `settings.json` of the working directory.
+//!
+//! F11 switches the window between fullscreen, borderless and windowed,
+//! the size of the window is kept for the windowed mode.
+//! Shift+F2 captures the faces of the level alone, at the size of `CaptureSettings`.
+
+use eframe::egui;
+use my_levels_finder::{get_json, js_obj_num, js_obj_str};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WindowMode {
+    #[default]
+    Fullscreen,
+    /// a window without decorations over the whole screen
+    Borderless,
+    Windowed,
+}
+
+impl WindowMode {
+    pub const ALL: [Self; 3] = [Self::Fullscreen, Self::Borderless, Self::Windowed];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fullscreen => "fullscreen",
+            Self::Borderless => "borderless",
+            Self::Windowed => "windowed",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// The window of the mode when the game starts
+    pub fn viewport(
+        self,
+        builder: egui::ViewportBuilder,
+        size: egui::Vec2,
+    ) -> egui::ViewportBuilder {
+        let builder = builder.with_resizable(true).with_inner_size(size);
+        match self {
+            Self::Fullscreen => builder.with_fullscreen(true),
+            Self::Borderless => builder.with_decorations(false).with_maximized(true),
+            Self::Windowed => builder,
+        }
+    }
+    /// Change the window to the mode while the game runs, `size` is the size of the windowed mode
+    pub fn commands(self, size: egui::Vec2) -> Vec<egui::ViewportCommand> {
+        use egui::ViewportCommand as C;
+        match self {
+            Self::Fullscreen => vec![C::Fullscreen(true)],
+            Self::Borderless => vec![
+                C::Fullscreen(false),
+                C::Decorations(false),
+                C::Maximized(true),
+            ],
+            Self::Windowed => vec![
+                C::Fullscreen(false),
+                C::Decorations(true),
+                C::Maximized(false),
+                C::InnerSize(size),
+            ],
+        }
+    }
+}
+
+/// The clean capture, the faces drawn offscreen without the buttons and the panels
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaptureSettings {
+    /// in pixels
+    pub width: u32,
+    pub height: u32,
+    /// the samples of a side of a pixel
+    pub supersample: u32,
+    /// leave the background out
+    pub transparent: bool,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            width: 3840,
+            height: 2160,
+            supersample: 2,
+            transparent: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub window: WindowMode,
+    /// the inner size of the windowed mode, in points
+    pub window_size: egui::Vec2,
+    pub capture: CaptureSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window: WindowMode::default(),
+            window_size: egui::vec2(1280.0, 720.0),
+            capture: CaptureSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    const FILE: &'static str = "settings.json";
+    /// the windowed mode is not made smaller than this
+    const MIN_SIZE: f32 = 200.0;
+
+    /// The saved settings, the defaults for what is missing
+    pub fn load() -> Self {
+        Self::from_json(&get_json(Self::FILE))
+    }
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::write(Self::FILE, self.to_json().pretty(2))
+            .map_err(|err| format!("Cannot save {}: {err}", Self::FILE))
+    }
+
+    fn from_json(j: &json::JsonValue) -> Self {
+        let default = Self::default();
+        let size =
+            |key, default: f32| (js_obj_num(j, key, default as i64) as f32).max(Self::MIN_SIZE);
+        let pixels = |key, default: u32| js_obj_num(j, key, default as i64).clamp(1, 16384) as u32;
+        Self {
+            window: WindowMode::from_name(js_obj_str(j, "window", "")).unwrap_or(default.window),
+            window_size: egui::vec2(
+                size("window_width", default.window_size.x),
+                size("window_height", default.window_size.y),
+            ),
+            capture: CaptureSettings {
+                width: pixels("capture_width", default.capture.width),
+                height: pixels("capture_height", default.capture.height),
+                supersample: js_obj_num(
+                    j,
+                    "capture_supersample",
+                    default.capture.supersample as i64,
+                )
+                .clamp(1, 8) as u32,
+                transparent: j["capture_transparent"]
+                    .as_bool()
+                    .unwrap_or(default.capture.transparent),
+            },
+        }
+    }
+    fn to_json(&self) -> json::JsonValue {
+        json::object! {
+            window: self.window.name(),
+            window_width: self.window_size.x.round() as i64,
+            window_height: self.window_size.y.round() as i64,
+            capture_width: self.capture.width,
+            capture_height: self.capture.height,
+            capture_supersample: self.capture.supersample,
+            capture_transparent: self.capture.transparent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_and_loaded() {
+        let settings = Settings {
+            window: WindowMode::Borderless,
+            window_size: egui::vec2(800.0, 600.0),
+            capture: CaptureSettings {
+                width: 1000,
+                height: 1000,
+                supersample: 3,
+                transparent: true,
+            },
+        };
+        assert_eq!(Settings::from_json(&settings.to_json()), settings);
+        // what is missing or broken is the default
+        let j = json::parse(r#"{"window": "tiny", "window_height": 10}"#).unwrap();
+        let loaded = Settings::from_json(&j);
+        assert_eq!(loaded.window, WindowMode::Fullscreen);
+        assert_eq!(loaded.window_size, egui::vec2(1280.0, 200.0));
+        assert_eq!(loaded.capture, CaptureSettings::default());
+        assert_eq!(WindowMode::Windowed.next(), WindowMode::Fullscreen);
+    }
+}
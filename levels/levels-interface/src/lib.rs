@@ -24,11 +24,21 @@ pub mod variables_functions_names {
     // selective
     pub const WHEN_ANGLED: B = b"when_angled\0";
     pub const GET_FACES: B = b"get_faces\0";
+    pub const WHEN_CLICKED: B = b"when_clicked\0";
+    pub const GET_VIEW: B = b"get_view\0";
+    pub const GET_THRESHOLDS: B = b"get_thresholds\0";
+    pub const GET_PROJECTION: B = b"get_projection\0";
+    pub const GET_STYLE: B = b"get_style\0";
+    pub const SET_WALK_GRAPH: B = b"set_walk_graph\0";
+    pub const GET_MARKERS: B = b"get_markers\0";
+    pub const WHEN_STOOD: B = b"when_stood\0";
+    pub const GET_STATE: B = b"get_state\0";
+    pub const SET_STATE: B = b"set_state\0";
 
     // variables
     pub const LEVEL_INFO: B = b"LEVEL_INFO\0";
 }
-use my_items::Face;
+use my_items::{marker::Marker, theme::Theme, walk::WalkGraph, Face, ProjectionMode, V3};
 #[allow(unused_imports)]
 // 对这个列表设置的别名为`names`, 不然太长太难用了
 use variables_functions_names as names;
@@ -94,6 +104,55 @@ impl LevelInfo {
     };
 }
 
+/// Where the camera looks at when the level starts, and when the view is reset
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelView {
+    /// the scale of the projection, the whole screen is 2 high
+    pub scale: f32,
+    /// the point in the middle of the screen
+    pub center: V3,
+}
+
+/// How the level may be looked at
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelProjection {
+    /// the projections the player can switch between, the first one is used at the start
+    pub modes: Vec<ProjectionMode>,
+    /// whether the camera turns around the y axis
+    pub yaw: bool,
+    /// whether the camera can look from above or below
+    pub pitch: bool,
+}
+impl Default for LevelProjection {
+    fn default() -> Self {
+        Self {
+            modes: vec![ProjectionMode::Isometric],
+            yaw: true,
+            pitch: false,
+        }
+    }
+}
+
+/// How the faces of the level are drawn, the player's settings are used where it allows them
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelStyle {
+    /// whether the lit faces are shaded by the light
+    pub lighting: bool,
+    /// whether the edges of the faces are outlined
+    pub outlines: bool,
+    /// the background and the colors of the buttons, None to use the theme of the group
+    pub theme: Option<Theme>,
+}
+impl Default for LevelStyle {
+    fn default() -> Self {
+        Self {
+            lighting: true,
+            outlines: false,
+            theme: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct MyInterface {
     /// This function is loaded and called after we have loaded the lib.
@@ -134,6 +193,119 @@ pub struct MyInterface {
 
     pub get_faces: fn(Pointered) -> Vec<Face>,
     pub when_angled: fn(Pointered, f32) -> bool,
+    /// This function is called when a face is clicked, with the id of the face in the list
+    /// returned by get_faces() and the clicked point of the face in world space.
+    ///
+    /// Return true if the faces are changed, so they will be fetched again.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn when_clicked(p: Pointered, id: usize, pos: V3) -> bool {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub when_clicked: fn(Pointered, usize, V3) -> bool,
+    /// This function is called once after new(), to tell where the camera starts.
+    ///
+    /// Return None to fit all faces into the screen, which is also done without this function.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn get_view(p: Pointered) -> Option<LevelView> {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub get_view: fn(Pointered) -> Option<LevelView>,
+    /// This function is called once after new(), to tell the angles where the level changes.
+    ///
+    /// When the camera turns past some of them in a frame, when_angled() is called just past
+    /// every one of them in order, before it is called with the angle of the frame.
+    /// They are the targets the camera snaps to as well, every 45 degrees without this function.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn get_thresholds(p: Pointered) -> Vec<f32> {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub get_thresholds: fn(Pointered) -> Vec<f32>,
+    /// This function is called once after new(), to tell the projections and the turning allowed.
+    ///
+    /// Without this function, the level is isometric and only turns around the y axis.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn get_projection(p: Pointered) -> LevelProjection {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub get_projection: fn(Pointered) -> LevelProjection,
+    /// This function is called once after new(), to tell whether the faces are lit and outlined,
+    /// and the theme replacing the one of the group.
+    ///
+    /// Without this function, the lit faces are shaded, there are no outlines
+    /// and the theme of the group is used.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn get_style(p: Pointered) -> LevelStyle {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub get_style: fn(Pointered) -> LevelStyle,
+    /// This function is called when the walkable faces are joined again, after the faces changed
+    /// or the camera turned past a snap target or stopped turning.
+    ///
+    /// The level can find the way between two faces by `graph.path(from, to)`.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn set_walk_graph(p: Pointered, graph: WalkGraph) {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub set_walk_graph: fn(Pointered, WalkGraph),
+    /// This function is called after new() and whenever the faces are fetched again,
+    /// to tell where the avatar starts and the goal it is to reach.
+    ///
+    /// The level is finished when the avatar reaches the goal, so it needs no check of its own.
+    /// A marker which is not on a walkable face of the faces is left out.
+    /// Without this function, the avatar starts on the first walkable face and there is no goal.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn get_markers(p: Pointered) -> Vec<Marker> {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub get_markers: fn(Pointered) -> Vec<Marker>,
+    /// This function is called when the avatar steps onto a face, with the id of the face
+    /// in the list returned by get_faces() and where it stands in world space.
+    ///
+    /// Return true if the faces are changed, as by the plates of `my_items::puzzle`.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn when_stood(p: Pointered, id: usize, pos: V3) -> bool {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub when_stood: fn(Pointered, usize, V3) -> bool,
+    /// This function is called after every move of the player, to keep the state of the level
+    /// for undoing the move later, in any form the level likes.
+    ///
+    /// A level changing only by the angle of the camera needs neither this nor set_state(),
+    /// as when_angled() is called again when a move is undone.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn get_state(p: Pointered) -> Vec<u8> {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub get_state: fn(Pointered) -> Vec<u8>,
+    /// This function is called when a move is undone or redone, or the level is restarted,
+    /// with a state returned by get_state() before.
+    ///
+    /// Return true if the faces are changed, so they will be fetched again.
+    /// ```Rust
+    /// #[no_mangle]
+    /// pub fn set_state(p: Pointered, state: Vec<u8>) -> bool {
+    ///     // codes here ...
+    /// }
+    /// ```
+    pub set_state: fn(Pointered, Vec<u8>) -> bool,
 
     pub is_ok: fn() -> bool,
 
@@ -202,6 +374,36 @@ impl MyInterface {
         if let Ok(when_angled) = lib.get(names::WHEN_ANGLED) {
             mif_builder.with_when_angled(*when_angled);
         }
+        if let Ok(when_clicked) = lib.get(names::WHEN_CLICKED) {
+            mif_builder.with_when_clicked(*when_clicked);
+        }
+        if let Ok(get_view) = lib.get(names::GET_VIEW) {
+            mif_builder.with_get_view(*get_view);
+        }
+        if let Ok(get_thresholds) = lib.get(names::GET_THRESHOLDS) {
+            mif_builder.with_get_thresholds(*get_thresholds);
+        }
+        if let Ok(get_projection) = lib.get(names::GET_PROJECTION) {
+            mif_builder.with_get_projection(*get_projection);
+        }
+        if let Ok(get_style) = lib.get(names::GET_STYLE) {
+            mif_builder.with_get_style(*get_style);
+        }
+        if let Ok(set_walk_graph) = lib.get(names::SET_WALK_GRAPH) {
+            mif_builder.with_set_walk_graph(*set_walk_graph);
+        }
+        if let Ok(get_markers) = lib.get(names::GET_MARKERS) {
+            mif_builder.with_get_markers(*get_markers);
+        }
+        if let Ok(when_stood) = lib.get(names::WHEN_STOOD) {
+            mif_builder.with_when_stood(*when_stood);
+        }
+        if let Ok(get_state) = lib.get(names::GET_STATE) {
+            mif_builder.with_get_state(*get_state);
+        }
+        if let Ok(set_state) = lib.get(names::SET_STATE) {
+            mif_builder.with_set_state(*set_state);
+        }
         Ok(mif_builder.build(Some(lib)))
     }
     pub fn close(self) {
@@ -218,7 +420,7 @@ impl MyInterface {
 #[allow(dead_code)]
 pub mod my_interface {
 
-    use my_items::Face;
+    use my_items::{marker::Marker, walk::WalkGraph, Face, V3};
 
     use crate::*;
 
@@ -235,6 +437,16 @@ pub mod my_interface {
         pub f_destory: Option<fn(Pointered) -> ()>,
         pub f_when_angled: Option<fn(Pointered, f32) -> bool>,
         pub f_get_faces: Option<fn(Pointered) -> Vec<Face>>,
+        pub f_when_clicked: Option<fn(Pointered, usize, V3) -> bool>,
+        pub f_get_view: Option<fn(Pointered) -> Option<LevelView>>,
+        pub f_get_thresholds: Option<fn(Pointered) -> Vec<f32>>,
+        pub f_get_projection: Option<fn(Pointered) -> LevelProjection>,
+        pub f_get_style: Option<fn(Pointered) -> LevelStyle>,
+        pub f_set_walk_graph: Option<fn(Pointered, WalkGraph)>,
+        pub f_get_markers: Option<fn(Pointered) -> Vec<Marker>>,
+        pub f_when_stood: Option<fn(Pointered, usize, V3) -> bool>,
+        pub f_get_state: Option<fn(Pointered) -> Vec<u8>>,
+        pub f_set_state: Option<fn(Pointered, Vec<u8>) -> bool>,
         pub level_info: Option<my_interface::LevelInfo>,
     }
     impl MyInterfaceBuilder {
@@ -243,6 +455,16 @@ pub mod my_interface {
             f_destory: None,
             f_get_faces: None,
             f_when_angled: None,
+            f_when_clicked: None,
+            f_get_view: None,
+            f_get_thresholds: None,
+            f_get_projection: None,
+            f_get_style: None,
+            f_set_walk_graph: None,
+            f_get_markers: None,
+            f_when_stood: None,
+            f_get_state: None,
+            f_set_state: None,
             level_info: None,
             is_ok: None,
         };
@@ -251,6 +473,16 @@ pub mod my_interface {
         pub const DESTORY: fn(Pointered) -> () = |_| ();
         pub const GET_FACES: fn(Pointered) -> Vec<Face> = |_| (vec![]);
         pub const WHEN_ANGLED: fn(Pointered, f32) -> bool = |_, _| (false);
+        pub const WHEN_CLICKED: fn(Pointered, usize, V3) -> bool = |_, _, _| false;
+        pub const GET_VIEW: fn(Pointered) -> Option<LevelView> = |_| None;
+        pub const GET_THRESHOLDS: fn(Pointered) -> Vec<f32> = |_| vec![];
+        pub const GET_PROJECTION: fn(Pointered) -> LevelProjection = |_| Default::default();
+        pub const GET_STYLE: fn(Pointered) -> LevelStyle = |_| Default::default();
+        pub const SET_WALK_GRAPH: fn(Pointered, WalkGraph) = |_, _| ();
+        pub const GET_MARKERS: fn(Pointered) -> Vec<Marker> = |_| vec![];
+        pub const WHEN_STOOD: fn(Pointered, usize, V3) -> bool = |_, _, _| false;
+        pub const GET_STATE: fn(Pointered) -> Vec<u8> = |_| vec![];
+        pub const SET_STATE: fn(Pointered, Vec<u8>) -> bool = |_, _| false;
 
         pub fn build(self, lib: Option<libloading::Library>) -> MyInterface {
             MyInterface {
@@ -259,6 +491,16 @@ pub mod my_interface {
                 destory: self.f_destory.unwrap_or(Self::DESTORY),
                 get_faces: self.f_get_faces.unwrap_or(Self::GET_FACES),
                 when_angled: self.f_when_angled.unwrap_or(Self::WHEN_ANGLED),
+                when_clicked: self.f_when_clicked.unwrap_or(Self::WHEN_CLICKED),
+                get_view: self.f_get_view.unwrap_or(Self::GET_VIEW),
+                get_thresholds: self.f_get_thresholds.unwrap_or(Self::GET_THRESHOLDS),
+                get_projection: self.f_get_projection.unwrap_or(Self::GET_PROJECTION),
+                get_style: self.f_get_style.unwrap_or(Self::GET_STYLE),
+                set_walk_graph: self.f_set_walk_graph.unwrap_or(Self::SET_WALK_GRAPH),
+                get_markers: self.f_get_markers.unwrap_or(Self::GET_MARKERS),
+                when_stood: self.f_when_stood.unwrap_or(Self::WHEN_STOOD),
+                get_state: self.f_get_state.unwrap_or(Self::GET_STATE),
+                set_state: self.f_set_state.unwrap_or(Self::SET_STATE),
                 level_info: self.level_info.unwrap_or(LevelInfo::NONE),
                 lib,
             }
@@ -276,6 +518,16 @@ pub mod my_interface {
                 f_destory: Some(destory),
                 f_get_faces: None,
                 f_when_angled: None,
+                f_when_clicked: None,
+                f_get_view: None,
+                f_get_thresholds: None,
+                f_get_projection: None,
+                f_get_style: None,
+                f_set_walk_graph: None,
+                f_get_markers: None,
+                f_when_stood: None,
+                f_get_state: None,
+                f_set_state: None,
 
                 is_ok: Some(is_ok),
             }
@@ -300,5 +552,60 @@ pub mod my_interface {
             self.f_get_faces = Some(get_faces);
             self
         }
+        pub fn with_when_clicked(
+            &mut self,
+            when_clicked: fn(Pointered, usize, V3) -> bool,
+        ) -> &mut Self {
+            self.f_when_clicked = Some(when_clicked);
+            self
+        }
+        pub fn with_get_view(&mut self, get_view: fn(Pointered) -> Option<LevelView>) -> &mut Self {
+            self.f_get_view = Some(get_view);
+            self
+        }
+        pub fn with_get_thresholds(
+            &mut self,
+            get_thresholds: fn(Pointered) -> Vec<f32>,
+        ) -> &mut Self {
+            self.f_get_thresholds = Some(get_thresholds);
+            self
+        }
+        pub fn with_get_projection(
+            &mut self,
+            get_projection: fn(Pointered) -> LevelProjection,
+        ) -> &mut Self {
+            self.f_get_projection = Some(get_projection);
+            self
+        }
+        pub fn with_get_style(&mut self, get_style: fn(Pointered) -> LevelStyle) -> &mut Self {
+            self.f_get_style = Some(get_style);
+            self
+        }
+        pub fn with_set_walk_graph(
+            &mut self,
+            set_walk_graph: fn(Pointered, WalkGraph),
+        ) -> &mut Self {
+            self.f_set_walk_graph = Some(set_walk_graph);
+            self
+        }
+        pub fn with_get_markers(&mut self, get_markers: fn(Pointered) -> Vec<Marker>) -> &mut Self {
+            self.f_get_markers = Some(get_markers);
+            self
+        }
+        pub fn with_when_stood(
+            &mut self,
+            when_stood: fn(Pointered, usize, V3) -> bool,
+        ) -> &mut Self {
+            self.f_when_stood = Some(when_stood);
+            self
+        }
+        pub fn with_get_state(&mut self, get_state: fn(Pointered) -> Vec<u8>) -> &mut Self {
+            self.f_get_state = Some(get_state);
+            self
+        }
+        pub fn with_set_state(&mut self, set_state: fn(Pointered, Vec<u8>) -> bool) -> &mut Self {
+            self.f_set_state = Some(set_state);
+            self
+        }
     }
 }
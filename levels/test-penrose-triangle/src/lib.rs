@@ -1,4 +1,4 @@
-use my_items::{self, Face, Pillar, V3};
+use my_items::{self, marker::Marker, Face, Pillar, V3};
 
 const S2: f32 = 1.414213562373095;
 
@@ -40,36 +40,70 @@ pub struct PenroseTriangle {
 }
 impl PenroseTriangle {
     pub fn new() -> Self {
+        // the same name in two states is the same pillar, which moves between them,
+        // the avatar walks on the tops of them
         let content = Content {
-            base: Pillar::new_upright(V3::from(-6.0, -2.0, -1.0), V3::from(12.0, 2.0, 2.0)),
-            left: Pillar::new_upright(V3::from(-6.0, -2.0, -11.0), V3::from(2.0, 2.0, 12.0)),
-            right: Pillar::new_upright(V3::from(4.0, 0.0, -1.0), V3::from(2.0, 10.0, 2.0)),
-            top: Pillar::new_upright(V3::from(4.0, 8.0, -1.0), V3::from(2.0, 2.0, 12.0)),
-            front: Pillar::new_upright(V3::from(4.0, 8.0, 9.0), V3::from(12.0, 2.0, 2.0)),
+            base: Pillar::new_upright(V3::from(-6.0, -2.0, -1.0), V3::from(12.0, 2.0, 2.0))
+                .with_tag("base")
+                .with_walkable_top(),
+            left: Pillar::new_upright(V3::from(-6.0, -2.0, -11.0), V3::from(2.0, 2.0, 12.0))
+                .with_tag("left")
+                .with_walkable_top(),
+            right: Pillar::new_upright(V3::from(4.0, 0.0, -1.0), V3::from(2.0, 10.0, 2.0))
+                .with_tag("right")
+                .with_walkable_top(),
+            top: Pillar::new_upright(V3::from(4.0, 8.0, -1.0), V3::from(2.0, 2.0, 12.0))
+                .with_tag("top")
+                .with_walkable_top(),
+            front: Pillar::new_upright(V3::from(4.0, 8.0, 9.0), V3::from(12.0, 2.0, 2.0))
+                .with_tag("front")
+                .with_walkable_top(),
             musk: Pillar::new_upright(V3::from(-6.0, -2.0, -11.0), V3::from(2.0, 2.0, 4.0))
                 .with_w(0.5)
-                .with_skipped_filter(false, true, false, true, true, true),
+                .with_skipped_filter(false, true, false, true, true, true)
+                .with_tag("musk")
+                .with_walkable_top(),
             musk2: Pillar::new_upright(V3::from(4.0, 0.0, -1.0), V3::from(2.0, 2.0, 2.0))
                 .with_w(0.5)
-                .with_skipped_filter(false, true, true, true, false, true),
+                .with_skipped_filter(false, true, true, true, false, true)
+                .with_tag("musk2")
+                .with_walkable_top(),
 
             up: (
-                Pillar::new_upright(V3::from(4.0, 8.0, 9.0), V3::from(8.0, 2.0, 2.0)),
-                Pillar::new_upright(V3::from(10.0, 8.0, 9.0), V3::from(2.0, 6.0, 2.0)),
-                Pillar::new_upright(V3::from(10.0, 12.0, 9.0), V3::from(12.0, 2.0, 2.0)),
+                Pillar::new_upright(V3::from(4.0, 8.0, 9.0), V3::from(8.0, 2.0, 2.0))
+                    .with_tag("front")
+                    .with_walkable_top(),
+                Pillar::new_upright(V3::from(10.0, 8.0, 9.0), V3::from(2.0, 6.0, 2.0))
+                    .with_tag("up")
+                    .with_walkable_top(),
+                Pillar::new_upright(V3::from(10.0, 12.0, 9.0), V3::from(12.0, 2.0, 2.0))
+                    .with_tag("up2")
+                    .with_walkable_top(),
             ),
-            back: Pillar::new_upright(V3::from(4.0, 4.0, -1.0), V3::from(-4.0, 2.0, 2.0)),
+            back: Pillar::new_upright(V3::from(4.0, 4.0, -1.0), V3::from(-4.0, 2.0, 2.0))
+                .with_tag("back")
+                .with_walkable_top(),
             shrink: (
-                Pillar::new_upright(V3::from(4.0, -2.0, -1.0), V3::from(2.0, 12.0, 2.0)),
+                Pillar::new_upright(V3::from(4.0, -2.0, -1.0), V3::from(2.0, 12.0, 2.0))
+                    .with_tag("right")
+                    .with_walkable_top(),
                 Pillar::new_upright(
                     V3::from(4.0, 5.0 * S2 - 2.0, -11.0),
                     V3::from(2.0, 2.0, 12.0),
-                ),
+                )
+                .with_tag("beam")
+                .with_walkable_top(),
             ),
-            shrink2: Pillar::new_upright(V3::from(4.0, 8.0, -1.0), V3::from(2.0, 2.0, -5.8)),
+            shrink2: Pillar::new_upright(V3::from(4.0, 8.0, -1.0), V3::from(2.0, 2.0, -5.8))
+                .with_tag("beam")
+                .with_walkable_top(),
             shrink3: (
-                Pillar::new_upright(V3::from(4.0, -2.0, -1.0), V3::from(2.0, 6.0, 2.0)),
-                Pillar::new_upright(V3::from(4.0, 4.0, -1.0), V3::from(2.0, 2.0, 7.7)),
+                Pillar::new_upright(V3::from(4.0, -2.0, -1.0), V3::from(2.0, 6.0, 2.0))
+                    .with_tag("right")
+                    .with_walkable_top(),
+                Pillar::new_upright(V3::from(4.0, 4.0, -1.0), V3::from(2.0, 2.0, 7.7))
+                    .with_tag("beam")
+                    .with_walkable_top(),
             ),
         };
         let mut s = Self {
@@ -84,6 +118,14 @@ impl PenroseTriangle {
     pub fn get(&self) -> &Vec<my_items::Face> {
         &self.faces
     }
+    /// The avatar starts at the end of the base, the goal is at the end of the front,
+    /// which is there only when the triangle is turned to its top
+    pub fn markers(&self) -> Vec<Marker> {
+        vec![
+            Marker::start(V3::from(-5.0, 0.0, 0.0)),
+            Marker::goal(V3::from(15.0, 10.0, 10.0)),
+        ]
+    }
     fn gen_vec(&mut self) {
         self.faces.clear();
         match self.state {
@@ -181,7 +223,7 @@ impl PenroseTriangle {
 
 impl Pointerable for PenroseTriangle {}
 
-use levels_interface::{self, LevelInfo, Pointerable, Pointered};
+use levels_interface::{self, LevelInfo, LevelView, Pointerable, Pointered};
 
 #[no_mangle]
 pub static mut LEVEL_INFO: LevelInfo = LevelInfo::NONE;
@@ -216,4 +258,18 @@ fn get_faces(p: Pointered) -> Vec<Face> {
     }
 }
 
+fn get_markers(p: Pointered) -> Vec<Marker> {
+    match PenroseTriangle::from_pointer(p) {
+        Some(s) => s.markers(),
+        _ => vec![],
+    }
+}
+
+fn get_view(_p: Pointered) -> Option<LevelView> {
+    Some(LevelView {
+        scale: 0.05,
+        center: V3::from(0.0, 0.0, 0.0),
+    })
+}
+
 include!("required.rs");
@@ -1,6 +1,6 @@
 mod exporting_functions_and_variables {
-    use levels_interface::Pointered;
-    use my_items::Face;
+    use levels_interface::{LevelView, Pointered};
+    use my_items::{marker::Marker, Face};
 
     #[no_mangle]
     static REQUIRED_INCLUDED: bool = true;
@@ -57,6 +57,18 @@ mod exporting_functions_and_variables {
         }
     }
 
+    #[no_mangle]
+    fn get_view(p: Pointered) -> Option<LevelView> {
+        match std::panic::catch_unwind(|| crate::get_view(p)) {
+            Ok(ok) => ok,
+            Err(err) => {
+                println!("{:#?}", err);
+                unsafe { STATE_IS_OK = false };
+                None
+            }
+        }
+    }
+
     #[no_mangle]
     fn get_faces(p: Pointered) -> Vec<Face> {
         match std::panic::catch_unwind(|| crate::get_faces(p)) {
@@ -68,4 +80,16 @@ mod exporting_functions_and_variables {
             }
         }
     }
+
+    #[no_mangle]
+    fn get_markers(p: Pointered) -> Vec<Marker> {
+        match std::panic::catch_unwind(|| crate::get_markers(p)) {
+            Ok(ok) => ok,
+            Err(err) => {
+                println!("{:#?}", err);
+                unsafe { STATE_IS_OK = false };
+                vec![]
+            }
+        }
+    }
 }
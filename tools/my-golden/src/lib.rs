@@ -0,0 +1,229 @@
+//! Golden image tests of the levels.
+//!
+//! Every level found through `levels.json` is loaded, turned round in fixed steps through
+//! `when_angled` and drawn by `my-rasterizer`. The images are compared with the ones in
+//! `assets/golden`, and the differences are written to `assets/golden/failed` for review.
+//!
+//! - `GOLDEN_LEVELS_DIR`: the directory with `levels.json` and the libraries, `../../` by default
+//! - `UPDATE_GOLDEN`: write the images instead of comparing them
+//!
+//! The libraries are built apart from the workspace, so the test is ignored by default.
+//! Build the levels, then run it with
+//!
+//! ```sh
+//! cargo test -p my-golden -- --ignored
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+use levels_interface::MyInterface;
+use my_items::{Color, Projection};
+
+pub struct Golden {
+    /// where the checked in images are
+    pub dir: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// how many angles in a full turn
+    pub steps: usize,
+    /// the same as `GlPaintOptions::scale` of the game view
+    pub scale: f32,
+    /// the largest difference of a channel that is still the same
+    pub tolerance: u8,
+    /// write the images instead of comparing them
+    pub update: bool,
+}
+
+impl Default for Golden {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("../../assets/golden"),
+            width: 320,
+            height: 240,
+            steps: 24,
+            scale: 0.05,
+            tolerance: 2,
+            update: false,
+        }
+    }
+}
+
+impl Golden {
+    /// the background of the game view, the panel color of egui
+    pub const BACKGROUND: Color = Color {
+        r: 27.0 / 255.0,
+        g: 27.0 / 255.0,
+        b: 27.0 / 255.0,
+        a: 1.0,
+    };
+
+    pub fn from_env() -> Self {
+        Self {
+            update: std::env::var_os("UPDATE_GOLDEN").is_some(),
+            ..Default::default()
+        }
+    }
+
+    /// the angles of the sweep in degrees, starting from 0
+    pub fn angles(&self) -> Vec<f32> {
+        (0..self.steps)
+            .map(|i| i as f32 * 360.0 / self.steps as f32)
+            .collect()
+    }
+
+    /// Load the level and draw it at every angle of the sweep
+    pub fn render_level(&self, path: &str) -> Result<Vec<(f32, RgbaImage)>, String> {
+        let mif = MyInterface::from_lib_safe(path.to_string())?;
+        let p = (mif.new)();
+        let mut faces = (mif.get_faces)(p);
+        let mut images = vec![];
+        for angle in self.angles() {
+            if (mif.when_angled)(p, angle.to_radians()) {
+                faces = (mif.get_faces)(p);
+            }
+            if !(mif.is_ok)() {
+                (mif.destory)(p);
+                mif.close();
+                return Err(format!("The level failed at {angle} degrees"));
+            }
+            let proj = Projection::new(angle.to_radians(), self.scale, 1.0);
+            let img = my_rasterizer::render(
+                &faces,
+                &[],
+                &proj,
+                self.width,
+                self.height,
+                Self::BACKGROUND,
+            );
+            images.push((angle, img));
+        }
+        (mif.destory)(p);
+        mif.close();
+        Ok(images)
+    }
+
+    /// Compare every state of the level with its golden image, or write them when updating
+    pub fn check_level(&self, name: &str, path: &str) -> Result<(), String> {
+        let failed_dir = self.dir.join("failed");
+        let mut errors = vec![];
+        for (angle, img) in self.render_level(path)? {
+            let file = format!("{name}_{:03}.png", angle.round() as u32);
+            let golden = self.dir.join(&file);
+            if self.update {
+                save(&img, &golden)?;
+                continue;
+            }
+            let Ok(expected) = image::open(&golden) else {
+                errors.push(format!(
+                    "{file} is missing, run with UPDATE_GOLDEN=1 to create it"
+                ));
+                continue;
+            };
+            if let Some(diff) = compare(&expected.to_rgba8(), &img, self.tolerance) {
+                save(&img, &failed_dir.join(&file))?;
+                save(
+                    &diff,
+                    &failed_dir.join(format!("{name}_{:03}.diff.png", angle.round() as u32)),
+                )?;
+                errors.push(format!("{file} is different, see {}", failed_dir.display()));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+fn save(img: &RgbaImage, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    img.save(path).map_err(|err| err.to_string())
+}
+
+/// None if the images are the same within `tolerance`,
+/// or an image with the different pixels in red over the dimmed `actual`
+pub fn compare(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> Option<RgbaImage> {
+    if expected.dimensions() != actual.dimensions() {
+        return Some(actual.clone());
+    }
+    let mut same = true;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (e, a) = (expected.get_pixel(x, y), actual.get_pixel(x, y));
+        if e.0.iter().zip(a.0).any(|(e, a)| e.abs_diff(a) > tolerance) {
+            same = false;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let gray = (a.0[0] as u32 + a.0[1] as u32 + a.0[2] as u32) / 3 / 4;
+            Rgba([gray as u8, gray as u8, gray as u8, 255])
+        }
+    });
+    if same {
+        None
+    } else {
+        Some(diff)
+    }
+}
+
+/// The name and the library path of every level found in `dir/levels.json`,
+/// the names are `group_level` and fit in file names
+pub fn level_libraries(dir: &str) -> Vec<(String, String)> {
+    let dir = if dir.ends_with('/') {
+        dir.to_string()
+    } else {
+        format!("{dir}/")
+    };
+    let game = my_levels_finder::get_levels(my_levels_finder::Link::new(&dir), "levels.json");
+    let mut levels = vec![];
+    for group in game.groups.values() {
+        for level in group.levels.values() {
+            let name = format!("{}_{}", group.name, level.name)
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '_' {
+                        c.to_ascii_lowercase()
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            levels.push((name, level.link.path().clone()));
+        }
+    }
+    levels.sort();
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_with_tolerance() {
+        let a = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut b = RgbaImage::from_pixel(4, 4, Rgba([102, 99, 100, 255]));
+        assert!(compare(&a, &b, 2).is_none());
+        b.put_pixel(1, 2, Rgba([110, 100, 100, 255]));
+        let diff = compare(&a, &b, 2).unwrap();
+        assert_eq!(diff.get_pixel(1, 2).0, [255, 0, 0, 255]);
+        assert_eq!(diff.get_pixel(0, 0).0, [25, 25, 25, 255]);
+        assert!(compare(&a, &RgbaImage::new(4, 3), 255).is_some());
+    }
+
+    #[test]
+    #[ignore = "needs the level libraries, see the docs of the crate"]
+    fn levels_match_golden_images() {
+        let dir = std::env::var("GOLDEN_LEVELS_DIR").unwrap_or("../../".to_string());
+        let levels = level_libraries(&dir);
+        assert!(!levels.is_empty(), "No level libraries found in {dir}");
+        let golden = Golden::from_env();
+        let errors: Vec<_> = levels
+            .iter()
+            .filter_map(|(name, path)| golden.check_level(name, path).err())
+            .collect();
+        assert!(errors.is_empty(), "{}", errors.join("\n"));
+    }
+}
@@ -1,8 +1,23 @@
-use std::{fmt::Debug, sync::Arc};
+pub mod marker;
+pub mod morph;
+pub mod order;
+pub mod pick;
+pub mod projection;
+pub mod puzzle;
+pub mod scene;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod shading;
+pub mod theme;
+pub mod walk;
+
+pub use projection::{Projection, ProjectionMode};
 
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct V2(f32, f32);
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct V3 {
     pub x: f32,
     pub y: f32,
@@ -14,6 +29,7 @@ impl V3 {
     }
 }
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct V4 {
     pub x: f32,
     pub y: f32,
@@ -26,13 +42,68 @@ impl V4 {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Where the `pos` and `dir` of a musk are measured
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MuskSpace {
+    /// projected with the item, so the musk turns together with the scene
+    #[default]
+    World,
+    /// fixed on the screen, only `x` and `y` are used.
+    /// y ranges from -1 to 1, and x is scaled by the aspect ratio
+    Screen,
+}
+
+/// A musk cuts off everything on the left side of the line through `pos` along `dir`,
+/// as it is seen on the screen.
+///
+/// An item may have several musks, it is only drawn where all of them let it through.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Musk {
     pub pos: V3,
     pub dir: V3,
+    pub space: MuskSpace,
+}
+impl Musk {
+    /// the max number of musks of an item that the shaders accept, the rest are ignored
+    pub const MAX: usize = 8;
+
+    pub fn new(pos: V3, dir: V3) -> Self {
+        Self {
+            pos,
+            dir,
+            space: MuskSpace::World,
+        }
+    }
+    pub fn new_on_screen(x: f32, y: f32, dx: f32, dy: f32) -> Self {
+        Self {
+            pos: V3::from(x, y, 0.0),
+            dir: V3::from(dx, dy, 0.0),
+            space: MuskSpace::Screen,
+        }
+    }
+    /// Musks keeping the inside of a convex polygon, one for each edge.
+    ///
+    /// The points should go clockwise as they are seen on the screen,
+    /// for a polygon in world space it is clockwise seen from the side we look at.
+    pub fn convex_polygon(points: &[V3], space: MuskSpace) -> Vec<Self> {
+        let n = points.len();
+        (0..n)
+            .map(|i| {
+                let (p, q) = (&points[i], &points[(i + 1) % n]);
+                Self {
+                    pos: p.clone(),
+                    dir: V3::from(q.x - p.x, q.y - p.y, q.z - p.z),
+                    space,
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -62,69 +133,135 @@ impl Color {
             c4.r, c4.g, c4.b, c4.a, // vec4
         ]
     }
+    pub fn from(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+    /// hue in degrees, saturation and value range from 0 to 1
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let c = value * saturation;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = value - c;
+        Self::from(r + m, g + m, b + m, alpha)
+    }
+    /// linear mix, returns self when t is 0 and other when t is 1
+    pub fn mix(&self, other: &Self, t: f32) -> Self {
+        Self::from(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
 }
 
-pub enum Colored {
-    Default,
-    Pure(Color),
-    Vertex(Vec<Color>),
-    Fun(Arc<dyn Fn(usize) -> Color>),
+/// A color function which can be compared, serialized and sent to another thread,
+/// it gives the color of the `id`th vertex.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorFun {
+    /// `base + step * id`
+    Linear { base: Color, step: Color },
+    /// from the first vertex to the last one of `count` vertices
+    Gradient {
+        from: Color,
+        to: Color,
+        count: usize,
+    },
+    /// look up the palette with id, wraps around
+    Palette(Vec<Color>),
+    /// hue goes `step` degrees further with each vertex
+    Hsv {
+        hue: f32,
+        step: f32,
+        saturation: f32,
+        value: f32,
+        alpha: f32,
+    },
+    /// `base` when the view angle is `facing` (in radians), `tint` when it is turned away by 180 degrees
+    Angled {
+        base: Color,
+        tint: Color,
+        facing: f32,
+    },
 }
 
-impl Default for Colored {
-    fn default() -> Self {
-        Colored::Default
-    }
-}
-impl Debug for Colored {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl ColorFun {
+    pub fn get(&self, id: usize, angle: f32) -> Color {
         match self {
-            Self::Default => write!(f, "Default"),
-            Self::Pure(arg0) => f.debug_tuple("Pure").field(arg0).finish(),
-            Self::Vertex(arg0) => f.debug_tuple("Vertex").field(arg0).finish(),
-            Self::Fun(_) => write!(f, "ColorFun"),
+            ColorFun::Linear { base, step } => Color::from(
+                base.r + step.r * id as f32,
+                base.g + step.g * id as f32,
+                base.b + step.b * id as f32,
+                base.a + step.a * id as f32,
+            ),
+            ColorFun::Gradient { from, to, count } => {
+                let t = if *count > 1 {
+                    id.min(count - 1) as f32 / (count - 1) as f32
+                } else {
+                    0.0
+                };
+                from.mix(to, t)
+            }
+            ColorFun::Palette(p) if !p.is_empty() => p[id % p.len()].clone(),
+            ColorFun::Palette(_) => Color::default(),
+            ColorFun::Hsv {
+                hue,
+                step,
+                saturation,
+                value,
+                alpha,
+            } => Color::from_hsv(hue + step * id as f32, *saturation, *value, *alpha),
+            ColorFun::Angled { base, tint, facing } => {
+                base.mix(tint, (1.0 - (angle - facing).cos()) / 2.0)
+            }
         }
     }
 }
-impl Clone for Colored {
-    fn clone(&self) -> Self {
-        match self {
-            Self::Default => Self::Default,
-            Self::Pure(c) => Self::Pure(c.clone()),
-            Self::Vertex(v_c) => Self::Vertex(v_c.clone()),
-            Self::Fun(b_f) => Self::Fun(Arc::clone(b_f)),
-        }
-    }
-}
-impl PartialEq for Colored {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Pure(l0), Self::Pure(r0)) => l0 == r0,
-            (Self::Vertex(l0), Self::Vertex(r0)) => l0 == r0,
-            (Self::Fun(l0), Self::Fun(r0)) => Arc::ptr_eq(l0, r0),
-            _ => false,
-        }
-    }
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Colored {
+    #[default]
+    Default,
+    Pure(Color),
+    Vertex(Vec<Color>),
+    Fun(ColorFun),
 }
-// I don't how to fix this
-unsafe impl Send for Colored {}
 
 impl Colored {
     pub fn get(&self, id: usize) -> Color {
+        self.get_at(id, 0.0)
+    }
+    /// the same as `get`, but angle dependent colors are evaluated at `angle`
+    pub fn get_at(&self, id: usize, angle: f32) -> Color {
         match self {
             Colored::Pure(p) => p.clone(),
             Colored::Vertex(v) => v.get(id).unwrap_or(&Color::default()).clone(),
-            Colored::Fun(f) => f(id),
+            Colored::Fun(f) => f.get(id, angle),
             _ => Color::default(),
         }
     }
+    /// whether the colors change with the view angle
+    pub fn is_angled(&self) -> bool {
+        matches!(self, Colored::Fun(ColorFun::Angled { .. }))
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line {
     pub pos1: V3,
     pub pos2: V3,
-    pub msk: Option<Musk>,
+    pub msks: Vec<Musk>,
 
     // pub color: Rc<Colored>,
     pub color: Colored,
@@ -143,20 +280,21 @@ impl Line {
                 y: y1,
                 z: z1,
             },
-            msk: None,
+            msks: vec![],
             color: Colored::Default,
         }
     }
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Face {
     pub pos11: V3,
     pub pos12: V3,
     pub pos21: V3,
     pub pos22: V3,
 
-    pub musk: Option<Musk>,
+    pub musks: Vec<Musk>,
 
     pub color: Colored,
     /// range from -1 to 1, default to be 0
@@ -164,7 +302,18 @@ pub struct Face {
     pub index: f32,
     /// to skip this face when drawing
     pub skipped: bool,
+    /// the name of the face, faces with the same tag are the same face in two states of a level,
+    /// see `morph`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tag: Option<String>,
+    /// to shade this face by the light, see `shading`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lit: bool,
+    /// to let the avatar stand on this face, see `walk`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub walkable: bool,
 
+    #[cfg_attr(feature = "serde", serde(skip))]
     pos_slice: Option<[f32; 12]>,
 }
 
@@ -191,15 +340,22 @@ impl Face {
                 y: pos[3].1,
                 z: pos[3].2,
             },
-            musk: None,
+            musks: vec![],
             color: Colored::Default,
             pos_slice: None,
             index: 0.0,
             skipped: false,
+            tag: None,
+            lit: false,
+            walkable: false,
         }
     }
     pub fn with_musk(mut self, musk: Musk) -> Self {
-        self.musk = Some(musk);
+        self.musks.push(musk);
+        self
+    }
+    pub fn with_musks(mut self, mut musks: Vec<Musk>) -> Self {
+        self.musks.append(&mut musks);
         self
     }
     pub fn with_color(mut self, color: Colored) -> Self {
@@ -213,6 +369,36 @@ impl Face {
     pub fn set_w(&mut self, w: f32) {
         self.index = w;
     }
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+    pub fn with_lit(mut self, lit: bool) -> Self {
+        self.lit = lit;
+        self
+    }
+    pub fn with_walkable(mut self, walkable: bool) -> Self {
+        self.walkable = walkable;
+        self
+    }
+    /// The unit normal of the plane of the face, zero if the face is degenerate
+    pub fn normal(&self) -> V3 {
+        let (a, b, c) = (&self.pos11, &self.pos12, &self.pos21);
+        let (u, v) = (
+            V3::from(b.x - a.x, b.y - a.y, b.z - a.z),
+            V3::from(c.x - a.x, c.y - a.y, c.z - a.z),
+        );
+        let n = V3::from(
+            u.y * v.z - u.z * v.y,
+            u.z * v.x - u.x * v.z,
+            u.x * v.y - u.y * v.x,
+        );
+        let len = (n.x * n.x + n.y * n.y + n.z * n.z).sqrt();
+        if len < f32::EPSILON {
+            return V3::default();
+        }
+        V3::from(n.x / len, n.y / len, n.z / len)
+    }
     pub fn new(pos11: V3, pos12: V3, pos21: V3, pos22: V3) -> Self {
         Face {
             pos11,
@@ -333,6 +519,7 @@ impl Face {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pillar(Vec<Face>);
 impl Pillar {
     pub fn new_upright(pos: V3, size: V3) -> Self {
@@ -378,6 +565,15 @@ impl Pillar {
         res.push(Face::new_on_z(pos.z, V2(pos.x, pos.y), V2(size.x, size.y)).with_color(color_333));
         Self(res)
     }
+    /// A pillar of one color, shaded by the light instead of the three greys
+    pub fn new_lit(pos: V3, size: V3, color: Color) -> Self {
+        let mut s = Self::new_upright(pos, size);
+        for f in s.0.iter_mut() {
+            f.color = Colored::Pure(color.clone());
+            f.lit = true;
+        }
+        s
+    }
     pub fn into_vec(self) -> Vec<Face> {
         self.0
     }
@@ -387,6 +583,19 @@ impl Pillar {
         }
         self
     }
+    /// Tag the faces as `tag/right`, `tag/left`, `tag/up`, `tag/down`, `tag/front` and `tag/back`
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        const SIDES: [&str; 6] = ["right", "left", "up", "down", "front", "back"];
+        for (f, side) in self.0.iter_mut().zip(SIDES) {
+            f.tag = Some(format!("{tag}/{side}"));
+        }
+        self
+    }
+    /// Let the avatar walk on the top of the pillar
+    pub fn with_walkable_top(mut self) -> Self {
+        self.0[2].walkable = true;
+        self
+    }
 
     pub fn set_skipped_filter_all(&mut self, skipped: bool) {
         self.set_skipped_filter(skipped, skipped, skipped, skipped, skipped, skipped);
@@ -0,0 +1,131 @@
+//! The start and the goal of a level, declared by the level together with its faces.
+//!
+//! A marker is a point on a walkable face, see `walk`. The avatar appears on the start,
+//! and the level is finished when the avatar reaches the goal,
+//! so a level needs no code of its own to tell when it is won.
+
+use crate::{walk, Color, Colored, Face, V3};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarkerKind {
+    Start,
+    Goal,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+    pub kind: MarkerKind,
+    /// a point on a walkable face in world space
+    pub pos: V3,
+}
+
+impl Marker {
+    /// how far the point may be from the face it is on
+    const TOLERANCE: f32 = 1e-3;
+
+    pub fn start(pos: V3) -> Self {
+        Self {
+            kind: MarkerKind::Start,
+            pos,
+        }
+    }
+    pub fn goal(pos: V3) -> Self {
+        Self {
+            kind: MarkerKind::Goal,
+            pos,
+        }
+    }
+
+    /// The walkable face the marker is on, the one with the greatest `index` if there are several
+    pub fn find_face(&self, faces: &[Face]) -> Option<usize> {
+        faces
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.walkable && !f.skipped)
+            .filter(|(_, f)| {
+                let (s, t) = walk::param(f, &self.pos);
+                let inside = |v: f32| (-Self::TOLERANCE..=1.0 + Self::TOLERANCE).contains(&v);
+                let p = walk::point(f, (s, t));
+                let d =
+                    (p.x - self.pos.x).abs() + (p.y - self.pos.y).abs() + (p.z - self.pos.z).abs();
+                inside(s) && inside(t) && d < Self::TOLERANCE
+            })
+            .max_by(|a, b| a.1.index.total_cmp(&b.1.index))
+            .map(|(id, _)| id)
+    }
+
+    /// A square `size` wide around the marker, lying on the face `on` just above it
+    pub fn face(&self, on: &Face, size: f32) -> Face {
+        let unit = |a: &V3, b: &V3| {
+            let d = V3::from(b.x - a.x, b.y - a.y, b.z - a.z);
+            let len = (d.x * d.x + d.y * d.y + d.z * d.z).sqrt().max(f32::EPSILON);
+            V3::from(d.x / len, d.y / len, d.z / len)
+        };
+        let u = unit(&on.pos11, &on.pos12);
+        let v = unit(&on.pos11, &on.pos21);
+        // the side of the face looking up, so it is not hidden in the face
+        let mut n = on.normal();
+        if n.y < 0.0 {
+            n = V3::from(-n.x, -n.y, -n.z);
+        }
+        let (h, lift) = (size / 2.0, size * 0.02);
+        let corner = |a: f32, b: f32| {
+            V3::from(
+                self.pos.x + (u.x * a + v.x * b) * h + n.x * lift,
+                self.pos.y + (u.y * a + v.y * b) * h + n.y * lift,
+                self.pos.z + (u.z * a + v.z * b) * h + n.z * lift,
+            )
+        };
+        let (color, tag) = match self.kind {
+            MarkerKind::Start => (Color::from(0.3, 0.8, 0.4, 1.0), "marker/start"),
+            MarkerKind::Goal => (Color::from(1.0, 0.8, 0.2, 1.0), "marker/goal"),
+        };
+        Face::new(
+            corner(-1.0, -1.0),
+            corner(1.0, -1.0),
+            corner(-1.0, 1.0),
+            corner(1.0, 1.0),
+        )
+        .with_color(Colored::Pure(color))
+        .with_w(on.index)
+        .with_tag(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pillar;
+
+    #[test]
+    fn found_on_walkable_faces() {
+        let mut faces = Pillar::new_upright(V3::from(0.0, 0.0, 0.0), V3::from(2.0, 1.0, 2.0))
+            .with_walkable_top()
+            .into_vec();
+        faces.append(
+            &mut Pillar::new_upright(V3::from(0.0, 0.0, 0.0), V3::from(2.0, 1.0, 2.0))
+                .with_walkable_top()
+                .with_w(0.5)
+                .into_vec(),
+        );
+        let goal = Marker::goal(V3::from(0.5, 1.0, 2.0));
+        // the top with the greater index
+        assert_eq!(goal.find_face(&faces), Some(8));
+        faces[8].skipped = true;
+        assert_eq!(goal.find_face(&faces), Some(2));
+        assert_eq!(
+            Marker::start(V3::from(0.5, 1.1, 1.0)).find_face(&faces),
+            None
+        );
+        assert_eq!(
+            Marker::start(V3::from(3.0, 1.0, 1.0)).find_face(&faces),
+            None
+        );
+
+        let square = goal.face(&faces[2], 0.5);
+        assert!(square.pos11.y > 1.0 && square.pos22.y == square.pos11.y);
+        assert_eq!(square.tag.as_deref(), Some("marker/goal"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Tweening between two lists of faces, so a level changing its state moves instead of popping.
+//!
+//! Faces are matched by their `tag`, the untagged ones by their place in the lists.
+//! A face in only one of the lists appears or disappears, and so does a face skipped on one side.
+
+use crate::{Color, Colored, Face, V3};
+
+/// How a face in only one of the lists comes and goes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Appear {
+    /// the colors go from and to transparent
+    #[default]
+    Fade,
+    /// grows from and shrinks to the middle of the face
+    Grow,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Morph {
+    from: Vec<Face>,
+    to: Vec<Face>,
+    /// for each face of `to`, the face of `from` it comes from
+    matched: Vec<Option<usize>>,
+    /// the faces of `from` matching nothing
+    gone: Vec<usize>,
+}
+
+impl Morph {
+    pub fn new(from: Vec<Face>, to: Vec<Face>) -> Self {
+        let mut used = vec![false; from.len()];
+        let mut matched = Vec::with_capacity(to.len());
+        for (i, f) in to.iter().enumerate() {
+            let j = match &f.tag {
+                Some(tag) => {
+                    (0..from.len()).find(|j| !used[*j] && from[*j].tag.as_ref() == Some(tag))
+                }
+                None => (i < from.len() && !used[i] && from[i].tag.is_none()).then_some(i),
+            };
+            if let Some(j) = j {
+                used[j] = true;
+            }
+            matched.push(j);
+        }
+        let gone = (0..from.len()).filter(|j| !used[*j]).collect();
+        Self {
+            from,
+            to,
+            matched,
+            gone,
+        }
+    }
+
+    /// the faces where the morph ends
+    pub fn target(&self) -> &Vec<Face> {
+        &self.to
+    }
+
+    /// The faces at `t`, which ranges from 0 to 1.
+    ///
+    /// The faces of `to` come first in their order, so their ids are the same as in `to`,
+    /// then the faces going away. Angle dependent colors are evaluated at `angle` while they are mixed
+    pub fn at(&self, t: f32, appear: Appear, angle: f32) -> Vec<Face> {
+        let t = t.clamp(0.0, 1.0);
+        let mut res = Vec::with_capacity(self.to.len() + self.gone.len());
+        for (f, m) in self.to.iter().zip(&self.matched) {
+            res.push(match m.map(|j| &self.from[j]) {
+                Some(g) if g.skipped == f.skipped => mix_faces(g, f, t, angle),
+                Some(g) if f.skipped => appearing(g, 1.0 - t, appear, angle),
+                _ => appearing(f, t, appear, angle),
+            });
+        }
+        for j in &self.gone {
+            res.push(appearing(&self.from[*j], 1.0 - t, appear, angle));
+        }
+        res
+    }
+}
+
+fn mix_v3(a: &V3, b: &V3, t: f32) -> V3 {
+    V3::from(
+        a.x + (b.x - a.x) * t,
+        a.y + (b.y - a.y) * t,
+        a.z + (b.z - a.z) * t,
+    )
+}
+
+fn mix_colors(a: &Colored, b: &Colored, t: f32, angle: f32) -> Colored {
+    match (a, b) {
+        _ if a == b && !a.is_angled() => a.clone(),
+        (Colored::Pure(a), Colored::Pure(b)) => Colored::Pure(a.mix(b, t)),
+        _ => Colored::Vertex(
+            (0..4)
+                .map(|i| a.get_at(i, angle).mix(&b.get_at(i, angle), t))
+                .collect(),
+        ),
+    }
+}
+
+fn mix_faces(a: &Face, b: &Face, t: f32, angle: f32) -> Face {
+    let mut f = b.clone();
+    f.pos11 = mix_v3(&a.pos11, &b.pos11, t);
+    f.pos12 = mix_v3(&a.pos12, &b.pos12, t);
+    f.pos21 = mix_v3(&a.pos21, &b.pos21, t);
+    f.pos22 = mix_v3(&a.pos22, &b.pos22, t);
+    f.index = a.index + (b.index - a.index) * t;
+    f.color = mix_colors(&a.color, &b.color, t, angle);
+    if t < 0.5 {
+        f.musks = a.musks.clone();
+    }
+    f.pos_slice = None;
+    f
+}
+
+/// The face `f` when it has appeared by `t`, it is gone at 0 and whole at 1
+fn appearing(f: &Face, t: f32, appear: Appear, angle: f32) -> Face {
+    let mut res = f.clone();
+    res.skipped = false;
+    match appear {
+        Appear::Fade => {
+            // the colors are premultiplied, so every channel goes to 0
+            let clear = Colored::Pure(Color::default());
+            res.color = mix_colors(&clear, &f.color, t, angle);
+        }
+        Appear::Grow => {
+            let c = V3::from(
+                (f.pos11.x + f.pos12.x + f.pos21.x + f.pos22.x) / 4.0,
+                (f.pos11.y + f.pos12.y + f.pos21.y + f.pos22.y) / 4.0,
+                (f.pos11.z + f.pos12.z + f.pos21.z + f.pos22.z) / 4.0,
+            );
+            res.pos11 = mix_v3(&c, &f.pos11, t);
+            res.pos12 = mix_v3(&c, &f.pos12, t);
+            res.pos21 = mix_v3(&c, &f.pos21, t);
+            res.pos22 = mix_v3(&c, &f.pos22, t);
+            res.pos_slice = None;
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pillar, V2};
+
+    fn white(face: Face) -> Face {
+        face.with_color(Colored::Pure(Color::from(1.0, 1.0, 1.0, 1.0)))
+    }
+
+    #[test]
+    fn matches_by_tag_then_by_place() {
+        let from = vec![
+            white(Face::new_on_z(0.0, V2(0.0, 0.0), V2(1.0, 1.0))).with_tag("a"),
+            white(Face::new_on_z(5.0, V2(0.0, 0.0), V2(1.0, 1.0))),
+            white(Face::new_on_x(0.0, V2(0.0, 0.0), V2(1.0, 1.0))).with_tag("gone"),
+        ];
+        let to = vec![
+            white(Face::new_on_z(7.0, V2(0.0, 0.0), V2(1.0, 1.0))),
+            white(Face::new_on_z(2.0, V2(0.0, 0.0), V2(1.0, 1.0))).with_tag("a"),
+        ];
+        let morph = Morph::new(from, to);
+        let half = morph.at(0.5, Appear::Fade, 0.0);
+        assert_eq!(half.len(), 4);
+        // the tagged face moves
+        assert_eq!(half[1].pos11.z, 1.0);
+        // the one without a tag takes the place of the first one of `from`, which is tagged
+        assert_eq!(half[0].pos11.z, 7.0);
+        assert_eq!(
+            half[0].color,
+            Colored::Pure(Color::from(0.5, 0.5, 0.5, 0.5))
+        );
+        // the rest of `from` goes away in its order
+        assert_eq!(half[2].pos11.z, 5.0);
+        assert_eq!(half[3].tag.as_deref(), Some("gone"));
+        assert_eq!(
+            half[3].color,
+            Colored::Pure(Color::from(0.5, 0.5, 0.5, 0.5))
+        );
+        assert_eq!(
+            morph.at(1.0, Appear::Fade, 0.0)[1].pos11,
+            morph.target()[1].pos11
+        );
+    }
+
+    #[test]
+    fn pillars_grow_and_stretch() {
+        let from = Pillar::new_upright(V3::from(0.0, 0.0, 0.0), V3::from(2.0, 2.0, 2.0))
+            .with_tag("p")
+            .into_vec();
+        let to = Pillar::new_upright(V3::from(0.0, 0.0, 0.0), V3::from(2.0, 4.0, 2.0))
+            .with_tag("p")
+            .with_skipped_filter(false, false, false, false, false, true)
+            .into_vec();
+        let morph = Morph::new(from, to);
+        let start = morph.at(0.0, Appear::Grow, 0.0);
+        assert_eq!(start.len(), 6);
+        assert_eq!(start[2].tag.as_deref(), Some("p/up"));
+        assert_eq!(start[2].pos11.y, 2.0);
+        assert_eq!(morph.at(0.5, Appear::Grow, 0.0)[2].pos11.y, 3.0);
+        // the back is skipped in the end, so it shrinks away
+        let back = &morph.at(1.0, Appear::Grow, 0.0)[5];
+        assert!(!back.skipped);
+        assert_eq!(back.pos11, back.pos22);
+    }
+}
@@ -0,0 +1,59 @@
+//! The order the faces are drawn in, by `GLFacesView` and the rasterizer alike.
+//!
+//! The opaque faces come first in their order, the depth test puts them right at any order.
+//! The translucent ones come after them from the back to the front by the depth of their middle,
+//! so whatever is behind a glass is already drawn when the glass is blended over it.
+//! Translucent faces crossing each other are sorted as a whole, one of them is still wrong.
+
+use crate::{Face, Projection};
+
+/// Whether any corner of the face lets what is behind it through at `angle`
+pub fn is_translucent(face: &Face, angle: f32) -> bool {
+    (0..4).any(|i| face.color.get_at(i, angle).a < 1.0)
+}
+
+/// The ids of the faces to draw in their order, the skipped ones are left out
+pub fn draw_order(faces: &[Face], proj: &Projection) -> Vec<usize> {
+    let mut order = Vec::with_capacity(faces.len());
+    let mut translucent = vec![];
+    for (id, f) in faces.iter().enumerate().filter(|(_, f)| !f.skipped) {
+        if is_translucent(f, proj.angle) {
+            let depth = [&f.pos11, &f.pos12, &f.pos21, &f.pos22]
+                .iter()
+                .map(|p| proj.face_clip(p, f.index).z)
+                .sum::<f32>();
+            translucent.push((id, depth));
+        } else {
+            order.push(id);
+        }
+    }
+    // the depth is larger for what is behind, the ties keep their order
+    translucent.sort_by(|a, b| b.1.total_cmp(&a.1));
+    order.extend(translucent.iter().map(|(id, _)| *id));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Colored, V2};
+
+    #[test]
+    fn translucent_faces_go_last_from_the_back() {
+        let glass = Colored::Pure(Color::from(0.2, 0.2, 0.2, 0.5));
+        let mut faces = vec![
+            Face::new_on_z(2.0, V2(0.0, 0.0), V2(1.0, 1.0)).with_color(glass.clone()),
+            Face::new_on_z(0.0, V2(0.0, 0.0), V2(1.0, 1.0))
+                .with_color(Colored::Pure(Color::from(1.0, 1.0, 1.0, 1.0))),
+            Face::new_on_z(-2.0, V2(0.0, 0.0), V2(1.0, 1.0)).with_color(glass.clone()),
+            Face::new_on_z(4.0, V2(0.0, 0.0), V2(1.0, 1.0)).with_color(glass),
+        ];
+        faces[3].skipped = true;
+        // looking from +z, the face at z = -2 is behind
+        let proj = Projection::new(0.0, 0.5, 1.0);
+        assert_eq!(draw_order(&faces, &proj), vec![1, 2, 0]);
+        // looking from -z, the other way round
+        let proj = Projection::new(180_f32.to_radians(), 0.5, 1.0);
+        assert_eq!(draw_order(&faces, &proj), vec![1, 0, 2]);
+    }
+}
@@ -0,0 +1,146 @@
+//! Finding the face under a point of the screen, the same face that `b_faces` draws there.
+
+use crate::{projection::face_alpha, Face, Projection, V3};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Picked {
+    /// the position of the face in the list
+    pub id: usize,
+    /// the point of the face in world space
+    pub pos: V3,
+    /// the depth in clip space, smaller is nearer
+    pub depth: f32,
+}
+
+/// The triangles drawn for a face by `GLFacesView`, every point of the face is covered twice
+pub const FACE_TRIANGLES: [[usize; 3]; 4] = [[0, 1, 2], [1, 2, 3], [2, 3, 0], [3, 0, 1]];
+
+/// Barycentric coordinates of `(x, y)` in the triangle `a b c`, None if it is outside
+pub fn barycentric(
+    a: (f32, f32),
+    b: (f32, f32),
+    c: (f32, f32),
+    x: f32,
+    y: f32,
+) -> Option<[f32; 3]> {
+    let det = (b.1 - c.1) * (a.0 - c.0) + (c.0 - b.0) * (a.1 - c.1);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let l0 = ((b.1 - c.1) * (x - c.0) + (c.0 - b.0) * (y - c.1)) / det;
+    let l1 = ((c.1 - a.1) * (x - c.0) + (a.0 - c.0) * (y - c.1)) / det;
+    let l2 = 1.0 - l0 - l1;
+    const E: f32 = -1e-6;
+    if l0 >= E && l1 >= E && l2 >= E {
+        Some([l0, l1, l2])
+    } else {
+        None
+    }
+}
+
+/// The front-most visible face at `(x, y)` in clip space.
+///
+/// Skipped faces, the parts cut off by musks and fully transparent parts are not picked.
+/// Set `musk_enabled` to false to ignore the musks like `GLFacesView::set_musk_enabled`.
+pub fn pick_face(
+    faces: &[Face],
+    proj: &Projection,
+    x: f32,
+    y: f32,
+    musk_enabled: bool,
+) -> Option<Picked> {
+    let (vx, vy) = proj.clip_to_view(x, y);
+    let mut res: Option<Picked> = None;
+    for (id, f) in faces.iter().enumerate().filter(|(_, f)| !f.skipped) {
+        if musk_enabled && !proj.through_musks(&f.musks, vx, vy) {
+            continue;
+        }
+        let pos = [&f.pos11, &f.pos12, &f.pos21, &f.pos22];
+        let clip = pos.map(|p| proj.face_clip(p, f.index));
+        for t in FACE_TRIANGLES {
+            let Some(l) = barycentric(
+                (clip[t[0]].x, clip[t[0]].y),
+                (clip[t[1]].x, clip[t[1]].y),
+                (clip[t[2]].x, clip[t[2]].y),
+                x,
+                y,
+            ) else {
+                continue;
+            };
+            let depth = l[0] * clip[t[0]].z + l[1] * clip[t[1]].z + l[2] * clip[t[2]].z;
+            if depth.abs() > 1.0 || res.as_ref().is_some_and(|r| r.depth <= depth) {
+                continue;
+            }
+            let alpha = (0..3)
+                .map(|i| l[i] * f.color.get_at(t[i], proj.angle).a)
+                .sum::<f32>();
+            if face_alpha(alpha) <= 0.0 {
+                continue;
+            }
+            let (a, b, c) = (pos[t[0]], pos[t[1]], pos[t[2]]);
+            res = Some(Picked {
+                id,
+                pos: V3::from(
+                    l[0] * a.x + l[1] * b.x + l[2] * c.x,
+                    l[0] * a.y + l[1] * b.y + l[2] * c.y,
+                    l[0] * a.z + l[1] * b.z + l[2] * c.z,
+                ),
+                depth,
+            });
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Colored, Musk, MuskSpace, Pillar, V2};
+
+    /// a square around `(c, c, c)`, all of them cover the same place on the screen
+    fn square(c: f32, index: f32) -> Face {
+        Face::new_on_z(c, V2(c - 1.0, c - 1.0), V2(2.0, 2.0))
+            .with_color(Colored::Pure(Color::from(1.0, 1.0, 1.0, 1.0)))
+            .with_w(index)
+    }
+
+    #[test]
+    fn picks_nearest() {
+        let proj = Projection::default();
+        let faces = vec![square(-1.0, 0.0), square(1.0, 0.0)];
+        let p = pick_face(&faces, &proj, 0.0, 0.0, true).unwrap();
+        assert_eq!(p.id, 1);
+        assert!((p.pos.z - 1.0).abs() < 1e-5);
+        assert!(pick_face(&faces, &proj, 0.99, 0.99, true).is_none());
+    }
+
+    #[test]
+    fn index_skipped_and_musks() {
+        let proj = Projection::default();
+        let mut faces = vec![square(-1.0, 0.5), square(1.0, 0.0)];
+        assert_eq!(pick_face(&faces, &proj, 0.0, 0.0, true).unwrap().id, 0);
+        faces[0].skipped = true;
+        assert_eq!(pick_face(&faces, &proj, 0.0, 0.0, true).unwrap().id, 1);
+        faces[0].skipped = false;
+        // cut off the upper half of the front face
+        faces[0] = faces[0]
+            .clone()
+            .with_musk(Musk::new_on_screen(0.0, 0.0, 1.0, 0.0));
+        assert_eq!(pick_face(&faces, &proj, 0.0, 0.1, true).unwrap().id, 1);
+        assert_eq!(pick_face(&faces, &proj, 0.0, -0.1, true).unwrap().id, 0);
+        assert_eq!(pick_face(&faces, &proj, 0.0, 0.1, false).unwrap().id, 0);
+        assert_eq!(faces[0].musks[0].space, MuskSpace::Screen);
+    }
+
+    #[test]
+    fn picks_top_of_pillar() {
+        let proj = Projection::new(0.3, 0.1, 1.0);
+        let faces =
+            Pillar::new_upright(V3::from(-1.0, -1.0, -1.0), V3::from(2.0, 2.0, 2.0)).into_vec();
+        let top = proj.face_clip(&V3::from(0.0, 1.0, 0.0), 0.0);
+        let p = pick_face(&faces, &proj, top.x, top.y, true).unwrap();
+        // the faces are right, left, up, down, front, back
+        assert_eq!(p.id, 2);
+        assert!((p.pos.y - 1.0).abs() < 1e-4);
+    }
+}
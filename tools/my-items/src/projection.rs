@@ -0,0 +1,479 @@
+//! The projection of `b_faces.vs` and `b_lines.vs`, done on the cpu.
+//!
+//! A point goes through three spaces:
+//! - world: where the items are defined
+//! - view: after `u_pj * u_proj`, y ranges from -1 to 1 on the screen and x is scaled by the aspect ratio
+//! - clip: what the shaders write to `gl_Position`, x and y range from -1 to 1
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::{Musk, MuskSpace, V3};
+
+/// `u_pj` of the isometric projection in glsl (column) order, the images of the x, y and z axes.
+/// The last row is the depth, larger for what is nearer
+pub const PJ: [[f32; 3]; 3] = [
+    [0.866025, -0.5, 1.0],  // x -> x'
+    [0.0, 1.0, 1.0],        // y -> y'
+    [-0.866025, -0.5, 1.0], // z -> z'
+];
+/// `zRatio` of `b_faces.vs`, the shaders multiply the last row of `u_pj` by it
+pub const FACES_Z_RATIO: f32 = 0.01;
+/// `zoffset` of `b_faces.vs`
+pub const FACES_Z_OFFSET: f32 = 0.0001;
+/// `zRatio` of `b_lines.vs`
+pub const LINES_Z_RATIO: f32 = -0.1;
+
+/// How the turned scene is put on the screen, all of them are parallel projections
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProjectionMode {
+    /// the three axes 120 degrees apart, as the levels are made for
+    #[default]
+    Isometric,
+    /// seen from 30 degrees above, the horizontal edges rise 1 in 2
+    Dimetric,
+    /// the front kept as it is, the depth drawn at 45 degrees in full length
+    Cavalier,
+    /// the ground plan kept as it is but turned by 45 degrees, the height drawn straight up
+    Military,
+    /// seen straight from the front. With the scale standing for the distance to the camera,
+    /// it is a weak perspective
+    WeakPerspective,
+}
+
+impl ProjectionMode {
+    pub const ALL: [Self; 5] = [
+        Self::Isometric,
+        Self::Dimetric,
+        Self::Cavalier,
+        Self::Military,
+        Self::WeakPerspective,
+    ];
+
+    /// `u_pj` of the shaders in glsl (column) order, see `PJ`
+    pub fn pj(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Isometric => PJ,
+            Self::Dimetric => [
+                [0.866025, -0.433013, 1.06066],
+                [0.0, 1.06066, 0.866025],
+                [-0.866025, -0.433013, 1.06066],
+            ],
+            Self::Cavalier => [
+                [1.0, 0.0, 0.866025],
+                [0.0, 1.0, 0.866025],
+                [-FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 1.224745],
+            ],
+            Self::Military => [
+                [FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.866025],
+                [0.0, 1.0, 1.224745],
+                [-FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.866025],
+            ],
+            Self::WeakPerspective => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.732051]],
+        }
+    }
+
+    /// The axis in world space that goes right on the screen, which the pitch turns around
+    pub fn pitch_axis(self) -> V3 {
+        match self {
+            Self::Isometric | Self::Dimetric | Self::Military => {
+                V3::from(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2)
+            }
+            Self::Cavalier | Self::WeakPerspective => V3::from(1.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Isometric => "isometric",
+            Self::Dimetric => "dimetric",
+            Self::Cavalier => "cavalier",
+            Self::Military => "military",
+            Self::WeakPerspective => "weak perspective",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    /// turning around the y axis, in radians
+    pub angle: f32,
+    /// turning around the `pitch_axis` of the mode after the angle, in radians.
+    /// Positive shows more of the top
+    pub pitch: f32,
+    pub mode: ProjectionMode,
+    pub scale: f32,
+    /// width / height of the viewport
+    pub aspect_ratio: f32,
+    /// added to every point before it is turned, `u_translate` of the shaders
+    pub translate: V3,
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self {
+            angle: 0.0,
+            pitch: 0.0,
+            mode: ProjectionMode::default(),
+            scale: 1.0,
+            aspect_ratio: 1.0,
+            translate: V3::default(),
+        }
+    }
+}
+
+impl Projection {
+    pub fn new(angle: f32, scale: f32, aspect_ratio: f32) -> Self {
+        Self {
+            angle,
+            scale,
+            aspect_ratio,
+            ..Default::default()
+        }
+    }
+    pub fn with_translate(mut self, translate: V3) -> Self {
+        self.translate = translate;
+        self
+    }
+    pub fn with_mode(mut self, mode: ProjectionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    pub fn with_pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// `u_pj` of the shaders, the projection of the mode in glsl (column) order
+    pub fn pj_mat(&self) -> [f32; 9] {
+        let pj = self.mode.pj();
+        [
+            pj[0][0], pj[0][1], pj[0][2], //
+            pj[1][0], pj[1][1], pj[1][2], //
+            pj[2][0], pj[2][1], pj[2][2], //
+        ]
+    }
+
+    /// `u_proj` of the shaders, the turning and scaling in glsl (column) order
+    pub fn proj_mat(&self) -> [f32; 9] {
+        let (sin, cos) = self.angle.sin_cos();
+        let yaw = [
+            [cos, 0.0, -sin], // x
+            [0.0, 1.0, 0.0],  // y
+            [sin, 0.0, cos],  // z
+        ];
+        let m = if self.pitch == 0.0 {
+            yaw
+        } else {
+            // Rodrigues' rotation around the pitch axis, applied after the yaw
+            let a = self.mode.pitch_axis();
+            let (sin, cos) = self.pitch.sin_cos();
+            let a = [a.x, a.y, a.z];
+            let pitch = |v: [f32; 3]| {
+                let dot = a[0] * v[0] + a[1] * v[1] + a[2] * v[2];
+                let cross = [
+                    a[1] * v[2] - a[2] * v[1],
+                    a[2] * v[0] - a[0] * v[2],
+                    a[0] * v[1] - a[1] * v[0],
+                ];
+                [0, 1, 2].map(|i| v[i] * cos + cross[i] * sin + a[i] * dot * (1.0 - cos))
+            };
+            yaw.map(pitch)
+        };
+        [
+            self.scale * m[0][0],
+            self.scale * m[0][1],
+            self.scale * m[0][2],
+            self.scale * m[1][0],
+            self.scale * m[1][1],
+            self.scale * m[1][2],
+            self.scale * m[2][0],
+            self.scale * m[2][1],
+            self.scale * m[2][2],
+        ]
+    }
+
+    /// `u_pj * u_proj * (p + u_translate)` with the last row multiplied by `zRatio`
+    pub fn view_with(&self, p: &V3, z_ratio: f32) -> V3 {
+        let t = &self.translate;
+        self.view_dir_with(&V3::from(p.x + t.x, p.y + t.y, p.z + t.z), z_ratio)
+    }
+    /// `u_pj * u_proj * d`, a direction is not moved by the translation
+    fn view_dir_with(&self, d: &V3, z_ratio: f32) -> V3 {
+        let m = self.proj_mat();
+        let r = [
+            m[0] * d.x + m[3] * d.y + m[6] * d.z,
+            m[1] * d.x + m[4] * d.y + m[7] * d.z,
+            m[2] * d.x + m[5] * d.y + m[8] * d.z,
+        ];
+        let pj = self.mode.pj();
+        V3::from(
+            pj[0][0] * r[0] + pj[1][0] * r[1] + pj[2][0] * r[2],
+            pj[0][1] * r[0] + pj[1][1] * r[1] + pj[2][1] * r[2],
+            z_ratio * (pj[0][2] * r[0] + pj[1][2] * r[1] + pj[2][2] * r[2]),
+        )
+    }
+    /// a point of a face in view space
+    pub fn view(&self, p: &V3) -> V3 {
+        self.view_with(p, FACES_Z_RATIO)
+    }
+
+    /// `gl_Position` of a face vertex in `b_faces.vs`,
+    /// the depth is smaller for what is in front
+    pub fn face_clip(&self, p: &V3, index: f32) -> V3 {
+        let v = self.view(p);
+        V3::from(v.x / self.aspect_ratio, v.y, -(v.z + index))
+    }
+    /// `gl_Position` of a line vertex in `b_lines.vs`
+    pub fn line_clip(&self, p: &V3) -> V3 {
+        let v = self.view_with(p, LINES_Z_RATIO);
+        V3::from(v.x / self.aspect_ratio, v.y, v.z)
+    }
+
+    pub fn view_to_clip(&self, x: f32, y: f32) -> (f32, f32) {
+        (x / self.aspect_ratio, y)
+    }
+    pub fn clip_to_view(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * self.aspect_ratio, y)
+    }
+    /// clip space to pixels of a `width` x `height` viewport, y goes down
+    pub fn clip_to_pixel(x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+        ((x + 1.0) / 2.0 * width, (1.0 - y) / 2.0 * height)
+    }
+    pub fn pixel_to_clip(x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+        (x / width * 2.0 - 1.0, 1.0 - y / height * 2.0)
+    }
+
+    /// The musk in view space: the position and direction of its line
+    pub fn view_musk(&self, musk: &Musk) -> ((f32, f32), (f32, f32)) {
+        match musk.space {
+            MuskSpace::World => {
+                let p = self.view(&musk.pos);
+                let d = self.view_dir_with(&musk.dir, FACES_Z_RATIO);
+                ((p.x, p.y), (d.x, d.y))
+            }
+            MuskSpace::Screen => ((musk.pos.x, musk.pos.y), (musk.dir.x, musk.dir.y)),
+        }
+    }
+    /// The move in world space that moves the scene by `(dx, dy)` in view space,
+    /// without changing the depth
+    pub fn view_to_world(&self, dx: f32, dy: f32) -> V3 {
+        // the columns of `u_pj * u_proj`, solved for `(dx, dy, 0)` by Cramer's rule
+        let [x, y, z] = [
+            V3::from(1.0, 0.0, 0.0),
+            V3::from(0.0, 1.0, 0.0),
+            V3::from(0.0, 0.0, 1.0),
+        ]
+        .map(|axis| self.view_dir_with(&axis, 1.0));
+        let det = |a: &V3, b: &V3, c: &V3| {
+            a.x * (b.y * c.z - c.y * b.z) - b.x * (a.y * c.z - c.y * a.z)
+                + c.x * (a.y * b.z - b.y * a.z)
+        };
+        let d = det(&x, &y, &z);
+        if d.abs() < f32::EPSILON {
+            return V3::default();
+        }
+        let v = V3::from(dx, dy, 0.0);
+        V3::from(
+            det(&v, &y, &z) / d,
+            det(&x, &v, &z) / d,
+            det(&x, &y, &v) / d,
+        )
+    }
+
+    /// The projection at the same angle with the points in the middle of the screen,
+    /// scaled to take `fill` of the smaller side of it.
+    /// It is not changed if there are no points.
+    pub fn fit<'a>(&self, points: impl IntoIterator<Item = &'a V3>, fill: f32) -> Self {
+        let points: Vec<&V3> = points.into_iter().collect();
+        let Some(first) = points.first() else {
+            return self.clone();
+        };
+        let (mut min, mut max) = ((*first).clone(), (*first).clone());
+        for p in &points {
+            min = V3::from(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            max = V3::from(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+        }
+        let centre = V3::from(
+            -(min.x + max.x) / 2.0,
+            -(min.y + max.y) / 2.0,
+            -(min.z + max.z) / 2.0,
+        );
+        let unit = Self {
+            scale: 1.0,
+            translate: centre,
+            ..self.clone()
+        };
+        let size = points
+            .iter()
+            .map(|p| {
+                let v = unit.view(p);
+                (v.x.abs() / self.aspect_ratio).max(v.y.abs())
+            })
+            .fold(0.0, f32::max);
+        if size <= 0.0 {
+            return Self {
+                scale: self.scale,
+                ..unit
+            };
+        }
+        Self {
+            scale: fill / size,
+            ..unit
+        }
+    }
+
+    /// Whether the point `(x, y)` in view space is let through by all musks,
+    /// the same test as in `b_faces.fs`
+    pub fn through_musks(&self, musks: &[Musk], x: f32, y: f32) -> bool {
+        musks.iter().take(Musk::MAX).all(|m| {
+            let ((px, py), (dx, dy)) = self.view_musk(m);
+            (x * dy - y * dx) - (px * dy - py * dx) >= 0.0
+        })
+    }
+}
+
+/// The alpha of a face after `b_faces.vs`, `a / (2 - a)`
+pub fn face_alpha(a: f32) -> f32 {
+    a / (2.0 - a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FACES_VS: &str = include_str!("../../../assets/shaders/b_faces.vs");
+    const LINES_VS: &str = include_str!("../../../assets/shaders/b_lines.vs");
+    const FACES_WGSL: &str = include_str!("../../../assets/shaders/b_faces.wgsl");
+    const LINES_WGSL: &str = include_str!("../../../assets/shaders/b_lines.wgsl");
+
+    /// the value of `const highp float {name} = {value};` in glsl
+    /// or `const {name}: f32 = {value};` in wgsl
+    fn shader_const(src: &str, name: &str) -> f32 {
+        let (glsl, wgsl) = (format!("float {name} = "), format!("{name}: f32 = "));
+        let start = src
+            .find(&glsl)
+            .map(|i| i + glsl.len())
+            .or_else(|| src.find(&wgsl).map(|i| i + wgsl.len()))
+            .expect("constant not found");
+        let end = start + src[start..].find(';').unwrap();
+        src[start..end].trim().parse().unwrap()
+    }
+
+    #[test]
+    fn same_constants_as_shaders() {
+        assert_eq!(shader_const(FACES_VS, "zRatio"), FACES_Z_RATIO);
+        assert_eq!(shader_const(FACES_VS, "zoffset"), FACES_Z_OFFSET);
+        assert_eq!(shader_const(LINES_VS, "zRatio"), LINES_Z_RATIO);
+        assert_eq!(shader_const(FACES_WGSL, "zRatio"), FACES_Z_RATIO);
+        assert_eq!(shader_const(LINES_WGSL, "zRatio"), LINES_Z_RATIO);
+        // the projection of the mode is a uniform, with the depth scaled by zRatio
+        for src in [FACES_VS, LINES_VS] {
+            assert!(src.contains("uniform mat3 u_pj;"));
+            assert!(src.contains("mat3(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u_pj"));
+        }
+        for src in [FACES_WGSL, LINES_WGSL] {
+            assert!(src.contains("pj: mat3x3<f32>,"));
+            assert!(
+                src.contains("mat3x3<f32>(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, zRatio) * u.pj")
+            );
+        }
+    }
+
+    #[test]
+    fn projects_axes() {
+        let p = Projection::new(0.0, 2.0, 1.0);
+        let x = p.view(&V3::from(1.0, 0.0, 0.0));
+        assert!((x.x - 2.0 * 0.866025).abs() < 1e-6 && (x.y + 1.0).abs() < 1e-6);
+        let y = p.view(&V3::from(0.0, 1.0, 0.0));
+        assert!(y.x.abs() < 1e-6 && (y.y - 2.0).abs() < 1e-6);
+        // turning by 90 degrees brings the z axis to where the x axis was
+        let p = Projection::new(90_f32.to_radians(), 2.0, 1.0);
+        let z = p.view(&V3::from(0.0, 0.0, 1.0));
+        assert!((z.x - x.x).abs() < 1e-5 && (z.y - x.y).abs() < 1e-5);
+    }
+
+    #[test]
+    fn modes_pan_and_pitch() {
+        for mode in ProjectionMode::ALL {
+            let flat = Projection::new(0.0, 0.5, 2.0).with_mode(mode);
+            let up = V3::from(0.0, 1.0, 0.0);
+            assert!((flat.view(&up).y - 0.5 * mode.pj()[1][1]).abs() < 1e-6);
+            // the right of the screen does not move when pitching
+            let axis = mode.pitch_axis();
+            let tilted = flat.clone().with_pitch(0.3);
+            assert!((tilted.view(&axis).x - flat.view(&axis).x).abs() < 1e-5);
+            // the top comes nearer
+            assert!(tilted.view(&up).z > flat.view(&up).z);
+
+            let tilted = Projection {
+                angle: 0.4,
+                ..tilted
+            };
+            let p = V3::from(1.0, -2.0, 0.5);
+            let d = tilted.view_to_world(0.3, -0.2);
+            let moved = tilted.clone().with_translate(d);
+            let (a, b) = (tilted.view(&p), moved.view(&p));
+            assert!((b.x - a.x - 0.3).abs() < 1e-5, "{mode:?}");
+            assert!((b.y - a.y + 0.2).abs() < 1e-5, "{mode:?}");
+            assert!((b.z - a.z).abs() < 1e-6, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn front_has_smaller_depth() {
+        let p = Projection::new(0.0, 1.0, 16.0 / 9.0);
+        let near = p.face_clip(&V3::from(1.0, 1.0, 1.0), 0.0);
+        let far = p.face_clip(&V3::from(-1.0, -1.0, -1.0), 0.0);
+        assert!(near.z < far.z);
+        assert!(p.face_clip(&V3::from(-1.0, -1.0, -1.0), 0.5).z < near.z);
+    }
+
+    #[test]
+    fn translates_and_fits() {
+        let p = Projection::new(0.4, 0.5, 2.0).with_translate(V3::from(1.0, 2.0, 3.0));
+        let origin = p.view(&V3::from(-1.0, -2.0, -3.0));
+        assert!(origin.x.abs() < 1e-6 && origin.y.abs() < 1e-6);
+        // panning moves every point by the same amount on the screen
+        let d = p.view_to_world(0.3, -0.2);
+        let moved = p
+            .clone()
+            .with_translate(V3::from(1.0 + d.x, 2.0 + d.y, 3.0 + d.z));
+        let v = moved.view(&V3::from(-1.0, -2.0, -3.0));
+        assert!((v.x - 0.3).abs() < 1e-5 && (v.y + 0.2).abs() < 1e-5 && v.z.abs() < 1e-6);
+
+        let corners = [V3::from(2.0, 2.0, 2.0), V3::from(4.0, 6.0, 4.0)];
+        let fitted = p.fit(&corners, 0.9);
+        let a = fitted.face_clip(&corners[0], 0.0);
+        let b = fitted.face_clip(&corners[1], 0.0);
+        assert!((a.x + b.x).abs() < 1e-5 && (a.y + b.y).abs() < 1e-5);
+        let size = a.x.abs().max(a.y.abs()).max(b.x.abs()).max(b.y.abs());
+        assert!((size - 0.9).abs() < 1e-5);
+        assert_eq!(p.fit(&[], 0.9), p);
+    }
+
+    #[test]
+    fn pixels_round_trip() {
+        let (x, y) = Projection::clip_to_pixel(0.25, -0.5, 800.0, 600.0);
+        assert_eq!((x, y), (500.0, 450.0));
+        assert_eq!(Projection::pixel_to_clip(x, y, 800.0, 600.0), (0.25, -0.5));
+    }
+
+    #[test]
+    fn musks_cut_left_side() {
+        let p = Projection::default();
+        let musk = Musk::new_on_screen(0.0, 0.0, 1.0, 0.0);
+        assert!(p.through_musks(std::slice::from_ref(&musk), 0.0, -0.5));
+        assert!(!p.through_musks(&[musk], 0.0, 0.5));
+        let square = Musk::convex_polygon(
+            &[
+                V3::from(-1.0, 1.0, 0.0),
+                V3::from(1.0, 1.0, 0.0),
+                V3::from(1.0, -1.0, 0.0),
+                V3::from(-1.0, -1.0, 0.0),
+            ],
+            MuskSpace::Screen,
+        );
+        assert!(p.through_musks(&square, 0.5, 0.5));
+        assert!(!p.through_musks(&square, 1.5, 0.5));
+    }
+}
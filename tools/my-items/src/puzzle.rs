@@ -0,0 +1,383 @@
+//! Parts of a level which the player moves, as the camera moves the level by its angle.
+//!
+//! A `Plate` is pressed while the avatar stands on it, a `Lever` is switched by clicking it.
+//! A switch moves the parts linked to it: a `Platform` turns a quarter about its axis
+//! every time the switch goes on, and a `Bridge` is extended while the switch is on.
+//! Every change is told back as a `PuzzleEvent`, so the level can do more of its own.
+//!
+//! A part is a block, its faces are tagged `name/side` by `Pillar::with_tag`,
+//! so they morph between the states of the part and are known to belong to it.
+//! A level may keep a `Puzzle` in its own code, or declare it in a `scene::Scene`.
+
+use crate::{Color, Colored, Face, Pillar, V3};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PartKind {
+    /// on while the avatar stands on its top
+    Plate,
+    /// switched on and off by clicking it
+    Lever,
+    /// turns a quarter about the `axis` through `pivot`, by the right hand
+    Platform { axis: Axis, pivot: V3 },
+    /// its far end is moved by `reach` when extended
+    Bridge { reach: V3 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Part {
+    pub name: String,
+    pub kind: PartKind,
+    /// the block of the part in its first state, as `Pillar::new_upright`
+    pub pos: V3,
+    pub size: V3,
+    /// 1 for on and 0 for off of the switches and the bridges,
+    /// the quarter turns from 0 to 3 of the platforms
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub state: u32,
+}
+
+/// A change of a part, by the player or by a switch linked to it
+#[derive(Clone, Debug, PartialEq)]
+pub enum PuzzleEvent {
+    /// a plate or a lever went on or off
+    Switched { name: String, on: bool },
+    /// a platform turned, `turns` quarters from its first state
+    Turned { name: String, turns: u32 },
+    /// a bridge was extended or drawn back
+    Extended { name: String, extended: bool },
+}
+
+impl Part {
+    /// the color of a lever which is on
+    const ON: Color = Color {
+        r: 0.4,
+        g: 0.75,
+        b: 0.95,
+        a: 1.0,
+    };
+
+    pub fn new(name: &str, kind: PartKind, pos: V3, size: V3) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            pos,
+            size,
+            state: 0,
+        }
+    }
+    pub fn plate(name: &str, pos: V3, size: V3) -> Self {
+        Self::new(name, PartKind::Plate, pos, size)
+    }
+    pub fn lever(name: &str, pos: V3, size: V3) -> Self {
+        Self::new(name, PartKind::Lever, pos, size)
+    }
+    pub fn platform(name: &str, pos: V3, size: V3, axis: Axis, pivot: V3) -> Self {
+        Self::new(name, PartKind::Platform { axis, pivot }, pos, size)
+    }
+    pub fn bridge(name: &str, pos: V3, size: V3, reach: V3) -> Self {
+        Self::new(name, PartKind::Bridge { reach }, pos, size)
+    }
+
+    pub fn is_switch(&self) -> bool {
+        matches!(self.kind, PartKind::Plate | PartKind::Lever)
+    }
+
+    /// Whether the face is one of the faces of the part
+    pub fn owns(&self, face: &Face) -> bool {
+        face.tag
+            .as_deref()
+            .and_then(|t| t.strip_prefix(self.name.as_str()))
+            .is_some_and(|side| side.starts_with('/'))
+    }
+
+    /// The faces of the part in its state, the top is walkable but for a lever
+    pub fn faces(&self) -> Vec<Face> {
+        let mut pillar = Pillar::new_upright(self.pos.clone(), self.size.clone());
+        if self.kind != PartKind::Lever {
+            pillar = pillar.with_walkable_top();
+        }
+        let mut faces = pillar.with_tag(&self.name).into_vec();
+        let on = self.state != 0;
+        match &self.kind {
+            // sunk by half of its height
+            PartKind::Plate if on => {
+                let down = self.size.y.abs() / 2.0;
+                move_all(&mut faces, |p| V3::from(p.x, p.y - down, p.z));
+            }
+            PartKind::Lever if on => {
+                for f in faces.iter_mut() {
+                    f.color = Colored::Pure(Self::ON);
+                }
+            }
+            PartKind::Platform { axis, pivot } => {
+                for _ in 0..self.state % 4 {
+                    move_all(&mut faces, |p| quarter(*axis, pivot, p));
+                }
+            }
+            PartKind::Bridge { reach } if on => {
+                // the corners on the far half along `reach`
+                let along = |p: &V3| p.x * reach.x + p.y * reach.y + p.z * reach.z;
+                let (lo, hi) = faces
+                    .iter()
+                    .flat_map(|f| [&f.pos11, &f.pos12, &f.pos21, &f.pos22])
+                    .map(along)
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), a| {
+                        (lo.min(a), hi.max(a))
+                    });
+                let mid = (lo + hi) / 2.0;
+                move_all(&mut faces, |p| {
+                    if along(p) > mid {
+                        V3::from(p.x + reach.x, p.y + reach.y, p.z + reach.z)
+                    } else {
+                        p.clone()
+                    }
+                });
+            }
+            _ => (),
+        }
+        faces
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Puzzle {
+    pub parts: Vec<Part>,
+    /// a switch and a part it moves, by their names
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub links: Vec<(String, String)>,
+}
+
+impl Puzzle {
+    pub fn with_part(mut self, part: Part) -> Self {
+        self.parts.push(part);
+        self
+    }
+    pub fn with_link(mut self, switch: &str, part: &str) -> Self {
+        self.links.push((switch.to_string(), part.to_string()));
+        self
+    }
+
+    pub fn part(&self, name: &str) -> Option<&Part> {
+        self.parts.iter().find(|p| p.name == name)
+    }
+
+    /// The faces of all the parts in their states
+    pub fn faces(&self) -> Vec<Face> {
+        self.parts.iter().flat_map(Part::faces).collect()
+    }
+
+    /// The states of the parts in their order, to be set again by `set_states`
+    pub fn states(&self) -> Vec<u32> {
+        self.parts.iter().map(|p| p.state).collect()
+    }
+    /// Set the states of the parts as they were, the linked parts are not moved.
+    ///
+    /// True if any of them changed.
+    pub fn set_states(&mut self, states: &[u32]) -> bool {
+        let mut changed = false;
+        for (part, state) in self.parts.iter_mut().zip(states) {
+            changed |= part.state != *state;
+            part.state = *state;
+        }
+        changed
+    }
+
+    /// The face is clicked, the lever it belongs to is switched
+    pub fn clicked(&mut self, face: &Face) -> Vec<PuzzleEvent> {
+        let lever = self
+            .parts
+            .iter()
+            .find(|p| p.kind == PartKind::Lever && p.owns(face));
+        match lever {
+            Some(lever) => {
+                let (name, state) = (lever.name.clone(), 1 - lever.state.min(1));
+                self.set(&name, state)
+            }
+            None => vec![],
+        }
+    }
+
+    /// The avatar stands on the face now, the plate under it is pressed and the others released
+    pub fn stood(&mut self, face: &Face) -> Vec<PuzzleEvent> {
+        let plates: Vec<_> = self
+            .parts
+            .iter()
+            .filter(|p| p.kind == PartKind::Plate)
+            .map(|p| (p.name.clone(), p.owns(face) as u32))
+            .collect();
+        plates
+            .into_iter()
+            .flat_map(|(name, state)| self.set(&name, state))
+            .collect()
+    }
+
+    /// Set the state of the part `name`, and move the parts linked to it if it is a switch.
+    ///
+    /// Nothing happens if it is in the state already.
+    pub fn set(&mut self, name: &str, state: u32) -> Vec<PuzzleEvent> {
+        let Some(part) = self.parts.iter_mut().find(|p| p.name == name) else {
+            return vec![];
+        };
+        let state = match part.kind {
+            PartKind::Platform { .. } => state % 4,
+            _ => state.min(1),
+        };
+        if part.state == state {
+            return vec![];
+        }
+        part.state = state;
+        let (name, on) = (part.name.clone(), state != 0);
+        let mut events = vec![match part.kind {
+            PartKind::Plate | PartKind::Lever => PuzzleEvent::Switched {
+                name: name.clone(),
+                on,
+            },
+            PartKind::Platform { .. } => PuzzleEvent::Turned {
+                name: name.clone(),
+                turns: state,
+            },
+            PartKind::Bridge { .. } => PuzzleEvent::Extended {
+                name: name.clone(),
+                extended: on,
+            },
+        }];
+        if !part.is_switch() {
+            return events;
+        }
+        let linked: Vec<_> = self
+            .links
+            .iter()
+            .filter(|(s, _)| *s == name)
+            .map(|(_, p)| p.clone())
+            .collect();
+        for target in linked {
+            let Some(t) = self.part(&target) else {
+                continue;
+            };
+            let next = match t.kind {
+                PartKind::Platform { .. } if on => t.state + 1,
+                PartKind::Platform { .. } => continue,
+                _ => on as u32,
+            };
+            events.append(&mut self.set(&target, next));
+        }
+        events
+    }
+}
+
+fn move_all(faces: &mut [Face], f: impl Fn(&V3) -> V3) {
+    for face in faces.iter_mut() {
+        face.pos11 = f(&face.pos11);
+        face.pos12 = f(&face.pos12);
+        face.pos21 = f(&face.pos21);
+        face.pos22 = f(&face.pos22);
+        face.pos_slice = None;
+    }
+}
+
+/// `p` turned a quarter about `axis` through `pivot`
+fn quarter(axis: Axis, pivot: &V3, p: &V3) -> V3 {
+    let (x, y, z) = (p.x - pivot.x, p.y - pivot.y, p.z - pivot.z);
+    let (x, y, z) = match axis {
+        Axis::X => (x, -z, y),
+        Axis::Y => (z, y, -x),
+        Axis::Z => (-y, x, z),
+    };
+    V3::from(x + pivot.x, y + pivot.y, z + pivot.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn puzzle() -> Puzzle {
+        let one = V3::from(1.0, 1.0, 1.0);
+        Puzzle::default()
+            .with_part(Part::plate("plate", V3::from(0.0, 0.0, 0.0), one.clone()))
+            .with_part(Part::lever("lever", V3::from(2.0, 0.0, 0.0), one.clone()))
+            .with_part(Part::bridge(
+                "bridge",
+                V3::from(4.0, 0.0, 0.0),
+                V3::from(2.0, 1.0, 1.0),
+                V3::from(3.0, 0.0, 0.0),
+            ))
+            .with_part(Part::platform(
+                "platform",
+                V3::from(0.0, 0.0, 4.0),
+                V3::from(2.0, 1.0, 1.0),
+                Axis::Y,
+                V3::from(0.0, 0.0, 4.0),
+            ))
+            .with_link("plate", "bridge")
+            .with_link("lever", "platform")
+            .with_link("lever", "plate")
+    }
+
+    #[test]
+    fn switches_move_their_parts() {
+        let mut puzzle = puzzle();
+        let faces = puzzle.faces();
+        assert_eq!(faces.len(), 24);
+        assert!(faces[2].walkable && !faces[8].walkable);
+
+        // on the top of the plate, the bridge goes out
+        assert_eq!(
+            puzzle.stood(&faces[2]),
+            vec![
+                PuzzleEvent::Switched {
+                    name: "plate".into(),
+                    on: true
+                },
+                PuzzleEvent::Extended {
+                    name: "bridge".into(),
+                    extended: true
+                },
+            ]
+        );
+        assert_eq!(puzzle.stood(&faces[2]), vec![]);
+        let bridge = puzzle.part("bridge").unwrap().faces();
+        // the right side moved, the left one did not
+        assert_eq!((bridge[0].pos11.x, bridge[1].pos11.x), (9.0, 4.0));
+        assert_eq!(puzzle.faces()[2].pos11.y, 0.5);
+
+        // the lever turns the platform, the plate is pressed already
+        let events = puzzle.clicked(&faces[8]);
+        assert_eq!(events.len(), 2);
+        assert_eq!(puzzle.part("platform").unwrap().state, 1);
+        assert_eq!(puzzle.faces()[8].color, Colored::Pure(Part::ON));
+        let top = &puzzle.part("platform").unwrap().faces()[2];
+        assert_eq!(top.tag.as_deref(), Some("platform/up"));
+        // from x 0..2 and z 4..5 to x 0..1 and z 2..4
+        let xs = [&top.pos11, &top.pos12, &top.pos21, &top.pos22].map(|p| p.x);
+        let zs = [&top.pos11, &top.pos12, &top.pos21, &top.pos22].map(|p| p.z);
+        assert_eq!(xs.iter().fold(f32::MIN, |a, b| a.max(*b)), 1.0);
+        assert_eq!(zs.iter().fold(f32::MAX, |a, b| a.min(*b)), 2.0);
+
+        // off the plate, the bridge follows it
+        assert_eq!(puzzle.stood(&faces[20]).len(), 2);
+        assert_eq!(puzzle.part("bridge").unwrap().state, 0);
+        // the lever off and on again turns the platform on, and presses the plate
+        assert_eq!(puzzle.clicked(&faces[8]).len(), 1);
+        assert_eq!(puzzle.clicked(&faces[8]).len(), 4);
+        assert_eq!(puzzle.part("platform").unwrap().state, 2);
+        assert_eq!(puzzle.set("platform", 6), vec![]);
+
+        let states = puzzle.states();
+        assert_eq!(states, [1, 1, 1, 2]);
+        assert!(puzzle.set_states(&[0, 0, 0, 0]));
+        assert!(!puzzle.set_states(&[0, 0, 0, 0]));
+        assert!(puzzle.set_states(&states));
+        assert_eq!(puzzle.states(), states);
+    }
+}
@@ -0,0 +1,138 @@
+//! A whole level as data, for the levels declared in a file instead of built as a library.
+//!
+//! ```json
+//! {
+//!     "blocks": [{ "pos": { "x": 0, "y": 0, "z": 0 }, "size": { "x": 4, "y": 1, "z": 2 }, "walkable": true }],
+//!     "markers": [{ "kind": "Start", "pos": { "x": 1, "y": 1, "z": 1 } }],
+//!     "puzzle": {
+//!         "parts": [{ "name": "lever", "kind": "Lever", "pos": ..., "size": ... }],
+//!         "links": [["lever", "bridge"]]
+//!     }
+//! }
+//! ```
+//! Every part of it may be left out, and `faces` holds faces of any other shape.
+//! The faces of the level are the faces, then the blocks, then the parts of the puzzle.
+
+use crate::{
+    marker::Marker,
+    puzzle::{Puzzle, PuzzleEvent},
+    Face, Pillar, V3,
+};
+
+/// A pillar as `Pillar::new_upright`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Block {
+    pub pos: V3,
+    pub size: V3,
+    /// the avatar may walk on its top
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub walkable: bool,
+    /// the faces are tagged `tag/side`, see `Pillar::with_tag`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tag: Option<String>,
+}
+
+impl Block {
+    pub fn faces(&self) -> Vec<Face> {
+        let mut pillar = Pillar::new_upright(self.pos.clone(), self.size.clone());
+        if self.walkable {
+            pillar = pillar.with_walkable_top();
+        }
+        if let Some(tag) = &self.tag {
+            pillar = pillar.with_tag(tag);
+        }
+        pillar.into_vec()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scene {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub faces: Vec<Face>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub blocks: Vec<Block>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub markers: Vec<Marker>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub puzzle: Puzzle,
+}
+
+impl Scene {
+    /// The faces of the level, with the parts of the puzzle in their states
+    pub fn get_faces(&self) -> Vec<Face> {
+        let mut faces = self.faces.clone();
+        faces.extend(self.blocks.iter().flat_map(Block::faces));
+        faces.append(&mut self.puzzle.faces());
+        faces
+    }
+
+    /// The face `id` of `get_faces()` is clicked
+    pub fn clicked(&mut self, id: usize) -> Vec<PuzzleEvent> {
+        match self.get_faces().get(id) {
+            Some(face) => self.puzzle.clicked(face),
+            None => vec![],
+        }
+    }
+
+    /// The avatar stands on the face `id` of `get_faces()` now
+    pub fn stood(&mut self, id: usize) -> Vec<PuzzleEvent> {
+        match self.get_faces().get(id) {
+            Some(face) => self.puzzle.stood(face),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::Part;
+
+    #[test]
+    fn parts_after_the_blocks() {
+        let one = V3::from(1.0, 1.0, 1.0);
+        let mut scene = Scene {
+            blocks: vec![Block {
+                pos: V3::from(0.0, -1.0, 0.0),
+                size: V3::from(4.0, 1.0, 1.0),
+                walkable: true,
+                tag: None,
+            }],
+            puzzle: Puzzle::default()
+                .with_part(Part::lever("lever", V3::from(0.0, 0.0, 0.0), one.clone()))
+                .with_part(Part::plate("plate", V3::from(2.0, 0.0, 0.0), one)),
+            ..Default::default()
+        };
+        let faces = scene.get_faces();
+        assert_eq!(faces.len(), 18);
+        assert!(faces[2].walkable);
+        assert_eq!(scene.clicked(2), vec![]);
+        assert_eq!(scene.clicked(6).len(), 1);
+        assert_eq!(scene.stood(14).len(), 1);
+        assert_eq!(scene.stood(99), vec![]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn declared_in_json() {
+        let scene: Scene = crate::serialize::from_json(
+            r#"{
+                "blocks": [{ "pos": { "x": 0, "y": 0, "z": 0 }, "size": { "x": 2, "y": 1, "z": 2 } }],
+                "puzzle": {
+                    "parts": [{
+                        "name": "bridge",
+                        "kind": { "Bridge": { "reach": { "x": 2, "y": 0, "z": 0 } } },
+                        "pos": { "x": 2, "y": 0, "z": 0 },
+                        "size": { "x": 1, "y": 1, "z": 1 }
+                    }]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(scene.get_faces().len(), 12);
+        assert!(!scene.get_faces()[2].walkable);
+        assert_eq!(scene.puzzle.part("bridge").unwrap().state, 0);
+    }
+}
@@ -0,0 +1,94 @@
+//! Saving and loading the geometry, enabled by the `serde` feature.
+//!
+//! Every item can be written to json, ron or a compact binary form.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|err| err.to_string())
+}
+pub fn from_json<T: DeserializeOwned>(s: &str) -> Result<T, String> {
+    serde_json::from_str(s).map_err(|err| err.to_string())
+}
+
+pub fn to_ron<T: Serialize>(value: &T) -> Result<String, String> {
+    ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+}
+pub fn from_ron<T: DeserializeOwned>(s: &str) -> Result<T, String> {
+    ron::from_str(s).map_err(|err| err.to_string())
+}
+
+/// The compact binary form, it is not readable but small and fast,
+/// good for sending the scene between processes
+pub fn to_binary<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    bincode::serde::encode_to_vec(value, bincode::config::standard()).map_err(|err| err.to_string())
+}
+pub fn from_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+        .map(|(value, _)| value)
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ColorFun, Colored, Face, Pillar, V3};
+
+    fn scene() -> Vec<Face> {
+        let mut faces = Pillar::new_upright(V3::from(-6.0, -2.0, -1.0), V3::from(12.0, 2.0, 2.0))
+            .with_w(0.5)
+            .with_skipped_filter(false, true, false, true, true, true)
+            .into_vec();
+        faces[0].color = Colored::Vertex(vec![Color::from(1.0, 0.0, 0.0, 1.0); 4]);
+        faces[1].color = Colored::Fun(ColorFun::Hsv {
+            hue: 30.0,
+            step: 90.0,
+            saturation: 0.5,
+            value: 0.8,
+            alpha: 1.0,
+        });
+        faces
+    }
+
+    fn assert_same(l: &[Face], r: &[Face]) {
+        assert_eq!(l.len(), r.len());
+        for (l, r) in l.iter().zip(r) {
+            assert_eq!(l.get_pos_slice(), r.get_pos_slice());
+            assert_eq!(l.color, r.color);
+            assert_eq!(l.index, r.index);
+            assert_eq!(l.skipped, r.skipped);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let faces = scene();
+        assert_same(
+            &faces,
+            &from_json::<Vec<Face>>(&to_json(&faces).unwrap()).unwrap(),
+        );
+        assert_same(
+            &faces,
+            &from_ron::<Vec<Face>>(&to_ron(&faces).unwrap()).unwrap(),
+        );
+        assert_same(
+            &faces,
+            &from_binary::<Vec<Face>>(&to_binary(&faces).unwrap()).unwrap(),
+        );
+    }
+
+    #[test]
+    fn color_fun_round_trip() {
+        let fun = Colored::Fun(ColorFun::Angled {
+            base: Color::from(0.5, 0.2, 0.8, 1.0),
+            tint: Color::from(0.8, 0.2, 0.5, 1.0),
+            facing: 0.3,
+        });
+        assert_eq!(fun, from_ron::<Colored>(&to_ron(&fun).unwrap()).unwrap());
+        assert_eq!(
+            fun,
+            from_binary::<Colored>(&to_binary(&fun).unwrap()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! The flat lighting and the outlines of the faces, done on the cpu the same as `b_faces.vs` and `b_faces.fs`.
+//!
+//! Only the faces that are `lit` are shaded, by the angle between their normal and the light.
+//! A face has two sides, the one facing the viewer is lit.
+//! The outlines are drawn along the four edges of every face, their width is in pixels.
+
+use crate::{Color, Projection, V3};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Light {
+    /// where the light comes from, it turns with the camera, not with the scene.
+    /// The axes are those of the world at the angle 0 without pitch
+    pub direction: V3,
+    /// the brightness of a face turned away from the light, from 0 to 1
+    pub ambient: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            direction: V3::from(-0.3, 1.0, 0.5),
+            ambient: 0.3,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outline {
+    /// in pixels
+    pub width: f32,
+    /// premultiplied like the colors of the faces
+    pub color: Color,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            width: 1.5,
+            color: Color::from(0.0, 0.0, 0.0, 1.0),
+        }
+    }
+}
+
+/// How the faces are shaded, nothing by default
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Shading {
+    pub light: Option<Light>,
+    pub outline: Option<Outline>,
+}
+
+impl Shading {
+    /// `u_light` and `u_ambient` of the shaders, zero when there is no light
+    pub fn light_uniforms(&self) -> ([f32; 3], f32) {
+        match &self.light {
+            Some(l) => ([l.direction.x, l.direction.y, l.direction.z], l.ambient),
+            None => ([0.0; 3], 1.0),
+        }
+    }
+    /// `u_outline_color` and `u_outline_width` of the shaders, the width is 0 when there is no outline
+    pub fn outline_uniforms(&self) -> ([f32; 4], f32) {
+        match &self.outline {
+            Some(o) => (o.color.as_slice4(), o.width),
+            None => ([0.0; 4], 0.0),
+        }
+    }
+}
+
+/// How bright a face with the `normal` is under the light, from `ambient` to 1
+pub fn light_factor(proj: &Projection, normal: &V3, light: &Light) -> f32 {
+    let m = proj.proj_mat();
+    let n = [
+        m[0] * normal.x + m[3] * normal.y + m[6] * normal.z,
+        m[1] * normal.x + m[4] * normal.y + m[7] * normal.z,
+        m[2] * normal.x + m[5] * normal.y + m[8] * normal.z,
+    ];
+    let l = [light.direction.x, light.direction.y, light.direction.z];
+    let len = |v: &[f32; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    let (n_len, l_len) = (len(&n), len(&l));
+    if n_len < f32::EPSILON || l_len < f32::EPSILON {
+        return 1.0;
+    }
+    // the side facing the viewer, the depth grows towards it
+    let pj = proj.mode.pj();
+    let toward = [pj[0][2], pj[1][2], pj[2][2]];
+    let facing = n[0] * toward[0] + n[1] * toward[1] + n[2] * toward[2];
+    let sign = if facing < 0.0 { -1.0 } else { 1.0 };
+    let dot = sign * (n[0] * l[0] + n[1] * l[1] + n[2] * l[2]) / n_len / l_len;
+    light.ambient + (1.0 - light.ambient) * dot.max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lights_the_side_facing_the_viewer() {
+        let proj = Projection::new(0.0, 0.5, 1.0);
+        let light = Light {
+            direction: V3::from(0.0, 1.0, 0.0),
+            ambient: 0.25,
+        };
+        let up = V3::from(0.0, 1.0, 0.0);
+        let down = V3::from(0.0, -1.0, 0.0);
+        // the top is seen from above, both normals of it are the same
+        assert!((light_factor(&proj, &up, &light) - 1.0).abs() < 1e-6);
+        assert!((light_factor(&proj, &down, &light) - 1.0).abs() < 1e-6);
+        let side = V3::from(1.0, 0.0, 0.0);
+        assert!((light_factor(&proj, &side, &light) - 0.25).abs() < 1e-6);
+        // turning the scene by 90 degrees does not move the light
+        let turned = Projection::new(90_f32.to_radians(), 0.5, 1.0);
+        let front = V3::from(0.0, 0.0, 1.0);
+        assert!((light_factor(&turned, &front, &light) - 0.25).abs() < 1e-6);
+        assert_eq!(Shading::default().light_uniforms(), ([0.0; 3], 1.0));
+    }
+}
@@ -0,0 +1,138 @@
+//! The look of a chapter, the background behind the faces and the colors of the buttons.
+//!
+//! A group of levels sets it in `levels.json`, the groups in it inherit it,
+//! and a level may replace it by its `LevelStyle`.
+//! The colors here are not premultiplied, unlike the colors of the faces.
+
+use crate::Color;
+
+/// What is drawn behind the faces of a level
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
+    /// the color of the panel
+    #[default]
+    None,
+    Plain(Color),
+    /// from the top of the view to the bottom
+    Gradient {
+        top: Color,
+        bottom: Color,
+    },
+    /// a plane of lines under the level at the height `y`, it turns with the camera
+    Grid {
+        back: Color,
+        line: Color,
+        y: f32,
+        /// between two lines, in the units of the world
+        spacing: f32,
+    },
+    /// dots drifting slowly over the view
+    Dots {
+        back: Color,
+        dot: Color,
+        /// between two dots, in points
+        spacing: f32,
+        /// in points per second
+        speed: f32,
+    },
+}
+
+impl Background {
+    /// The color the background is filled with, before its lines and dots
+    pub fn fill(&self) -> Option<&Color> {
+        match self {
+            Self::None => None,
+            Self::Plain(c) => Some(c),
+            Self::Gradient { top, .. } => Some(top),
+            Self::Grid { back, .. } | Self::Dots { back, .. } => Some(back),
+        }
+    }
+}
+
+/// The colors of the buttons, None keeps the colors of the button itself
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    /// the tint of the image of a button
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub button: Option<Color>,
+    /// the tint of the image of a button under the pointer
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hovered: Option<Color>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub text: Option<Color>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub background: Background,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub palette: Palette,
+}
+
+impl Color {
+    /// `#rrggbb` or `#rrggbbaa`, the `#` may be left out
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| {
+            let v = match hex.get(i * 2..i * 2 + 2) {
+                Some(s) => u8::from_str_radix(s, 16).ok()?,
+                None => 255,
+            };
+            Some(v as f32 / 255.0)
+        };
+        Some(Self::from(
+            channel(0)?,
+            channel(1)?,
+            channel(2)?,
+            channel(3)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_from_hex() {
+        assert_eq!(
+            Color::from_hex("#ff0000"),
+            Some(Color::from(1.0, 0.0, 0.0, 1.0))
+        );
+        assert_eq!(
+            Color::from_hex("00ff0000"),
+            Some(Color::from(0.0, 1.0, 0.0, 0.0))
+        );
+        assert_eq!(Color::from_hex("#fff"), None);
+        assert_eq!(Color::from_hex("#gg0000"), None);
+        assert_eq!(Background::default().fill(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn themes_in_json() {
+        let theme = Theme {
+            background: Background::Gradient {
+                top: Color::from(0.1, 0.2, 0.3, 1.0),
+                bottom: Color::from(0.0, 0.0, 0.0, 1.0),
+            },
+            palette: Palette {
+                text: Some(Color::from(1.0, 1.0, 1.0, 1.0)),
+                ..Default::default()
+            },
+        };
+        let json = crate::serialize::to_json(&theme).unwrap();
+        assert_eq!(crate::serialize::from_json::<Theme>(&json), Ok(theme));
+        assert_eq!(
+            crate::serialize::from_json::<Theme>("{}"),
+            Ok(Theme::default())
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Walking on the faces as they are seen, like in Monument Valley.
+//!
+//! The `walkable` faces are where the avatar can stand. Two of them are joined when an edge
+//! of one touches an edge of the other on the screen, whatever their depth,
+//! so the avatar walks across the joins of an impossible figure the way they look.
+//! The joins depend on the projection, the graph is built again when it turns.
+//! The way between two faces is found by A* with the distances on the screen.
+
+use std::collections::{BTreeMap, BinaryHeap};
+
+use crate::{Face, Projection, V3};
+
+/// A join from a walkable face to another one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    /// the face it goes to
+    pub to: usize,
+    /// where it leaves the face, on its edge in world space
+    pub from_pos: V3,
+    /// where it enters the other face, the same point as `from_pos` on the screen
+    pub to_pos: V3,
+    /// the way on the screen from the middle of the face to the middle of the other one,
+    /// through the join
+    pub cost: f32,
+}
+
+/// A point on a walkable face
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// the position of the face in the list
+    pub face: usize,
+    pub pos: V3,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WalkGraph {
+    /// the links of every walkable face, by the position of the face in the list
+    links: BTreeMap<usize, Vec<Link>>,
+    /// the middle of every walkable face in view space
+    centres: BTreeMap<usize, (f32, f32)>,
+}
+
+impl WalkGraph {
+    /// how far apart two edges may be on the screen and still touch, in units of the world
+    pub const TOLERANCE: f32 = 0.05;
+    /// how much of the shorter edge two touching edges must share
+    pub const MIN_OVERLAP: f32 = 0.25;
+
+    /// The walkable faces which are not skipped, joined as they are seen through `proj`.
+    ///
+    /// Faces seen edge-on are left out, and a join cut off by the musks of either face is not made.
+    pub fn new(faces: &[Face], proj: &Projection) -> Self {
+        let tolerance = Self::TOLERANCE * proj.scale;
+        // the faces with their corners in world and view space
+        let outlines: Vec<_> = faces
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.walkable && !f.skipped)
+            .filter_map(|(id, f)| {
+                let world = corners(f);
+                let view = world.clone().map(|p| {
+                    let v = proj.view(&p);
+                    (v.x, v.y)
+                });
+                (area(&view).abs() > tolerance * tolerance).then_some((id, world, view))
+            })
+            .collect();
+        let centres: BTreeMap<usize, (f32, f32)> = outlines
+            .iter()
+            .map(|(id, _, view)| (*id, middle(view)))
+            .collect();
+        let mut links: BTreeMap<usize, Vec<Link>> =
+            outlines.iter().map(|(id, ..)| (*id, vec![])).collect();
+        for (a, (id_a, world_a, view_a)) in outlines.iter().enumerate() {
+            for (id_b, world_b, view_b) in outlines.iter().skip(a + 1) {
+                let Some((pos_a, pos_b, at)) =
+                    touching(world_a, view_a, world_b, view_b, tolerance)
+                else {
+                    continue;
+                };
+                if !through(&faces[*id_a], proj, view_a, at)
+                    || !through(&faces[*id_b], proj, view_b, at)
+                {
+                    continue;
+                }
+                let (ca, cb) = (centres[id_a], centres[id_b]);
+                let cost = (at.0 - ca.0).hypot(at.1 - ca.1) + (at.0 - cb.0).hypot(at.1 - cb.1);
+                links.entry(*id_a).or_default().push(Link {
+                    to: *id_b,
+                    from_pos: pos_a.clone(),
+                    to_pos: pos_b.clone(),
+                    cost,
+                });
+                links.entry(*id_b).or_default().push(Link {
+                    to: *id_a,
+                    from_pos: pos_b,
+                    to_pos: pos_a,
+                    cost,
+                });
+            }
+        }
+        Self { links, centres }
+    }
+
+    /// Whether the face `id` can be stood on
+    pub fn contains(&self, id: usize) -> bool {
+        self.links.contains_key(&id)
+    }
+    /// The walkable faces in the order of the list
+    pub fn faces(&self) -> impl Iterator<Item = usize> + '_ {
+        self.links.keys().copied()
+    }
+    /// The joins from the face `id`, empty if it is not walkable
+    pub fn links(&self, id: usize) -> &[Link] {
+        self.links.get(&id).map_or(&[], |l| l.as_slice())
+    }
+    pub fn link(&self, from: usize, to: usize) -> Option<&Link> {
+        self.links(from).iter().find(|l| l.to == to)
+    }
+
+    /// The faces from `from` to `to` along the shortest way on the screen, both of them included.
+    ///
+    /// It is A* over the joins, with the straight line on the screen to `to` as the heuristic.
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let goal = *self.centres.get(&to)?;
+        if !self.contains(from) {
+            return None;
+        }
+        let h = |id: usize| {
+            let c = self.centres[&id];
+            (c.0 - goal.0).hypot(c.1 - goal.1)
+        };
+        let mut cost = BTreeMap::from([(from, 0.0)]);
+        let mut came_from = BTreeMap::new();
+        let mut open = BinaryHeap::from([Open {
+            f: h(from),
+            id: from,
+        }]);
+        while let Some(Open { id, .. }) = open.pop() {
+            if id == to {
+                let mut path = vec![to];
+                while let Some(prev) = came_from.get(path.last()?) {
+                    path.push(*prev);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let g = cost[&id];
+            for link in self.links(id) {
+                let g = g + link.cost;
+                if cost.get(&link.to).is_some_and(|c| *c <= g) {
+                    continue;
+                }
+                cost.insert(link.to, g);
+                came_from.insert(link.to, id);
+                open.push(Open {
+                    f: g + h(link.to),
+                    id: link.to,
+                });
+            }
+        }
+        None
+    }
+
+    /// The points to walk through from `from` to `to`, `to` is the last one.
+    ///
+    /// Crossing a join is two steps, from the edge of one face to the edge of the other.
+    pub fn steps(&self, from: &Step, to: &Step) -> Option<Vec<Step>> {
+        let route = self.path(from.face, to.face)?;
+        let mut steps = vec![];
+        for pair in route.windows(2) {
+            let link = self.link(pair[0], pair[1])?;
+            steps.push(Step {
+                face: pair[0],
+                pos: link.from_pos.clone(),
+            });
+            steps.push(Step {
+                face: pair[1],
+                pos: link.to_pos.clone(),
+            });
+        }
+        steps.push(to.clone());
+        Some(steps)
+    }
+}
+
+/// A face to go on from in `WalkGraph::path`, the one with the least `f` first
+struct Open {
+    /// the cost to it and the heuristic from it
+    f: f32,
+    id: usize,
+}
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+impl Eq for Open {}
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // `BinaryHeap` pops the greatest
+        other.f.total_cmp(&self.f).then(other.id.cmp(&self.id))
+    }
+}
+
+/// The middle of a face
+pub fn centre(face: &Face) -> V3 {
+    let c = corners(face);
+    V3::from(
+        c.iter().map(|p| p.x).sum::<f32>() / 4.0,
+        c.iter().map(|p| p.y).sum::<f32>() / 4.0,
+        c.iter().map(|p| p.z).sum::<f32>() / 4.0,
+    )
+}
+
+/// Where `pos` is on the face, along `pos11 -> pos12` and `pos11 -> pos21`, both from 0 to 1
+pub fn param(face: &Face, pos: &V3) -> (f32, f32) {
+    let o = &face.pos11;
+    let u = sub(&face.pos12, o);
+    let v = sub(&face.pos21, o);
+    let p = sub(pos, o);
+    // least squares, in case the point is not quite on the plane
+    let (uu, uv, vv) = (dot(&u, &u), dot(&u, &v), dot(&v, &v));
+    let (pu, pv) = (dot(&p, &u), dot(&p, &v));
+    let det = uu * vv - uv * uv;
+    if det.abs() < f32::EPSILON {
+        return (0.5, 0.5);
+    }
+    ((pu * vv - pv * uv) / det, (pv * uu - pu * uv) / det)
+}
+
+/// The point of the face at `param`
+pub fn point(face: &Face, (s, t): (f32, f32)) -> V3 {
+    let o = &face.pos11;
+    let u = sub(&face.pos12, o);
+    let v = sub(&face.pos21, o);
+    V3::from(
+        o.x + s * u.x + t * v.x,
+        o.y + s * u.y + t * v.y,
+        o.z + s * u.z + t * v.z,
+    )
+}
+
+fn sub(a: &V3, b: &V3) -> V3 {
+    V3::from(a.x - b.x, a.y - b.y, a.z - b.z)
+}
+fn dot(a: &V3, b: &V3) -> f32 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+fn lerp(a: &V3, b: &V3, t: f32) -> V3 {
+    V3::from(
+        a.x + (b.x - a.x) * t,
+        a.y + (b.y - a.y) * t,
+        a.z + (b.z - a.z) * t,
+    )
+}
+
+/// The corners of a face going round it
+fn corners(face: &Face) -> [V3; 4] {
+    [
+        face.pos11.clone(),
+        face.pos12.clone(),
+        face.pos22.clone(),
+        face.pos21.clone(),
+    ]
+}
+
+fn area(p: &[(f32, f32); 4]) -> f32 {
+    (0..4)
+        .map(|i| {
+            let (a, b) = (p[i], p[(i + 1) % 4]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f32>()
+        / 2.0
+}
+
+/// The edges of two faces sharing the longest part on the screen,
+/// the middle of that part on both faces in world space, and on the screen
+fn touching(
+    world_a: &[V3; 4],
+    view_a: &[(f32, f32); 4],
+    world_b: &[V3; 4],
+    view_b: &[(f32, f32); 4],
+    tolerance: f32,
+) -> Option<(V3, V3, (f32, f32))> {
+    let mut best: Option<(f32, V3, V3, (f32, f32))> = None;
+    for i in 0..4 {
+        let (a0, a1) = (view_a[i], view_a[(i + 1) % 4]);
+        let d = (a1.0 - a0.0, a1.1 - a0.1);
+        let len = d.0.hypot(d.1);
+        if len <= tolerance {
+            continue;
+        }
+        for j in 0..4 {
+            let (b0, b1) = (view_b[j], view_b[(j + 1) % 4]);
+            let off = |p: (f32, f32)| (d.0 * (p.1 - a0.1) - d.1 * (p.0 - a0.0)).abs() / len;
+            if off(b0) > tolerance || off(b1) > tolerance {
+                continue;
+            }
+            let along = |p: (f32, f32)| (d.0 * (p.0 - a0.0) + d.1 * (p.1 - a0.1)) / (len * len);
+            let (t0, t1) = (along(b0), along(b1));
+            let (lo, hi) = (t0.min(t1).max(0.0), t0.max(t1).min(1.0));
+            let overlap = (hi - lo) * len;
+            let len_b = (b1.0 - b0.0).hypot(b1.1 - b0.1);
+            if overlap <= tolerance || overlap < WalkGraph::MIN_OVERLAP * len.min(len_b) {
+                continue;
+            }
+            if best.as_ref().is_some_and(|b| b.0 >= overlap) {
+                continue;
+            }
+            let t = (lo + hi) / 2.0;
+            let at = (a0.0 + d.0 * t, a0.1 + d.1 * t);
+            let db = (b1.0 - b0.0, b1.1 - b0.1);
+            let s = ((at.0 - b0.0) * db.0 + (at.1 - b0.1) * db.1) / (len_b * len_b);
+            best = Some((
+                overlap,
+                lerp(&world_a[i], &world_a[(i + 1) % 4], t),
+                lerp(&world_b[j], &world_b[(j + 1) % 4], s.clamp(0.0, 1.0)),
+                at,
+            ));
+        }
+    }
+    best.map(|(_, a, b, at)| (a, b, at))
+}
+
+fn middle(view: &[(f32, f32); 4]) -> (f32, f32) {
+    (
+        view.iter().map(|p| p.0).sum::<f32>() / 4.0,
+        view.iter().map(|p| p.1).sum::<f32>() / 4.0,
+    )
+}
+
+/// Whether the face is seen at `at` on its edge, tested a little inside it
+fn through(face: &Face, proj: &Projection, view: &[(f32, f32); 4], at: (f32, f32)) -> bool {
+    let c = middle(view);
+    let (x, y) = (at.0 + (c.0 - at.0) * 0.1, at.1 + (c.1 - at.1) * 0.1);
+    proj.through_musks(&face.musks, x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pillar;
+
+    /// the top of a 2 x 2 pillar with its corner at `(x, y, z)`
+    fn top(x: f32, y: f32, z: f32) -> Vec<Face> {
+        Pillar::new_upright(V3::from(x, y - 1.0, z), V3::from(2.0, 1.0, 2.0))
+            .with_walkable_top()
+            .into_vec()
+    }
+
+    #[test]
+    fn joins_what_touches_on_the_screen() {
+        // (1, 1, 1) goes straight into the screen in the isometric projection,
+        // so the second top looks like it is next to the first one
+        let mut faces = top(0.0, 0.0, 0.0);
+        faces.append(&mut top(4.0, 2.0, 2.0));
+        faces.append(&mut top(0.0, 0.0, 2.0));
+        // the faces are right, left, up, down, front, back
+        let (a, b, c) = (2, 8, 14);
+        let proj = Projection::new(0.0, 0.1, 1.0);
+        let graph = WalkGraph::new(&faces, &proj);
+        assert_eq!(graph.faces().collect::<Vec<_>>(), vec![a, b, c]);
+
+        let link = graph.link(a, b).unwrap();
+        assert!((link.from_pos.x - 2.0).abs() < 1e-4 && link.from_pos.y.abs() < 1e-4);
+        assert!((link.to_pos.x - 4.0).abs() < 1e-4 && (link.to_pos.y - 2.0).abs() < 1e-4);
+        assert_eq!(graph.link(b, a).unwrap().to_pos, link.from_pos);
+        // next to each other in the world too
+        assert!(graph.link(a, c).is_some());
+        assert_eq!(graph.path(c, b), Some(vec![c, a, b]));
+
+        let to = Step {
+            face: b,
+            pos: centre(&faces[b]),
+        };
+        let from = Step {
+            face: c,
+            pos: centre(&faces[c]),
+        };
+        let steps = graph.steps(&from, &to).unwrap();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps.last(), Some(&to));
+
+        // turned away, the illusion is gone
+        let graph = WalkGraph::new(&faces, &Projection::new(0.3, 0.1, 1.0));
+        assert!(graph.link(a, b).is_none());
+        assert!(graph.link(a, c).is_some());
+        assert_eq!(graph.path(c, b), None);
+    }
+
+    #[test]
+    fn shortest_way_on_the_screen() {
+        // three tops in a row, and a long one along all of them
+        let mut faces = top(0.0, 0.0, 0.0);
+        faces.append(&mut top(2.0, 0.0, 0.0));
+        faces.append(&mut top(4.0, 0.0, 0.0));
+        faces.append(
+            &mut Pillar::new_upright(V3::from(0.0, -1.0, 2.0), V3::from(6.0, 1.0, 2.0))
+                .with_walkable_top()
+                .into_vec(),
+        );
+        let (a, b, c, d) = (2, 8, 14, 20);
+        let graph = WalkGraph::new(&faces, &Projection::new(0.0, 0.1, 1.0));
+        assert_eq!(graph.links(d).len(), 3);
+        assert_eq!(graph.path(a, c), Some(vec![a, b, c]));
+        assert_eq!(graph.path(c, c), Some(vec![c]));
+        assert_eq!(graph.path(a, 0), None);
+        // 2 along the x axis, which is as long on the screen, at the scale of 0.1
+        assert!((graph.link(a, b).unwrap().cost - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn params_on_a_face() {
+        let face = &top(0.0, 1.0, 0.0)[2];
+        let p = point(face, (0.25, 0.5));
+        assert_eq!(p, V3::from(0.5, 1.0, 1.0));
+        let (s, t) = param(face, &p);
+        assert!((s - 0.25).abs() < 1e-6 && (t - 0.5).abs() < 1e-6);
+        assert_eq!(centre(face), V3::from(1.0, 1.0, 1.0));
+    }
+}
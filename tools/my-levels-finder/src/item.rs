@@ -5,8 +5,9 @@ use std::{
 };
 
 use json::JsonValue;
+use my_items::theme::Theme;
 
-use crate::{get_json, js_obj_arr, js_obj_num, js_obj_str};
+use crate::{get_json, js_obj_arr, js_obj_num, js_obj_str, theme::get_theme};
 
 static mut INDEX_ID_MAX: i64 = -1;
 lazy_static! {
@@ -65,6 +66,8 @@ pub struct Group {
     pub levels: Vec<Level>,
     pub groups: Vec<Group>,
     pub linkers: Vec<Linker>,
+    /// None to use the theme of the group it is in
+    pub theme: Option<Theme>,
 }
 
 #[derive(Debug)]
@@ -110,6 +113,7 @@ impl FromJson for Group {
             levels,
             groups,
             linkers,
+            theme: get_theme(j),
         }
     }
 }
@@ -168,14 +172,16 @@ impl Group {
             i.find();
         }
     }
-    pub fn collect(&self, collections: &mut CollectedGame) {
+    /// `theme` is the theme of the group it is in
+    pub fn collect(&self, collections: &mut CollectedGame, theme: &Theme) {
         let group_index = self.index;
-        collections.new_group(group_index, &self.name);
+        let theme = self.theme.as_ref().unwrap_or(theme);
+        collections.new_group(group_index, &self.name, theme);
         for l in &self.levels {
             collections.new_level(group_index, l.index, &l.name, l.filename.with(""));
         }
         for g in &self.groups {
-            g.collect(collections);
+            g.collect(collections, theme);
         }
     }
 }
@@ -186,7 +192,7 @@ impl Game {
     }
     pub fn collect(&self) -> CollectedGame {
         let mut collection = CollectedGame::new(&self.0.name);
-        self.0.collect(&mut collection);
+        self.0.collect(&mut collection, &Theme::default());
         collection.clean();
         collection
     }
@@ -201,6 +207,7 @@ pub struct CollectedLevel {
 pub struct CollectedGroup {
     pub name: String,
     pub levels: HashMap<i64, CollectedLevel>,
+    pub theme: Theme,
 }
 #[derive(Debug, Clone)]
 pub struct CollectedGame {
@@ -225,6 +232,7 @@ impl CollectedGroup {
         Self {
             name: name.clone(),
             levels: HashMap::new(),
+            theme: Theme::default(),
         }
     }
 }
@@ -235,12 +243,13 @@ impl CollectedGame {
             groups: HashMap::new(),
         }
     }
-    fn new_group(&mut self, group_index: i64, name: &String) {
-        if let Some(g) = self.groups.get_mut(&group_index) {
-            g.name = name.clone();
-        } else {
-            self.groups.insert(group_index, CollectedGroup::new(name));
-        }
+    fn new_group(&mut self, group_index: i64, name: &String, theme: &Theme) {
+        let g = self
+            .groups
+            .entry(group_index)
+            .or_insert_with(|| CollectedGroup::new(name));
+        g.name = name.clone();
+        g.theme = theme.clone();
     }
     fn new_level(&mut self, group_index: i64, index: i64, name: &String, link: Link) {
         if let Some(g) = self.groups.get_mut(&group_index) {
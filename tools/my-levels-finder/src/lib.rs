@@ -2,6 +2,7 @@
 extern crate lazy_static; // 1.4.0
 #[allow(dead_code)]
 mod item;
+mod theme;
 
 use std::fs;
 
@@ -29,6 +30,13 @@ pub fn js_num(j: &JsonValue, default: i64) -> i64 {
         default
     }
 }
+pub fn js_f32(j: &JsonValue, default: f32) -> f32 {
+    if let JsonValue::Number(s) = j {
+        f32::from(*s)
+    } else {
+        default
+    }
+}
 pub fn js_obj_str<'a>(j: &'a JsonValue, key: &str, default: &'a str) -> &'a str {
     if let JsonValue::Object(o) = j {
         if let Some(j) = o.get(key) {
@@ -45,6 +53,14 @@ pub fn js_obj_num(j: &JsonValue, key: &str, default: i64) -> i64 {
     }
     default
 }
+pub fn js_obj_f32(j: &JsonValue, key: &str, default: f32) -> f32 {
+    if let JsonValue::Object(o) = j {
+        if let Some(j) = o.get(key) {
+            return js_f32(j, default);
+        }
+    }
+    default
+}
 pub fn js_obj_arr<'a>(j: &'a JsonValue, key: &str) -> Option<&'a Vec<JsonValue>> {
     if let JsonValue::Object(o) = j {
         if let JsonValue::Array(arr) = o.get(key)? {
@@ -55,6 +71,7 @@ pub fn js_obj_arr<'a>(j: &'a JsonValue, key: &str) -> Option<&'a Vec<JsonValue>>
 }
 
 pub use item::CollectedGame;
+pub use item::CollectedGroup;
 pub use item::Link;
 pub fn get_levels(path: Link, filename: &str) -> CollectedGame {
     let j = get_json(&path.with(filename).path());
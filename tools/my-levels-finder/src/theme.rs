@@ -0,0 +1,111 @@
+//! The `theme` of a group in `levels.json`, the groups in it without one use it too.
+//!
+//! ```json
+//! "theme": {
+//!     "background": { "kind": "gradient", "top": "#1d2b53", "bottom": "#0b0f1e" },
+//!     "palette": { "button": "#ffd0a0", "hovered": "#ffffff", "text": "#402000" }
+//! }
+//! ```
+//! The kinds of background are `none`, `plain` with a `color`, `gradient` with `top` and `bottom`,
+//! `grid` with a `color`, a `line` color, the height `y` and the `spacing` of the lines,
+//! and `dots` with a `color`, a `dot` color, the `spacing` and the `speed` of the dots.
+//!
+//! A color is `#rrggbb`, `#rrggbbaa` or an array of 3 or 4 numbers from 0 to 1.
+//! What is missing or broken is left as the default.
+
+use json::JsonValue;
+use my_items::{
+    theme::{Background, Palette, Theme},
+    Color,
+};
+
+use crate::{js_f32, js_obj_f32, js_obj_str, js_str};
+
+/// The theme of the group `j`, None if it has none
+pub fn get_theme(j: &JsonValue) -> Option<Theme> {
+    let JsonValue::Object(o) = j else {
+        return None;
+    };
+    let theme = o.get("theme")?;
+    Some(Theme {
+        background: get_background(&theme["background"]),
+        palette: get_palette(&theme["palette"]),
+    })
+}
+
+fn get_color(j: &JsonValue) -> Option<Color> {
+    match j {
+        JsonValue::Array(a) if a.len() == 3 || a.len() == 4 => {
+            let c = |i: usize| a.get(i).map(|j| js_f32(j, 1.0)).unwrap_or(1.0);
+            Some(Color::from(c(0), c(1), c(2), c(3)))
+        }
+        _ => Color::from_hex(js_str(j, "")),
+    }
+}
+
+fn get_background(j: &JsonValue) -> Background {
+    let color = |key: &str, default: Color| get_color(&j[key]).unwrap_or(default);
+    let black = Color::from(0.0, 0.0, 0.0, 1.0);
+    let gray = Color::from(0.5, 0.5, 0.5, 1.0);
+    match js_obj_str(j, "kind", "none") {
+        "plain" => Background::Plain(color("color", black)),
+        "gradient" => Background::Gradient {
+            top: color("top", gray),
+            bottom: color("bottom", black),
+        },
+        "grid" => Background::Grid {
+            back: color("color", black),
+            line: color("line", gray),
+            y: js_obj_f32(j, "y", 0.0),
+            spacing: js_obj_f32(j, "spacing", 1.0).max(0.1),
+        },
+        "dots" => Background::Dots {
+            back: color("color", black),
+            dot: color("dot", gray),
+            spacing: js_obj_f32(j, "spacing", 40.0).max(16.0),
+            speed: js_obj_f32(j, "speed", 8.0),
+        },
+        _ => Background::None,
+    }
+}
+
+fn get_palette(j: &JsonValue) -> Palette {
+    Palette {
+        button: get_color(&j["button"]),
+        hovered: get_color(&j["hovered"]),
+        text: get_color(&j["text"]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_theme() {
+        let j = json::parse(
+            r##"{
+                "name": "Group1",
+                "theme": {
+                    "background": { "kind": "grid", "line": [1, 0, 0], "spacing": 2 },
+                    "palette": { "button": "#ff000080", "text": "broken" }
+                }
+            }"##,
+        )
+        .unwrap();
+        let theme = get_theme(&j).unwrap();
+        assert_eq!(
+            theme.background,
+            Background::Grid {
+                back: Color::from(0.0, 0.0, 0.0, 1.0),
+                line: Color::from(1.0, 0.0, 0.0, 1.0),
+                y: 0.0,
+                spacing: 2.0,
+            }
+        );
+        assert_eq!(theme.palette.button.unwrap().a, 128.0 / 255.0);
+        assert_eq!(theme.palette.hovered, None);
+        assert_eq!(theme.palette.text, None);
+        assert_eq!(get_theme(&json::parse(r#"{"name": "a"}"#).unwrap()), None);
+    }
+}
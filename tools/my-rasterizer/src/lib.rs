@@ -0,0 +1,415 @@
+//! Drawing faces and lines without a gpu.
+//!
+//! The result is the same as `GLFacesView` and `GLLinesView` painting into the egui frame:
+//! - the projection, `index` offset and depth test (`LEQUAL`) of `b_faces.vs` and `b_lines.vs`
+//! - the order of the faces, the translucent ones sorted from the back, see `my_items::order`
+//! - the musks of `b_faces.fs` and `b_lines.fs`, cutting off the left side of their lines
+//! - a face is drawn as the 4 triangles of `FACE_TRIANGLES`, so every pixel is covered twice,
+//!   with the alpha `a / (2 - a)`
+//! - the light of `b_faces.vs` on the lit faces and the outlines of `b_faces.fs`,
+//!   their `fwidth` is exact as a triangle is flat on the screen
+//! - the blending of egui, `ONE, ONE_MINUS_SRC_ALPHA` for colors
+//!   and `ONE_MINUS_DST_ALPHA, ONE` for alpha
+//!
+//! There is no multisampling, the pixels are sampled at their centers.
+
+use image::{Rgba, RgbaImage};
+use my_items::{
+    order::draw_order,
+    pick::{barycentric, FACE_TRIANGLES},
+    projection::face_alpha,
+    shading::{light_factor, Outline, Shading},
+    Color, Face, Line, Projection, V3,
+};
+
+pub struct Rasterizer {
+    width: u32,
+    height: u32,
+    /// rgba of every pixel, row by row from the top
+    color: Vec<[f32; 4]>,
+    /// depth of every pixel in clip space, cleared for every draw like the views do
+    depth: Vec<f32>,
+    musk_enabled: bool,
+    shading: Shading,
+}
+
+impl Rasterizer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width * height) as usize;
+        Self {
+            width,
+            height,
+            color: vec![[0.0; 4]; len],
+            depth: vec![1.0; len],
+            musk_enabled: true,
+            shading: Shading::default(),
+        }
+    }
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.clear(color);
+        self
+    }
+    /// the same as `GLFacesView::set_musk_enabled`
+    pub fn with_musk_enabled(mut self, musk: bool) -> Self {
+        self.musk_enabled = musk;
+        self
+    }
+    /// the same as `GlPaintOptions::shading`
+    pub fn with_shading(mut self, shading: Shading) -> Self {
+        self.shading = shading;
+        self
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    /// The aspect ratio to put into the projection
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.color.fill(color.as_slice4());
+    }
+
+    /// Draw the faces like `GLFacesView::paint`, in the order of `draw_order`
+    pub fn draw_faces(&mut self, faces: &[Face], proj: &Projection) {
+        self.depth.fill(1.0);
+        let outline = self.shading.outline.clone();
+        for f in draw_order(faces, proj).into_iter().map(|id| &faces[id]) {
+            let pos = [&f.pos11, &f.pos12, &f.pos21, &f.pos22];
+            let clip = pos.map(|p| proj.face_clip(p, f.index));
+            let light = match &self.shading.light {
+                Some(light) if f.lit => light_factor(proj, &f.normal(), light),
+                _ => 1.0,
+            };
+            let color = [0, 1, 2, 3].map(|i| {
+                let c = f.color.get_at(i, proj.angle);
+                Color::from(c.r * light, c.g * light, c.b * light, face_alpha(c.a))
+            });
+            let uv = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+            for t in FACE_TRIANGLES {
+                self.triangle(
+                    t.map(|i| &clip[i]),
+                    t.map(|i| &color[i]),
+                    t.map(|i| uv[i]),
+                    outline.as_ref(),
+                    proj,
+                    &f.musks,
+                );
+            }
+        }
+    }
+
+    /// Draw the lines like `GLLinesView::paint`, they are 1 pixel wide and opaque
+    pub fn draw_lines(&mut self, lines: &[Line], proj: &Projection) {
+        self.depth.fill(1.0);
+        for l in lines {
+            let (a, b) = (proj.line_clip(&l.pos1), proj.line_clip(&l.pos2));
+            let (c1, c2) = (l.color.get_at(0, proj.angle), l.color.get_at(3, proj.angle));
+            let (ax, ay) = self.to_pixel(a.x, a.y);
+            let (bx, by) = self.to_pixel(b.x, b.y);
+            let steps = (bx - ax).abs().max((by - ay).abs()).ceil().max(1.0) as usize;
+            for i in 0..=steps {
+                let t = i as f32 / steps as f32;
+                let (x, y) = (ax + (bx - ax) * t, ay + (by - ay) * t);
+                if x < 0.0 || y < 0.0 {
+                    continue;
+                }
+                let c = c1.mix(&c2, t);
+                let z = a.z + (b.z - a.z) * t;
+                let color = Color::from(c.r, c.g, c.b, 1.0);
+                self.fragment(x as u32, y as u32, z, &color, proj, &l.msks);
+            }
+        }
+    }
+
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            let c = self.color[(y * self.width + x) as usize];
+            Rgba(c.map(|v| (v * 255.0).round() as u8))
+        })
+    }
+
+    /// clip space to pixels, not rounded
+    fn to_pixel(&self, x: f32, y: f32) -> (f32, f32) {
+        Projection::clip_to_pixel(x, y, self.width as f32, self.height as f32)
+    }
+
+    fn triangle(
+        &mut self,
+        v: [&V3; 3],
+        c: [&Color; 3],
+        uv: [[f32; 2]; 3],
+        outline: Option<&Outline>,
+        proj: &Projection,
+        musks: &[my_items::Musk],
+    ) {
+        let px = v.map(|v| self.to_pixel(v.x, v.y));
+        // fwidth of the uv, |d/dx| + |d/dy|
+        let (ex, ey) = (
+            (px[1].0 - px[0].0, px[1].1 - px[0].1),
+            (px[2].0 - px[0].0, px[2].1 - px[0].1),
+        );
+        let det = ex.0 * ey.1 - ey.0 * ex.1;
+        let fwidth = [0, 1].map(|k| {
+            let (da, db) = (uv[1][k] - uv[0][k], uv[2][k] - uv[0][k]);
+            ((da * ey.1 - db * ex.1) / det).abs() + ((ex.0 * db - ey.0 * da) / det).abs()
+        });
+        // the bounding box in pixels
+        let min = |f: fn(&(f32, f32)) -> f32| px.iter().map(f).fold(f32::MAX, f32::min);
+        let max = |f: fn(&(f32, f32)) -> f32| px.iter().map(f).fold(f32::MIN, f32::max);
+        let (x0, y0) = (min(|p| p.0).max(0.0) as u32, min(|p| p.1).max(0.0) as u32);
+        let (x1, y1) = (max(|p| p.0).ceil().max(0.0), max(|p| p.1).ceil().max(0.0));
+        for y in y0..(y1 as u32).min(self.height) {
+            for x in x0..(x1 as u32).min(self.width) {
+                let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+                let Some(l) = barycentric(px[0], px[1], px[2], cx, cy) else {
+                    continue;
+                };
+                let z = l[0] * v[0].z + l[1] * v[1].z + l[2] * v[2].z;
+                let mut color = Color::from(
+                    l[0] * c[0].r + l[1] * c[1].r + l[2] * c[2].r,
+                    l[0] * c[0].g + l[1] * c[1].g + l[2] * c[2].g,
+                    l[0] * c[0].b + l[1] * c[1].b + l[2] * c[2].b,
+                    l[0] * c[0].a + l[1] * c[1].a + l[2] * c[2].a,
+                );
+                if let Some(o) = outline {
+                    // how many pixels away from the nearest edge
+                    let edge = [0, 1]
+                        .map(|k| {
+                            let t = l[0] * uv[0][k] + l[1] * uv[1][k] + l[2] * uv[2][k];
+                            t.min(1.0 - t) / fwidth[k]
+                        })
+                        .into_iter()
+                        .fold(f32::MAX, f32::min);
+                    if edge < o.width {
+                        let c = &o.color;
+                        color = Color::from(c.r, c.g, c.b, face_alpha(c.a));
+                    }
+                }
+                self.fragment(x, y, z, &color, proj, musks);
+            }
+        }
+    }
+
+    /// The fragment shader, depth test and blending of one pixel
+    fn fragment(
+        &mut self,
+        x: u32,
+        y: u32,
+        z: f32,
+        c: &Color,
+        proj: &Projection,
+        musks: &[my_items::Musk],
+    ) {
+        if x >= self.width || y >= self.height || z.abs() > 1.0 {
+            return;
+        }
+        let i = (y * self.width + x) as usize;
+        if z > self.depth[i] {
+            return;
+        }
+        if self.musk_enabled {
+            let (cx, cy) = Projection::pixel_to_clip(
+                x as f32 + 0.5,
+                y as f32 + 0.5,
+                self.width as f32,
+                self.height as f32,
+            );
+            let (vx, vy) = proj.clip_to_view(cx, cy);
+            if !proj.through_musks(musks, vx, vy) {
+                return;
+            }
+        }
+        self.depth[i] = z;
+        let d = &mut self.color[i];
+        *d = [
+            (c.r + d[0] * (1.0 - c.a)).clamp(0.0, 1.0),
+            (c.g + d[1] * (1.0 - c.a)).clamp(0.0, 1.0),
+            (c.b + d[2] * (1.0 - c.a)).clamp(0.0, 1.0),
+            (c.a * (1.0 - d[3]) + d[3]).clamp(0.0, 1.0),
+        ];
+    }
+}
+
+/// Faces with lines over them in a `width` x `height` image, the aspect ratio of `proj` is replaced
+pub fn render(
+    faces: &[Face],
+    lines: &[Line],
+    proj: &Projection,
+    width: u32,
+    height: u32,
+    background: Color,
+) -> RgbaImage {
+    let mut r = Rasterizer::new(width, height).with_background(background);
+    let proj = Projection {
+        aspect_ratio: r.aspect_ratio(),
+        ..proj.clone()
+    };
+    r.draw_faces(faces, &proj);
+    r.draw_lines(lines, &proj);
+    r.to_image()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use my_items::{Colored, Musk};
+
+    const BLACK: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+
+    /// a square around `(c, c, c)`, covering the center of the screen
+    fn square(c: f32, color: Color, index: f32) -> Face {
+        Face::new(
+            V3::from(c - 1.0, c - 1.0, c),
+            V3::from(c + 1.0, c - 1.0, c),
+            V3::from(c - 1.0, c + 1.0, c),
+            V3::from(c + 1.0, c + 1.0, c),
+        )
+        .with_color(Colored::Pure(color))
+        .with_w(index)
+    }
+
+    fn center(img: &RgbaImage) -> [u8; 4] {
+        img.get_pixel(img.width() / 2, img.height() / 2).0
+    }
+
+    #[test]
+    fn background_only() {
+        let img = render(&[], &[], &Projection::default(), 8, 6, BLACK);
+        assert!(img.pixels().all(|p| p.0 == [0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn opaque_face_and_depth() {
+        let red = Color::from(1.0, 0.0, 0.0, 1.0);
+        let green = Color::from(0.0, 1.0, 0.0, 1.0);
+        let proj = Projection::new(0.0, 0.5, 1.0);
+        let img = render(
+            &[
+                square(-1.0, red.clone(), 0.0),
+                square(1.0, green.clone(), 0.0),
+            ],
+            &[],
+            &proj,
+            32,
+            32,
+            BLACK,
+        );
+        assert_eq!(center(&img), [0, 255, 0, 255]);
+        assert_eq!(img.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        // the index brings the face to the front
+        let img = render(
+            &[square(-1.0, red, 0.5), square(1.0, green, 0.0)],
+            &[],
+            &proj,
+            32,
+            32,
+            BLACK,
+        );
+        assert_eq!(center(&img), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn translucent_face_is_drawn_twice() {
+        let proj = Projection::new(0.0, 0.5, 1.0);
+        let face = square(0.0, Color::from(0.2, 0.2, 0.2, 0.5), 0.0);
+        let img = render(&[face], &[], &proj, 32, 32, BLACK);
+        // a = 1/3, 0.2 + (0.2 + 0) * 2/3
+        let v: f32 = (0.2 + 0.2 * (2.0 / 3.0)) * 255.0;
+        assert_eq!(center(&img)[0], v.round() as u8);
+    }
+
+    #[test]
+    fn translucent_faces_are_sorted() {
+        let proj = Projection::new(0.0, 0.5, 1.0);
+        let glass = |c: f32, color| square(c, Color::from(color, 0.0, 0.0, 0.5), 0.0);
+        // the same image whichever of the two is given first
+        let img = render(
+            &[glass(1.0, 0.5), glass(-1.0, 0.2)],
+            &[],
+            &proj,
+            32,
+            32,
+            BLACK,
+        );
+        let swapped = render(
+            &[glass(-1.0, 0.2), glass(1.0, 0.5)],
+            &[],
+            &proj,
+            32,
+            32,
+            BLACK,
+        );
+        assert_eq!(center(&img), center(&swapped));
+        // the front one is blended last, a = 1/3
+        let back: f32 = 0.2 + 0.2 * (2.0 / 3.0);
+        let v: f32 = (0.5 + 0.5 * (2.0 / 3.0) + back * (2.0 / 3.0) * (2.0 / 3.0)) * 255.0;
+        assert_eq!(center(&img)[0], v.round() as u8);
+    }
+
+    #[test]
+    fn musks_and_lines() {
+        let white = Color::from(1.0, 1.0, 1.0, 1.0);
+        let proj = Projection::new(0.0, 0.5, 1.0);
+        // cut off the upper half
+        let face =
+            square(0.0, white.clone(), 0.0).with_musk(Musk::new_on_screen(0.0, 0.0, 1.0, 0.0));
+        let img = render(std::slice::from_ref(&face), &[], &proj, 32, 32, BLACK);
+        assert_eq!(img.get_pixel(16, 18).0, [255, 255, 255, 255]);
+        assert_eq!(img.get_pixel(16, 13).0, [0, 0, 0, 255]);
+
+        let mut r = Rasterizer::new(32, 32)
+            .with_background(BLACK)
+            .with_musk_enabled(false);
+        r.draw_faces(&[face], &proj);
+        assert_eq!(r.to_image().get_pixel(16, 13).0, [255, 255, 255, 255]);
+
+        // lines are drawn over the faces
+        let mut line = Line::default_with(0.0, -4.0, 0.0, 0.0, 4.0, 0.0);
+        line.color = Colored::Pure(Color::from(0.0, 0.0, 1.0, 1.0));
+        r.draw_lines(&[line], &proj);
+        assert_eq!(r.to_image().get_pixel(16, 16).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn light_and_outlines() {
+        use my_items::shading::Light;
+
+        let white = Color::from(1.0, 1.0, 1.0, 1.0);
+        let proj = Projection::new(0.0, 0.5, 1.0);
+        let face = square(0.0, white, 0.0).with_lit(true);
+        let shading = Shading {
+            light: Some(Light {
+                direction: V3::from(0.0, 1.0, 0.0),
+                ambient: 0.2,
+            }),
+            outline: Some(Outline {
+                width: 2.0,
+                color: Color::from(1.0, 0.0, 0.0, 1.0),
+            }),
+        };
+        let mut r = Rasterizer::new(32, 32)
+            .with_background(BLACK)
+            .with_shading(shading);
+        r.draw_faces(std::slice::from_ref(&face), &proj);
+        let img = r.to_image();
+        // the square faces the z axis, away from the light
+        assert_eq!(center(&img), [51, 51, 51, 255]);
+        // the left edge of the square is 2 pixels wide from x = 9
+        assert_eq!(img.get_pixel(8, 16).0, [0, 0, 0, 255]);
+        assert_eq!(img.get_pixel(10, 16).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(11, 16).0, [51, 51, 51, 255]);
+        // without the light it keeps its color
+        let img = render(&[face], &[], &proj, 32, 32, BLACK);
+        assert_eq!(center(&img), [255, 255, 255, 255]);
+    }
+}